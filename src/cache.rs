@@ -0,0 +1,310 @@
+use crate::dictionary::DictionaryData;
+use crate::hunspell::CompoundRules;
+use crate::language::{Language, LanguageManager};
+use crate::wordlist::WordList;
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+// File layout: magic | format version (u32 LE) | header length (u64 LE) |
+// JSON header | FST bytes running to the end of the file
+const MAGIC: &[u8; 8] = b"ATOMDICT";
+// Version 2 stores word frequencies as FST values
+const CACHE_VERSION: u32 = 2;
+const PREAMBLE_LEN: usize = MAGIC.len() + 4 + 8;
+
+/// Outcome of compiling the cache for one language
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheStatus {
+    UpToDate(PathBuf),
+    Built(PathBuf),
+    NoSources,
+}
+
+// Identifies one source file as it was when the cache was built
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SourceStamp {
+    path: PathBuf,
+    len: u64,
+    modified_ns: u64,
+    hash: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheHeader {
+    language: Language,
+    sources: Vec<SourceStamp>,
+    no_suggest_words: HashSet<String>,
+    forbidden_words: HashSet<String>,
+    compound_rules: CompoundRules,
+}
+
+pub fn cache_dir() -> PathBuf {
+    let mut path = LanguageManager::system_dict_dir();
+    path.push("cache");
+    path
+}
+
+pub fn cache_path(language: &Language) -> PathBuf {
+    cache_dir().join(format!("{}.dict", language.code()))
+}
+
+/// Map the compiled dictionary for `language` if it was built from `sources`
+/// as they are now. Missing, stale or corrupt caches simply return `None`.
+pub(crate) fn load(language: &Language, sources: &[PathBuf]) -> Option<DictionaryData> {
+    load_from(&cache_path(language), language, sources)
+}
+
+/// Write the compiled dictionary for `language`, replacing any existing cache
+pub(crate) fn store(language: &Language, sources: &[PathBuf], data: &DictionaryData) -> anyhow::Result<PathBuf> {
+    store_at(&cache_path(language), language, sources, data)
+}
+
+fn load_from(path: &Path, language: &Language, sources: &[PathBuf]) -> Option<DictionaryData> {
+    let file = File::open(path).ok()?;
+    
+    // SAFETY: cache files are only ever replaced by renaming a new file over
+    // them, never rewritten in place, so the mapping cannot change under us.
+    let mmap = Arc::new(unsafe { Mmap::map(&file) }.ok()?);
+    
+    let (header, words_start) = read_header(&mmap)?;
+    if header.language != *language {
+        return None;
+    }
+    
+    let touched = check_sources(&header.sources, sources)?;
+    let words = WordList::from_mapped(Arc::clone(&mmap), words_start..mmap.len()).ok()?;
+    
+    let data = DictionaryData {
+        words,
+        no_suggest_words: header.no_suggest_words,
+        forbidden_words: header.forbidden_words,
+        compound_rules: header.compound_rules,
+    };
+    
+    // Sources whose mtime changed but whose contents didn't get re-stamped so
+    // the next start can skip hashing them again
+    if touched {
+        store_at(path, language, sources, &data).ok();
+    }
+    
+    Some(data)
+}
+
+fn store_at(path: &Path, language: &Language, sources: &[PathBuf], data: &DictionaryData) -> anyhow::Result<PathBuf> {
+    let stamps = sources
+        .iter()
+        .map(|path| stamp(path))
+        .collect::<io::Result<Vec<_>>>()?;
+    
+    let header = CacheHeader {
+        language: *language,
+        sources: stamps,
+        no_suggest_words: data.no_suggest_words.clone(),
+        forbidden_words: data.forbidden_words.clone(),
+        compound_rules: data.compound_rules.clone(),
+    };
+    let header_bytes = serde_json::to_vec(&header)?;
+    
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp_path = path.with_extension(format!("dict.{}.tmp", std::process::id()));
+    
+    let written = (|| -> io::Result<()> {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(MAGIC)?;
+        file.write_all(&CACHE_VERSION.to_le_bytes())?;
+        file.write_all(&(header_bytes.len() as u64).to_le_bytes())?;
+        file.write_all(&header_bytes)?;
+        file.write_all(data.words.as_bytes())?;
+        file.sync_all()?;
+        // Other processes may still have the old cache mapped, which is
+        // fine: the rename leaves their copy intact
+        fs::rename(&tmp_path, path)
+    })();
+    
+    if let Err(e) = written {
+        fs::remove_file(&tmp_path).ok();
+        return Err(e.into());
+    }
+    
+    Ok(path.to_path_buf())
+}
+
+fn read_header(bytes: &[u8]) -> Option<(CacheHeader, usize)> {
+    if bytes.len() < PREAMBLE_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return None;
+    }
+    
+    let version = u32::from_le_bytes(bytes[MAGIC.len()..MAGIC.len() + 4].try_into().ok()?);
+    if version != CACHE_VERSION {
+        return None;
+    }
+    
+    let header_len = u64::from_le_bytes(bytes[MAGIC.len() + 4..PREAMBLE_LEN].try_into().ok()?);
+    let header_end = PREAMBLE_LEN.checked_add(usize::try_from(header_len).ok()?)?;
+    if header_end > bytes.len() {
+        return None;
+    }
+    
+    let header = serde_json::from_slice(&bytes[PREAMBLE_LEN..header_end]).ok()?;
+    Some((header, header_end))
+}
+
+// Returns `None` if any source changed, otherwise whether any source was
+// touched (new mtime, same contents)
+fn check_sources(recorded: &[SourceStamp], sources: &[PathBuf]) -> Option<bool> {
+    if recorded.len() != sources.len() {
+        return None;
+    }
+    
+    let mut touched = false;
+    for (stamp, path) in recorded.iter().zip(sources) {
+        let path = fs::canonicalize(path).ok()?;
+        if stamp.path != path {
+            return None;
+        }
+        
+        let metadata = fs::metadata(&path).ok()?;
+        if metadata.len() != stamp.len {
+            return None;
+        }
+        
+        if modified_ns(&metadata) != stamp.modified_ns {
+            if hash_file(&path).ok()? != stamp.hash {
+                return None;
+            }
+            touched = true;
+        }
+    }
+    
+    Some(touched)
+}
+
+fn stamp(path: &Path) -> io::Result<SourceStamp> {
+    let path = fs::canonicalize(path)?;
+    let metadata = fs::metadata(&path)?;
+    
+    Ok(SourceStamp {
+        len: metadata.len(),
+        modified_ns: modified_ns(&metadata),
+        hash: hash_file(&path)?,
+        path,
+    })
+}
+
+fn modified_ns(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+fn hash_file(path: &Path) -> io::Result<u64> {
+    Ok(xxhash_rust::xxh3::xxh3_64(&fs::read(path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+    
+    // A scratch directory holding one source word list and the cache path
+    fn scratch(name: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("spellchecker-cache-{}-{}", std::process::id(), name));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("words.txt");
+        fs::write(&source, "house\t5\nmouse\t2\n").unwrap();
+        (source, dir.join("en.dict"))
+    }
+    
+    fn data() -> DictionaryData {
+        DictionaryData {
+            words: WordList::from_counts([("house", 5), ("mouse", 2), ("geëet", 0)]).unwrap(),
+            no_suggest_words: HashSet::from(["mouse".to_string()]),
+            ..DictionaryData::default()
+        }
+    }
+    
+    fn set_modified(path: &Path, time: SystemTime) {
+        File::options().write(true).open(path).unwrap().set_modified(time).unwrap();
+    }
+    
+    #[test]
+    fn round_trip_keeps_words_and_frequencies() {
+        let (source, path) = scratch("round-trip");
+        let sources = [source];
+        store_at(&path, &Language::English, &sources, &data()).unwrap();
+        
+        let loaded = load_from(&path, &Language::English, &sources).unwrap();
+        assert_eq!(loaded.words.counts(), data().words.counts());
+        assert_eq!(loaded.words.frequency("house"), 5);
+        assert_eq!(loaded.no_suggest_words, data().no_suggest_words);
+        assert!(load_from(&path, &Language::Afrikaans, &sources).is_none());
+        fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+    
+    #[test]
+    fn changed_sources_make_the_cache_stale() {
+        let (source, path) = scratch("stale");
+        let sources = [source.clone()];
+        let built = SystemTime::now() - Duration::from_secs(60);
+        set_modified(&source, built);
+        store_at(&path, &Language::English, &sources, &data()).unwrap();
+        
+        // Touched but unchanged: still valid, and re-stamped with the new mtime
+        set_modified(&source, built + Duration::from_secs(10));
+        assert!(load_from(&path, &Language::English, &sources).is_some());
+        let (header, _) = read_header(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(header.sources, [stamp(&source).unwrap()]);
+        
+        // Same length and a new mtime, but different contents
+        fs::write(&source, "house\t5\nlouse\t2\n").unwrap();
+        assert!(load_from(&path, &Language::English, &sources).is_none());
+        
+        // Rebuilt for the new contents, then the length changes
+        store_at(&path, &Language::English, &sources, &data()).unwrap();
+        assert!(load_from(&path, &Language::English, &sources).is_some());
+        let modified = fs::metadata(&source).unwrap().modified().unwrap();
+        fs::write(&source, "house\t5\nlouse\t2\nmoose\n").unwrap();
+        set_modified(&source, modified);
+        assert!(load_from(&path, &Language::English, &sources).is_none());
+        
+        // A different list of sources
+        assert!(load_from(&path, &Language::English, &[]).is_none());
+        fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+    
+    #[test]
+    fn truncated_or_corrupt_caches_are_ignored() {
+        let (source, path) = scratch("corrupt");
+        let sources = [source];
+        store_at(&path, &Language::English, &sources, &data()).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        
+        for len in 0..bytes.len() {
+            fs::write(&path, &bytes[..len]).unwrap();
+            assert!(load_from(&path, &Language::English, &sources).is_none(), "truncated to {}", len);
+        }
+        
+        for at in 0..bytes.len() {
+            let mut corrupt = bytes.clone();
+            corrupt[at] ^= 0x55;
+            fs::write(&path, &corrupt).unwrap();
+            if let Some(loaded) = load_from(&path, &Language::English, &sources) {
+                // Only bytes the loader never reads may change undetected
+                assert_eq!(loaded.words.counts(), data().words.counts(), "byte {} changed", at);
+            }
+        }
+        fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
@@ -1,29 +1,64 @@
+use crate::compound::CompoundPart;
 use crate::dictionary::{Dictionary, DictionaryManager};
+use crate::gettext;
+use crate::grammar::RuleEngine;
+use crate::keyboard::EditCosts;
+use crate::policy::CheckPolicy;
+use crate::prose::{self, FrontEnd};
+use crate::sentence::SentenceSegmenter;
+use crate::stream::{MarkedLanguage, StreamCheck, MAX_WHOLE_FILE_BYTES};
+use crate::tokenizer::{identifier_parts, is_cjk_char, Token, TokenKind, Tokenizer, UnicodeTokenizer};
 use crate::language::Language;
 use crate::util::{sanitize_word, is_valid_word, is_code_file, is_likely_code};
 use dashmap::DashMap;
 use rayon::prelude::*;
 use serde::Serialize;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io::{BufRead, Read, Write};
+use std::ops::Range;
+use std::str::FromStr;
 use std::path::Path;
 use std::sync::Arc;
 
+// Sounding alike halves the cost of a candidate's spelling difference
+const PHONETIC_DISCOUNT: f32 = 0.5;
+// Weights of the parts of a suggestion's score; they add up to 1
+const DISTANCE_WEIGHT: f32 = 0.6;
+const FREQUENCY_WEIGHT: f32 = 0.25;
+const ACCEPTANCE_WEIGHT: f32 = 0.15;
+// Confidence given to a word whose only fix is putting its accents back
+const ACCENT_CONFIDENCE: f32 = 0.9;
+// Documents are checked in chunks of this many lines, in parallel once
+// there is more than one
+const CHUNK_LINES: usize = 256;
+const PARALLEL_MIN_LINES: usize = CHUNK_LINES + 1;
+
 #[derive(Debug, Clone, Serialize)]
 pub struct WordCheck {
     pub word: String,
     pub original: String,
     pub start: usize,
     pub end: usize,
+    /// Byte offset of the word from the start of the document
+    pub offset: usize,
     pub is_correct: bool,
-    pub suggestions: Vec<String>,
+    /// Not counted towards the totals (numbers, known acronyms, the word being typed, ...)
+    #[serde(skip)]
+    pub skipped: bool,
+    pub suggestions: Vec<Suggestion>,
     pub line: usize,
     pub column: usize,
     pub confidence: f32,
     pub word_type: WordType,
+    /// How the word was split when accepted as a closed compound
+    pub compound: Option<Vec<CompoundPart>>,
+    /// The whole identifier when this is one part of it ("recieve" in
+    /// "recieveMessage"); suggestions then replace the whole identifier
+    pub identifier: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
 pub enum WordType {
     Normal,
     CodeIdentifier,
@@ -33,6 +68,108 @@ pub enum WordType {
     Number,
     Symbol,
     ShortWord,
+    /// "Dr.", "e.g."
+    Abbreviation,
+}
+
+impl WordType {
+    pub const ALL: [WordType; 9] = [
+        WordType::Normal,
+        WordType::ProperNoun,
+        WordType::Acronym,
+        WordType::CodeIdentifier,
+        WordType::TechnicalTerm,
+        WordType::Number,
+        WordType::Symbol,
+        WordType::ShortWord,
+        WordType::Abbreviation,
+    ];
+    
+    /// Name used on the command line ("proper-noun")
+    pub fn name(&self) -> &'static str {
+        match self {
+            WordType::Normal => "normal",
+            WordType::ProperNoun => "proper-noun",
+            WordType::Acronym => "acronym",
+            WordType::CodeIdentifier => "code-identifier",
+            WordType::TechnicalTerm => "technical-term",
+            WordType::Number => "number",
+            WordType::Symbol => "symbol",
+            WordType::ShortWord => "short-word",
+            WordType::Abbreviation => "abbreviation",
+        }
+    }
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            WordType::Normal => "Words",
+            WordType::ProperNoun => "Proper nouns",
+            WordType::Acronym => "Acronyms",
+            WordType::CodeIdentifier => "Code identifiers",
+            WordType::TechnicalTerm => "Technical terms",
+            WordType::Number => "Numbers",
+            WordType::Symbol => "Symbols",
+            WordType::ShortWord => "Short words",
+            WordType::Abbreviation => "Abbreviations",
+        }
+    }
+}
+
+impl FromStr for WordType {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let name = s.trim().to_lowercase().replace('_', "-");
+        WordType::ALL
+            .into_iter()
+            .find(|word_type| word_type.name() == name)
+            .ok_or_else(|| anyhow::anyhow!("unknown word type '{}'", s))
+    }
+}
+
+/// A replacement offered for a misspelled word
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Suggestion {
+    pub word: String,
+    pub strategy: SuggestionStrategy,
+    /// How good a replacement this is, from 0 to 1, combining spelling
+    /// distance, word frequency and how often the user has picked it before
+    pub score: f32,
+}
+
+/// How a suggestion was found
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Hash)]
+pub enum SuggestionStrategy {
+    /// A few (keyboard-weighted) edits away from the misspelling
+    EditDistance,
+    /// Spelled differently but pronounced alike ("fonetik" -> "phonetic")
+    Phonetic,
+    /// The only dictionary word that matches once accents are put back
+    /// ("ete" -> "été")
+    Accent,
+}
+
+/// What kind of problem a `Diagnostic` reports
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Hash)]
+pub enum DiagnosticKind {
+    Grammar,
+    /// A correctly spelled word that is probably the wrong one in context
+    RealWord,
+}
+
+/// A problem found by a rule that looks beyond single words
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub rule_id: &'static str,
+    pub kind: DiagnosticKind,
+    pub message: String,
+    /// The flagged text
+    pub text: String,
+    pub line: usize,
+    pub column: usize,
+    /// Byte range in the document that `replacement` replaces
+    pub range: Range<usize>,
+    pub replacement: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -41,6 +178,7 @@ pub struct DocumentAnalysis {
     pub misspelled_words: usize,
     pub accuracy: f32,
     pub words: Vec<WordCheck>,
+    pub diagnostics: Vec<Diagnostic>,
     pub suggestions_count: usize,
     pub language: Language,
     pub lines_checked: usize,
@@ -48,6 +186,115 @@ pub struct DocumentAnalysis {
     pub likely_code: bool,
     pub file_type: Option<String>,
     pub unique_words: usize,
+    /// For files with a front end, a hash of each line's prose, so an edit
+    /// only re-checks the lines whose prose changed
+    #[serde(skip)]
+    pub prose_lines: Vec<u64>,
+}
+
+/// The lines `SpellChecker::recheck_edit` checked again: lines
+/// `first..=old_last` of the previous document are now `first..=new_last`,
+/// and the lines after them moved by the difference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecheckedLines {
+    pub first: usize,
+    pub old_last: usize,
+    pub new_last: usize,
+}
+
+/// A single replacement in a previously checked document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentEdit {
+    /// Byte range of the old text that was replaced
+    pub range: Range<usize>,
+    /// Byte length of the text that replaced it
+    pub new_len: usize,
+    /// Line breaks in the replaced text
+    pub removed_lines: usize,
+    /// Line breaks in the replacement text
+    pub inserted_lines: usize,
+}
+
+impl DocumentEdit {
+    pub fn new(previous: &str, range: Range<usize>, replacement: &str) -> Self {
+        Self {
+            removed_lines: count_newlines(&previous[range.clone()]),
+            inserted_lines: count_newlines(replacement),
+            new_len: replacement.len(),
+            range,
+        }
+    }
+    
+    /// The smallest single edit turning `previous` into `current`, if they differ
+    pub fn between(previous: &str, current: &str) -> Option<Self> {
+        if previous == current {
+            return None;
+        }
+        
+        let mut prefix = previous.bytes()
+            .zip(current.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !previous.is_char_boundary(prefix) || !current.is_char_boundary(prefix) {
+            prefix -= 1;
+        }
+        
+        let max_suffix = previous.len().min(current.len()) - prefix;
+        let mut suffix = previous.bytes().rev()
+            .zip(current.bytes().rev())
+            .take(max_suffix)
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !previous.is_char_boundary(previous.len() - suffix)
+            || !current.is_char_boundary(current.len() - suffix)
+        {
+            suffix -= 1;
+        }
+        
+        let replacement = &current[prefix..current.len() - suffix];
+        Some(Self::new(previous, prefix..previous.len() - suffix, replacement))
+    }
+    
+    /// Line number (1-based) the edit starts on; `text` may be the old or new document
+    pub fn first_line(&self, text: &str) -> usize {
+        text.as_bytes()[..self.range.start.min(text.len())].iter().filter(|&&b| b == b'\n').count() + 1
+    }
+    
+    /// An edit that changes nothing but marks the line containing `offset` for re-checking
+    pub fn touch(offset: usize) -> Self {
+        Self {
+            range: offset..offset,
+            new_len: 0,
+            removed_lines: 0,
+            inserted_lines: 0,
+        }
+    }
+}
+
+// Per-document settings shared by every line being checked
+#[derive(Clone, Copy)]
+pub(crate) struct LineContext<'a> {
+    dictionary: &'a Dictionary,
+    // Sorted ranges of the text written in another language, with that
+    // language's dictionary if one is installed
+    pub(crate) languages: &'a [(Range<usize>, Option<Arc<Dictionary>>)],
+    tokenizer: &'a dyn Tokenizer,
+    // The whole document, for looking back across line breaks
+    text: &'a str,
+    is_code: bool,
+}
+
+impl<'a> LineContext<'a> {
+    // The context for a word starting at `offset`, with the dictionary of
+    // its language; None when that language has no dictionary
+    fn at(&self, offset: usize) -> Option<LineContext<'a>> {
+        let i = self.languages.partition_point(|(range, _)| range.end <= offset);
+        let dictionary = match self.languages.get(i).filter(|(range, _)| range.start <= offset) {
+            Some((_, dictionary)) => dictionary.as_deref()?,
+            None => self.dictionary,
+        };
+        Some(LineContext { dictionary, ..*self })
+    }
 }
 
 pub struct SpellChecker {
@@ -55,13 +302,23 @@ pub struct SpellChecker {
     current_language: Language,
     suggestions_enabled: bool,
     case_sensitive: bool,
-    max_suggestions: usize,
+    // Accept words typed without their accents ("ete" for "été")
+    accent_lenient: bool,
+    policy: CheckPolicy,
     cache: Arc<DashMap<String, bool>>,
     ignore_list: HashSet<String>,
     user_dictionary: HashSet<String>,
     proper_nouns: HashSet<String>,
-    acronyms: HashSet<String>,
-    confidence_threshold: f32,
+    // How many times each word has been picked from the suggestions
+    accepted_suggestions: HashMap<String, u32>,
+    grammar_enabled: bool,
+    grammar_rules: RuleEngine,
+    segmenter: SentenceSegmenter,
+    edit_costs: EditCosts,
+    tokenizer: Arc<dyn Tokenizer>,
+    // Pool used for parallel checking when the worker count is capped;
+    // otherwise rayon's global pool is used
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
 }
 
 impl SpellChecker {
@@ -80,13 +337,19 @@ impl SpellChecker {
             current_language: language,
             suggestions_enabled: true,
             case_sensitive: false,
-            max_suggestions: 5,
+            accent_lenient: false,
+            policy: CheckPolicy::default(),
             cache: Arc::new(DashMap::new()),
             ignore_list: HashSet::new(),
             user_dictionary: HashSet::new(),
             proper_nouns: HashSet::new(),
-            acronyms: HashSet::new(),
-            confidence_threshold: 0.7,
+            accepted_suggestions: HashMap::new(),
+            grammar_enabled: true,
+            grammar_rules: RuleEngine::for_language(language),
+            segmenter: SentenceSegmenter::for_language(language),
+            edit_costs: EditCosts::for_language(language),
+            tokenizer: Arc::new(UnicodeTokenizer::for_language(language)),
+            thread_pool: None,
         };
         
         // Load user data
@@ -122,22 +385,36 @@ impl SpellChecker {
             }
         }
         
-        // Load common acronyms
-        self.acronyms.extend(vec![
-            "api", "http", "https", "url", "uri", "html", "css", "js", "ts",
-            "json", "xml", "sql", "nosql", "cpu", "gpu", "ram", "rom", "usb",
-            "ssd", "hdd", "lan", "wan", "vpn", "dns", "ip", "tcp", "udp",
-            "ftp", "ssh", "ssl", "tls", "csv", "pdf", "doc", "jpg", "png",
-            "gif", "mp3", "mp4", "avi", "mkv", "zip", "rar", "tar", "gz",
-            "exe", "dll", "so", "dylib", "bin", "iso", "img", "vm", "aws",
-            "gcp", "azure", "api", "ui", "ux", "cli", "gui", "ide", "sdk",
-        ].into_iter().map(String::from));
+        // Load suggestion acceptance counts. Each acceptance is appended as
+        // its own line, so a word's lines add up.
+        self.accepted_suggestions.clear();
+        if let Ok(content) = fs::read_to_string(self.accepted_suggestions_path()) {
+            let mut lines = 0;
+            for line in content.lines() {
+                if let Some((word, count)) = line.split_once('\t') {
+                    if let Ok(count) = count.trim().parse::<u32>() {
+                        let total = self.accepted_suggestions.entry(word.trim().to_lowercase()).or_insert(0);
+                        *total = total.saturating_add(count);
+                        lines += 1;
+                    }
+                }
+            }
+            
+            // Fold the appended lines back into one per word
+            if lines > self.accepted_suggestions.len() {
+                self.write_accepted_suggestions().ok();
+            }
+        }
     }
     
     pub fn set_language(&mut self, language: Language) -> anyhow::Result<()> {
         if language != self.current_language {
             self.dictionary_manager.get_dictionary(&language)?;
             self.current_language = language;
+            self.grammar_rules = RuleEngine::for_language(language);
+            self.segmenter = SentenceSegmenter::for_language(language);
+            self.edit_costs = EditCosts::for_language(language);
+            self.tokenizer = Arc::new(UnicodeTokenizer::for_language(language));
             self.cache.clear();
             self.load_user_data();
         }
@@ -148,111 +425,551 @@ impl SpellChecker {
         self.current_language
     }
     
-    pub fn get_current_dictionary(&self) -> anyhow::Result<Dictionary> {
+    pub fn get_current_dictionary(&self) -> anyhow::Result<Arc<Dictionary>> {
         self.dictionary_manager.get_dictionary(&self.current_language)
     }
     
     pub fn check_document(&self, text: &str, filename: Option<&str>) -> DocumentAnalysis {
+        self.check_text(text, filename, None)
+    }
+    
+    // Files with a front end are checked with everything but their prose
+    // blanked out; positions are mapped back to the file afterwards
+    fn check_text(&self, text: &str, filename: Option<&str>, cursor: Option<usize>) -> DocumentAnalysis {
         let start_time = std::time::Instant::now();
         
         let dictionary = match self.get_current_dictionary() {
             Ok(dict) => dict,
-            Err(_) => {
-                return DocumentAnalysis {
-                    total_words: 0,
-                    misspelled_words: 0,
-                    accuracy: 100.0,
-                    words: Vec::new(),
-                    suggestions_count: 0,
-                    language: self.current_language,
-                    lines_checked: 0,
-                    check_duration_ms: 0,
-                    likely_code: false,
-                    file_type: filename.map(|f| f.to_string()),
-                    unique_words: 0,
-                };
+            Err(_) => return self.empty_analysis(filename.map(|f| f.to_string())),
+        };
+        
+        let (prose, is_code) = self.extract_prose(text, filename);
+        let original = text;
+        let text = prose.as_ref().map_or(text, |prose| prose.text.as_str());
+        // The cursor is in the file, but words are found in its prose
+        let cursor = cursor.map(|cursor| prose.as_ref().map_or(cursor, |prose| prose.to_prose(cursor)));
+        
+        let languages = self.marked_languages(prose.as_ref());
+        let mut context = self.line_context(&dictionary, text, is_code);
+        context.languages = &languages;
+        
+        // (byte offset, content) of every line
+        let mut lines = Vec::new();
+        let mut line_offset = 0;
+        for line in text.split('\n') {
+            lines.push((line_offset, line.strip_suffix('\r').unwrap_or(line)));
+            line_offset += line.len() + 1;
+        }
+        
+        let check_chunk = |(index, chunk): (usize, &[(usize, &str)])| {
+            let mut words = Vec::new();
+            for (i, (line_offset, line)) in chunk.iter().enumerate() {
+                self.check_line(line, index * CHUNK_LINES + i + 1, *line_offset, &context, cursor, &mut words);
             }
+            words
         };
         
-        let is_cjk = matches!(self.current_language, Language::Chinese | Language::Japanese | Language::Korean);
-        let is_code = filename.map(|f| is_code_file(f)).unwrap_or(false) || is_likely_code(text);
+        // Chunks are collected in document order, so the result doesn't
+        // depend on how many threads did the work
+        let chunks: Vec<Vec<WordCheck>> = if lines.len() < PARALLEL_MIN_LINES {
+            lines.chunks(CHUNK_LINES).enumerate().map(&check_chunk).collect()
+        } else {
+            self.in_pool(|| lines.par_chunks(CHUNK_LINES).enumerate().map(&check_chunk).collect())
+        };
+        let words = chunks.concat();
         
-        let lines: Vec<&str> = text.lines().collect();
-        let mut words = Vec::new();
-        let mut suggestions_count = 0;
-        let mut total_words = 0;
-        let mut misspelled_words = 0;
-        let mut unique_words = HashSet::new();
+        let mut analysis = self.summarize(words, text, is_code, filename.map(|f| f.to_string()), start_time);
+        if let Some(prose) = prose {
+            analysis.prose_lines = prose.line_hashes();
+            if !prose.keeps_offsets() {
+                map_to_original(&mut analysis, &prose, original);
+            }
+        }
+        analysis
+    }
+    
+    // The prose of `text` when `filename` has a front end, and whether the
+    // text is code
+    fn extract_prose(&self, text: &str, filename: Option<&str>) -> (Option<prose::Prose>, bool) {
+        let front_end = filename.and_then(FrontEnd::for_filename);
+        let is_code = match front_end {
+            Some(front_end) => front_end.is_code(),
+            None => filename.map(is_code_file).unwrap_or(false) || is_likely_code(text),
+        };
+        let prose = front_end.map(|front_end| front_end.extract(text, &self.policy, self.file_language(filename, front_end)));
+        (prose, is_code)
+    }
+    
+    // Translation files name their language ("fr.json", "values-fr/").
+    // Without a dictionary for it, the current one is used.
+    fn file_language(&self, filename: Option<&str>, front_end: FrontEnd) -> Option<Language> {
+        filename
+            .filter(|_| front_end.names_language())
+            .and_then(prose::filename_language)
+            .filter(|language| *language == self.current_language || self.language_dictionary(*language).is_some())
+    }
+    
+    // Parts marked as another language ("lang" in HTML), with the
+    // dictionary they are checked against
+    pub(crate) fn marked_languages(&self, prose: Option<&prose::Prose>) -> Vec<MarkedLanguage> {
+        prose
+            .iter()
+            .flat_map(|prose| &prose.languages)
+            .filter(|(_, language)| *language != self.current_language)
+            .map(|(range, language)| (range.clone(), self.language_dictionary(*language)))
+            .collect()
+    }
+    
+    // The dictionary for text marked as `language`, if one is installed
+    fn language_dictionary(&self, language: Language) -> Option<Arc<Dictionary>> {
+        self.dictionary_manager
+            .get_dictionary(&language)
+            .ok()
+            .filter(|dictionary| dictionary.word_count() > 0)
+    }
+    
+    /// Check `reader` line by line without holding the whole input in memory.
+    ///
+    /// Whether it is code is decided from `filename` and the first buffered
+    /// block of input. Only lines with problems are yielded; the totals cover
+    /// every line read so far. Files with a front end have their prose
+    /// checked, found a block of lines at a time where the front end allows
+    /// it; a file whose front end needs it whole is checked as plain text
+    /// past `MAX_WHOLE_FILE_BYTES`.
+    pub fn check_stream<R: BufRead>(&self, mut reader: R, filename: Option<&str>) -> anyhow::Result<StreamCheck<'_, R>> {
+        let dictionary = self.get_current_dictionary()?;
         
-        for (line_idx, line) in lines.iter().enumerate() {
-            let line_num = line_idx + 1;
+        let mut head = Vec::new();
+        if let Some(front_end) = filename.and_then(FrontEnd::for_filename) {
+            let language = self.file_language(filename, front_end);
+            if front_end.reads_by_block() {
+                let stream = StreamCheck::new(self, dictionary, reader, front_end.is_code());
+                return Ok(stream.with_front_end(front_end, language, String::new()));
+            }
             
-            let word_pattern = if is_cjk {
-                crate::util::CJK_WORD_REGEX.clone()
-            } else if is_code {
-                crate::util::CODE_WORD_REGEX.clone()
-            } else {
-                crate::util::WORD_REGEX.clone()
+            (&mut reader).take(MAX_WHOLE_FILE_BYTES as u64 + 1).read_to_end(&mut head)?;
+            if head.len() <= MAX_WHOLE_FILE_BYTES {
+                let content = String::from_utf8_lossy(&head).into_owned();
+                let stream = StreamCheck::new(self, dictionary, reader, front_end.is_code());
+                return Ok(stream.with_front_end(front_end, language, content));
+            }
+        }
+        
+        let is_code = filename.map(is_code_file).unwrap_or(false) || {
+            let head = if head.is_empty() { reader.fill_buf()? } else { &head[..] };
+            let valid = match std::str::from_utf8(head) {
+                Ok(text) => text,
+                Err(e) => std::str::from_utf8(&head[..e.valid_up_to()]).unwrap_or_default(),
             };
+            is_likely_code(valid)
+        };
+        
+        Ok(StreamCheck::new(self, dictionary, reader, is_code).with_head(head))
+    }
+    
+    // The prose of one block of a streamed file, and the parts of it
+    // marked as another language
+    pub(crate) fn block_prose(
+        &self,
+        text: &str,
+        front_end: FrontEnd,
+        language: Option<Language>,
+        translations: Option<Option<Language>>,
+    ) -> (prose::Prose, Vec<MarkedLanguage>) {
+        let spans = match front_end {
+            FrontEnd::Gettext => gettext::entry_spans(text, self.policy.source_language, translations),
+            _ => front_end.spans(text, &self.policy),
+        };
+        let prose = prose::Prose::in_language(text, spans, language);
+        let languages = self.marked_languages(Some(&prose));
+        (prose, languages)
+    }
+    
+    /// Re-check only the lines touched by `edit`, reusing the rest of `previous`.
+    ///
+    /// `text` is the document after the edit. The line after the edit is also
+    /// re-checked, since its first word may have changed sentence position;
+    /// words beyond that are shifted rather than re-checked. A word containing `cursor` (a byte offset
+    /// into `text`) is treated as still being typed and skipped.
+    ///
+    /// Also returns the lines re-checked, or None when lines away from the
+    /// edit may have been re-checked too: after a full check, or in a file
+    /// with a front end.
+    pub fn recheck_edit(
+        &self,
+        previous: DocumentAnalysis,
+        text: &str,
+        edit: &DocumentEdit,
+        cursor: Option<usize>,
+    ) -> (DocumentAnalysis, Option<RecheckedLines>) {
+        let start_time = std::time::Instant::now();
+        
+        let edit_end = edit.range.start + edit.new_len;
+        if previous.language != self.current_language
+            || edit_end > text.len()
+            || !text.is_char_boundary(edit.range.start)
+        {
+            return (self.check_text(text, previous.file_type.as_deref(), cursor), None);
+        }
+        
+        if previous.file_type.as_deref().is_some_and(prose::has_front_end) {
+            return (self.recheck_prose(previous, text, edit, cursor, start_time), None);
+        }
+        
+        let dictionary = match self.get_current_dictionary() {
+            Ok(dict) => dict,
+            Err(_) => return (self.empty_analysis(previous.file_type), None),
+        };
+        
+        let context = self.line_context(&dictionary, text, previous.likely_code);
+        
+        // Everything before the edit is unchanged, so line numbers up to it are too
+        let first_line_start = text[..edit.range.start].rfind('\n').map_or(0, |i| i + 1);
+        let first_line = edit.first_line(text);
+        
+        let mut rechecked = Vec::new();
+        let mut line_offset = first_line_start;
+        let mut lines = text[first_line_start..].split('\n');
+        
+        for (i, line) in lines.by_ref().take(edit.inserted_lines + 1).enumerate() {
+            let content = line.strip_suffix('\r').unwrap_or(line);
+            self.check_line(content, first_line + i, line_offset, &context, cursor, &mut rechecked);
+            line_offset += line.len() + 1;
+        }
+        
+        // The edit may have added or removed the sentence end before the next line
+        let mut extra_lines = 0;
+        if let Some(line) = lines.next().filter(|line| !line.trim().is_empty()) {
+            let content = line.strip_suffix('\r').unwrap_or(line);
+            let line_num = first_line + edit.inserted_lines + 1;
+            self.check_line(content, line_num, line_offset, &context, cursor, &mut rechecked);
+            extra_lines = 1;
+        }
+        
+        let old_last_line = first_line + edit.removed_lines + extra_lines;
+        let new_last_line = first_line + edit.inserted_lines + extra_lines;
+        
+        let mut words = previous.words;
+        let from = words.partition_point(|w| w.line < first_line);
+        let to = words.partition_point(|w| w.line <= old_last_line);
+        
+        let offset_delta = edit.new_len as isize - edit.range.len() as isize;
+        for word in &mut words[to..] {
+            word.line = word.line + new_last_line - old_last_line;
+            word.offset = (word.offset as isize + offset_delta) as usize;
+        }
+        words.splice(from..to, rechecked);
+        
+        let lines = RecheckedLines { first: first_line, old_last: old_last_line, new_last: new_last_line };
+        (self.summarize(words, text, previous.likely_code, previous.file_type, start_time), Some(lines))
+    }
+    
+    // `recheck_edit` for a file with a front end. An edit can open a comment
+    // that runs to the end of the file, so the whole file is lexed again,
+    // but only lines whose prose changed are re-checked, each with the line
+    // after it.
+    fn recheck_prose(
+        &self,
+        previous: DocumentAnalysis,
+        original: &str,
+        edit: &DocumentEdit,
+        cursor: Option<usize>,
+        start_time: std::time::Instant,
+    ) -> DocumentAnalysis {
+        let filename = previous.file_type.as_deref();
+        let (Some(prose), is_code) = self.extract_prose(original, filename) else {
+            return self.check_text(original, filename, cursor);
+        };
+        let hashes = prose.line_hashes();
+        if previous.prose_lines.len() + edit.inserted_lines != hashes.len() + edit.removed_lines {
+            return self.check_text(original, filename, cursor);
+        }
+        
+        let dictionary = match self.get_current_dictionary() {
+            Ok(dict) => dict,
+            Err(_) => return self.empty_analysis(previous.file_type),
+        };
+        
+        let first_line = edit.first_line(original);
+        let old_last_line = first_line + edit.removed_lines;
+        let new_last_line = first_line + edit.inserted_lines;
+        
+        // Whether each line must be re-checked: the edited ones, those whose
+        // prose changed, and the line after each of those
+        let mut changed: Vec<bool> = (1..=hashes.len())
+            .map(|line| {
+                let old_line = if line < first_line {
+                    line
+                } else if line > new_last_line {
+                    line - new_last_line + old_last_line
+                } else {
+                    return true;
+                };
+                previous.prose_lines[old_line - 1] != hashes[line - 1]
+            })
+            .collect();
+        for i in (1..changed.len()).rev() {
+            changed[i] |= changed[i - 1];
+        }
+        
+        // The words of the other lines, moved to where they are now
+        let offset_delta = edit.new_len as isize - edit.range.len() as isize;
+        let mut words: Vec<WordCheck> = previous.words
+            .into_iter()
+            .filter_map(|mut word| {
+                if word.line > old_last_line {
+                    word.line = word.line - old_last_line + new_last_line;
+                    word.offset = (word.offset as isize + offset_delta) as usize;
+                } else if word.line >= first_line {
+                    return None;
+                }
+                (changed.get(word.line - 1) == Some(&false)).then_some(word)
+            })
+            .collect();
+        if !prose.keeps_offsets() {
+            relocate(&mut words, &mut [], |offset| prose.to_prose(offset), &prose.text);
+        }
+        
+        let languages = self.marked_languages(Some(&prose));
+        let mut context = self.line_context(&dictionary, &prose.text, is_code);
+        context.languages = &languages;
+        let cursor = cursor.map(|cursor| prose.to_prose(cursor));
+        for (i, (line_offset, line)) in prose::lines(&prose.text).enumerate() {
+            if changed[i] {
+                self.check_line(line, i + 1, line_offset, &context, cursor, &mut words);
+            }
+        }
+        words.sort_by_key(|word| word.line);
+        
+        let mut analysis = self.summarize(words, &prose.text, is_code, previous.file_type, start_time);
+        analysis.prose_lines = hashes;
+        if !prose.keeps_offsets() {
+            map_to_original(&mut analysis, &prose, original);
+        }
+        analysis
+    }
+    
+    fn in_pool<T: Send>(&self, work: impl FnOnce() -> T + Send) -> T {
+        match &self.thread_pool {
+            Some(pool) => pool.install(work),
+            None => work(),
+        }
+    }
+    
+    pub(crate) fn line_context<'a>(&'a self, dictionary: &'a Dictionary, text: &'a str, is_code: bool) -> LineContext<'a> {
+        LineContext { dictionary, languages: &[], tokenizer: self.tokenizer.as_ref(), text, is_code }
+    }
+    
+    pub(crate) fn check_line(
+        &self,
+        line: &str,
+        line_num: usize,
+        line_offset: usize,
+        context: &LineContext,
+        cursor: Option<usize>,
+        words: &mut Vec<WordCheck>,
+    ) {
+        let is_code = context.is_code;
+        
+        let tokens = context.tokenizer.tokenize(line)
+            .into_iter()
+            .flat_map(|token| match context.at(line_offset + token.bytes.start) {
+                Some(context) => self.segment_token(token, context.dictionary),
+                None => vec![token],
+            });
+        
+        for token in tokens {
+            let offset = line_offset + token.bytes.start;
             
-            for mat in word_pattern.find_iter(line) {
-                let original_word = mat.as_str();
-                let start = mat.start();
-                let end = mat.end();
-                
-                // Determine word type
-                let word_type = self.determine_word_type(original_word, is_code);
-                
-                // Skip based on word type
-                if self.should_skip_word(original_word, &word_type) {
-                    words.push(WordCheck {
-                        word: original_word.to_lowercase(),
-                        original: original_word.to_string(),
-                        start,
-                        end,
-                        is_correct: true,
-                        suggestions: Vec::new(),
-                        line: line_num,
-                        column: start + 1,
-                        confidence: 1.0,
-                        word_type,
-                    });
+            // Capitals only hint at a proper noun in the middle of a sentence
+            let sentence_start = token.text.starts_with(char::is_uppercase)
+                && self.segmenter.is_sentence_start(context.text, offset);
+            
+            // Determine word type
+            let word_type = self.determine_word_type(token.text, token.kind, is_code, sentence_start);
+            
+            // Skip based on word type, and leave the word being typed alone.
+            // Words in a language without a dictionary can't be checked.
+            let being_typed = cursor.is_some_and(|c| offset <= c && c <= line_offset + token.bytes.end);
+            let language_context = context.at(offset);
+            if being_typed || language_context.is_none() || self.should_skip_word(token.text, &word_type) {
+                words.push(skipped_word(&token, line_num, line_offset, word_type));
+                continue;
+            }
+            let context = language_context.as_ref().unwrap_or(context);
+            
+            // "recieveMessage" is checked as "recieve" and "Message"
+            if word_type == WordType::CodeIdentifier {
+                let parts = identifier_parts(token.text);
+                if parts.len() > 1 {
+                    self.check_identifier(&token, &parts, line_num, line_offset, context, words);
                     continue;
                 }
-                
-                let word_lower = original_word.to_lowercase();
-                unique_words.insert(word_lower.clone());
-                
-                // Check in various dictionaries and lists
-                let is_correct = self.check_word_correctness(&word_lower, original_word, &word_type, &dictionary, is_code);
-                let confidence = self.calculate_confidence(original_word, &word_type, is_correct, is_code);
-                
-                total_words += 1;
-                if !is_correct && confidence >= self.confidence_threshold {
-                    misspelled_words += 1;
+            }
+            
+            // "Recieve the package." is looked up as "recieve"
+            let lookup_word = if sentence_start && word_type == WordType::Normal {
+                lowercase_first(token.text)
+            } else {
+                token.text.to_string()
+            };
+            
+            words.push(self.check_token(&token, word_type, &lookup_word, line_num, line_offset, context));
+        }
+    }
+    
+    fn check_token(
+        &self,
+        token: &Token,
+        word_type: WordType,
+        lookup_word: &str,
+        line_num: usize,
+        line_offset: usize,
+        context: &LineContext,
+    ) -> WordCheck {
+        let original_word = token.text;
+        let word_lower = original_word.to_lowercase();
+        
+        // Check in various dictionaries and lists
+        let is_correct = self.check_word_correctness(&word_lower, lookup_word, &word_type, context.dictionary, context.is_code);
+        let mut confidence = self.calculate_confidence(original_word, &word_type, is_correct, context.is_code);
+        
+        // A missing accent is almost certainly a mistake when there is
+        // exactly one way to put it back
+        if !is_correct && context.dictionary.restore_accents(lookup_word).is_some() {
+            confidence = confidence.max(ACCENT_CONFIDENCE);
+        }
+        
+        let suggestions = if !is_correct && self.suggestions_enabled && confidence >= self.policy.min_confidence {
+            self.get_suggestions(&word_lower, context.dictionary)
+        } else {
+            Vec::new()
+        };
+        
+        let compound = if is_correct {
+            context.dictionary.compound_split(lookup_word)
+        } else {
+            None
+        };
+        
+        WordCheck {
+            word: word_lower,
+            original: original_word.to_string(),
+            start: token.bytes.start,
+            end: token.bytes.end,
+            offset: line_offset + token.bytes.start,
+            is_correct: is_correct || confidence < self.policy.min_confidence,
+            skipped: false,
+            suggestions,
+            line: line_num,
+            column: token.chars.start + 1,
+            confidence,
+            word_type,
+            compound,
+            identifier: None,
+        }
+    }
+    
+    // Check each part of an identifier on its own. A misspelled part is
+    // reported at its own span, with suggestions for the whole identifier
+    // written the way the original was ("recieve_all" -> "receive_all").
+    fn check_identifier(
+        &self,
+        token: &Token,
+        parts: &[Range<usize>],
+        line_num: usize,
+        line_offset: usize,
+        context: &LineContext,
+        words: &mut Vec<WordCheck>,
+    ) {
+        let screaming_case = !token.text.chars().any(char::is_lowercase);
+        
+        for part in parts {
+            let text = &token.text[part.clone()];
+            let chars_before = token.text[..part.start].chars().count();
+            let part_token = Token {
+                text,
+                bytes: token.bytes.start + part.start..token.bytes.start + part.end,
+                chars: token.chars.start + chars_before..token.chars.start + chars_before + text.chars().count(),
+                kind: TokenKind::Word,
+            };
+            
+            // In "HTTPServer" the capitals mark an acronym, in "MAX_SIZE" they don't
+            let word_type = if text.chars().count() < self.policy.min_word_length {
+                WordType::ShortWord
+            } else if text.chars().all(char::is_uppercase) && !screaming_case {
+                WordType::Acronym
+            } else {
+                WordType::Normal
+            };
+            
+            if self.should_skip_word(text, &word_type) {
+                words.push(skipped_word(&part_token, line_num, line_offset, word_type));
+                continue;
+            }
+            
+            // Capitals in "recieveMessage" or "MAX_SIZE" are only style
+            let lookup_word = if word_type == WordType::Normal { text.to_lowercase() } else { text.to_string() };
+            let mut check = self.check_token(&part_token, word_type, &lookup_word, line_num, line_offset, context);
+            check.suggestions = std::mem::take(&mut check.suggestions)
+                .into_iter()
+                .filter_map(|mut suggestion| {
+                    suggestion.word = replace_identifier_part(token.text, part.clone(), &suggestion.word)?;
+                    Some(suggestion)
+                })
+                .collect();
+            check.identifier = Some(token.text.to_string());
+            words.push(check);
+        }
+    }
+    
+    // Chinese and Japanese runs come from the tokenizer whole; split them
+    // into dictionary words so only the unknown pieces are reported
+    fn segment_token<'a>(&self, token: Token<'a>, dictionary: &Dictionary) -> Vec<Token<'a>> {
+        if !matches!(dictionary.language(), Language::Chinese | Language::Japanese)
+            || !token.text.starts_with(is_cjk_char)
+        {
+            return vec![token];
+        }
+        
+        let mut char_start = token.chars.start;
+        dictionary.segment(token.text)
+            .into_iter()
+            .map(|segment| {
+                let text = &token.text[segment.range.clone()];
+                let chars = char_start..char_start + text.chars().count();
+                char_start = chars.end;
+                Token {
+                    text,
+                    bytes: token.bytes.start + segment.range.start..token.bytes.start + segment.range.end,
+                    chars,
+                    kind: TokenKind::Word,
                 }
-                
-                let suggestions = if !is_correct && self.suggestions_enabled && confidence >= self.confidence_threshold {
-                    let sugg = self.get_suggestions(&word_lower, &dictionary);
-                    suggestions_count += sugg.len();
-                    sugg
-                } else {
-                    Vec::new()
-                };
-                
-                words.push(WordCheck {
-                    word: word_lower.clone(),
-                    original: original_word.to_string(),
-                    start,
-                    end,
-                    is_correct: is_correct || confidence < self.confidence_threshold,
-                    suggestions,
-                    line: line_num,
-                    column: start + 1,
-                    confidence,
-                    word_type,
-                });
+            })
+            .collect()
+    }
+    
+    // Recompute document totals from the per-word results
+    fn summarize(
+        &self,
+        words: Vec<WordCheck>,
+        text: &str,
+        is_code: bool,
+        file_type: Option<String>,
+        start_time: std::time::Instant,
+    ) -> DocumentAnalysis {
+        let mut total_words = 0;
+        let mut misspelled_words = 0;
+        let mut suggestions_count = 0;
+        let mut unique_words = HashSet::new();
+        
+        for word in words.iter().filter(|w| !w.skipped) {
+            total_words += 1;
+            if !word.is_correct {
+                misspelled_words += 1;
             }
+            suggestions_count += word.suggestions.len();
+            unique_words.insert(word.word.as_str());
         }
         
         let accuracy = if total_words > 0 {
@@ -261,27 +978,62 @@ impl SpellChecker {
             100.0
         };
         
-        let check_duration = start_time.elapsed();
+        let diagnostics = self.grammar_diagnostics(&words, text);
         
         DocumentAnalysis {
             total_words,
             misspelled_words,
             accuracy,
+            unique_words: unique_words.len(),
             words,
+            diagnostics,
             suggestions_count,
             language: self.current_language,
-            lines_checked: lines.len(),
-            check_duration_ms: check_duration.as_millis(),
+            lines_checked: text.lines().count(),
+            check_duration_ms: start_time.elapsed().as_millis(),
             likely_code: is_code,
-            file_type: filename.map(|f| f.to_string()),
-            unique_words: unique_words.len(),
+            file_type,
+            prose_lines: Vec::new(),
         }
     }
     
-    fn determine_word_type(&self, word: &str, is_code: bool) -> WordType {
-        // Check for numbers
-        if word.chars().all(|c| c.is_numeric()) {
-            return WordType::Number;
+    pub(crate) fn grammar_diagnostics(&self, words: &[WordCheck], text: &str) -> Vec<Diagnostic> {
+        if self.grammar_enabled {
+            self.grammar_rules.check(words, text)
+        } else {
+            Vec::new()
+        }
+    }
+    
+    fn empty_analysis(&self, file_type: Option<String>) -> DocumentAnalysis {
+        DocumentAnalysis {
+            total_words: 0,
+            misspelled_words: 0,
+            accuracy: 100.0,
+            words: Vec::new(),
+            diagnostics: Vec::new(),
+            suggestions_count: 0,
+            language: self.current_language,
+            lines_checked: 0,
+            check_duration_ms: 0,
+            likely_code: false,
+            file_type,
+            unique_words: 0,
+            prose_lines: Vec::new(),
+        }
+    }
+    
+    fn determine_word_type(&self, word: &str, kind: TokenKind, is_code: bool, sentence_start: bool) -> WordType {
+        // Identifiers made of several words ("recieveMessage", "MAX_SIZE",
+        // "utf8Name") are checked a part at a time
+        let is_compound_identifier = is_code && identifier_parts(word).len() > 1;
+        
+        // Numbers, ordinals ("21st") and digits mixed with letters ("mp3")
+        match kind {
+            TokenKind::Alphanumeric if is_compound_identifier => return WordType::CodeIdentifier,
+            TokenKind::Number | TokenKind::Ordinal | TokenKind::Alphanumeric => return WordType::Number,
+            TokenKind::Abbreviation => return WordType::Abbreviation,
+            TokenKind::Word => {}
         }
         
         // Check for symbols
@@ -289,18 +1041,23 @@ impl SpellChecker {
             return WordType::Symbol;
         }
         
-        // Check for short words
-        if word.len() <= 2 {
+        // Check for short words; most Chinese and Japanese words are one or
+        // two characters
+        if word.chars().count() < self.policy.min_word_length && !word.starts_with(is_cjk_char) {
             return WordType::ShortWord;
         }
         
+        if is_compound_identifier {
+            return WordType::CodeIdentifier;
+        }
+        
         // Check for acronyms (all caps or with numbers)
         if word.chars().all(|c| c.is_uppercase() || c.is_numeric() || c == '_') && word.len() <= 6 {
             return WordType::Acronym;
         }
         
         // Check for proper nouns (starts with capital, not at sentence start)
-        if word.chars().next().map(|c| c.is_uppercase()).unwrap_or(false) && word.len() > 2 {
+        if !sentence_start && word.chars().next().map(|c| c.is_uppercase()).unwrap_or(false) && word.len() > 2 {
             let common_caps = ["I", "A", "The", "And", "But", "Or", "For", "Nor", "Yet", "So"];
             if !common_caps.contains(&word) {
                 return WordType::ProperNoun;
@@ -309,7 +1066,6 @@ impl SpellChecker {
         
         // Check for code identifiers
         if is_code && (word.contains('_') || 
-                      (word.chars().any(|c| c.is_uppercase()) && word.chars().any(|c| c.is_lowercase())) ||
                       word.starts_with("get_") || word.starts_with("set_") ||
                       word.ends_with("_t") || word.ends_with("_ptr") ||
                       word.ends_with("Handler") || word.ends_with("Service")) {
@@ -325,10 +1081,13 @@ impl SpellChecker {
     }
     
     fn should_skip_word(&self, word: &str, word_type: &WordType) -> bool {
+        if !self.policy.for_type(word_type).check {
+            return true;
+        }
+        
         match word_type {
-            WordType::Number | WordType::Symbol | WordType::ShortWord => true,
             WordType::Acronym => {
-                self.acronyms.contains(&word.to_lowercase())
+                self.policy.known_acronyms.contains(&word.to_lowercase())
             }
             WordType::CodeIdentifier => {
                 word.len() <= 3 || // Very short identifiers
@@ -354,28 +1113,25 @@ impl SpellChecker {
             return true;
         }
         
-        // Check cache
-        let cache_key = format!("{}_{}", self.current_language.code(), word_lower);
-        if let Some(cached) = self.cache.get(&cache_key) {
-            return *cached;
-        }
+        // Only the dictionary lookup is cached: how lenient to be depends on
+        // where the word appears (a proper noun mid-sentence, say)
+        let lookup = if self.case_sensitive { original_word } else { word_lower };
+        let cache_key = format!("{}_{}_{}", dictionary.language().code(), is_code, lookup);
+        let cached = self.cache.get(&cache_key).map(|found| *found);
         
         // Check main dictionary
-        let in_dictionary = dictionary.contains(original_word, self.case_sensitive, is_code);
-        
-        // For proper nouns and acronyms, be more lenient
-        let is_correct = match word_type {
-            WordType::ProperNoun | WordType::Acronym => {
-                in_dictionary || self.looks_reasonable(original_word)
-            }
-            WordType::CodeIdentifier => {
-                in_dictionary || original_word.len() <= 15
-            }
-            _ => in_dictionary,
-        };
+        let in_dictionary = cached.unwrap_or_else(|| {
+            let found = dictionary.contains(original_word, self.case_sensitive, is_code)
+                || (self.accent_lenient && !dictionary.accent_matches(original_word).is_empty());
+            self.cache.insert(cache_key, found);
+            found
+        });
         
-        self.cache.insert(cache_key, is_correct);
-        is_correct
+        // Some types (proper nouns, acronyms, ...) are accepted more leniently
+        let rules = self.policy.for_type(word_type);
+        in_dictionary
+            || (rules.accept_plausible && self.looks_reasonable(original_word))
+            || original_word.chars().count() <= rules.accept_up_to
     }
     
     fn looks_reasonable(&self, word: &str) -> bool {
@@ -402,16 +1158,9 @@ impl SpellChecker {
             return 1.0;
         }
         
-        let mut confidence: f32 = 0.5;
-        
-        match word_type {
-            WordType::Normal => confidence *= 1.2,
-            WordType::CodeIdentifier => confidence *= if is_code { 0.3 } else { 0.8 },
-            WordType::Acronym => confidence *= 0.4,
-            WordType::ProperNoun => confidence *= 0.6,
-            WordType::TechnicalTerm => confidence *= 0.8,
-            _ => confidence *= 0.2,
-        }
+        let rules = self.policy.for_type(word_type);
+        let weight = rules.code_weight.filter(|_| is_code).unwrap_or(rules.weight);
+        let mut confidence: f32 = 0.5 * weight;
         
         if word.len() < 3 {
             confidence *= 0.3;
@@ -427,44 +1176,110 @@ impl SpellChecker {
             confidence *= 1.3;
         }
         
-        confidence.min(1.0).max(0.0)
+        confidence.clamp(0.0, 1.0)
     }
     
-    fn get_suggestions(&self, word: &str, dictionary: &Dictionary) -> Vec<String> {
+    fn get_suggestions(&self, word: &str, dictionary: &Dictionary) -> Vec<Suggestion> {
         if word.len() <= 1 {
             return Vec::new();
         }
         
-        let dict_words = dictionary.get_words();
-        let max_candidates = 2000;
+        let sounds_alike: HashSet<String> = dictionary.phonetic_candidates(word).into_iter().collect();
+        let cost = |candidate: &str| {
+            let distance = self.edit_costs.distance(word, candidate);
+            if sounds_alike.contains(candidate) {
+                distance * PHONETIC_DISCOUNT
+            } else {
+                distance
+            }
+        };
         
-        let candidates: Vec<&String> = dict_words.iter()
-            .filter(|w| {
-                let len_diff = (w.len() as isize - word.len() as isize).abs();
-                len_diff <= 3
+        let mut candidates: Vec<Suggestion> = dictionary.suggestion_candidates(word, self.policy.max_suggestion_distance)
+            .into_iter()
+            .filter(|(candidate, _)| candidate != word)
+            .map(|(candidate, _)| Suggestion {
+                score: self.suggestion_score(cost(&candidate), &candidate, dictionary),
+                word: candidate,
+                strategy: SuggestionStrategy::EditDistance,
             })
-            .take(max_candidates)
             .collect();
         
-        let mut suggestions: Vec<(String, usize)> = candidates
-            .par_iter()
-            .map(|&dict_word| {
-                let distance = self.edit_distance(word, dict_word);
-                (dict_word.clone(), distance)
-            })
-            .filter(|(_, distance)| *distance <= 2)
+        // Phonetic matches too far apart for the edit-distance search
+        let mut phonetic: Vec<&String> = sounds_alike.iter()
+            .filter(|candidate| *candidate != word && !candidates.iter().any(|s| &s.word == *candidate))
             .collect();
+        phonetic.sort_unstable();
+        candidates.extend(phonetic.into_iter().map(|candidate| Suggestion {
+            score: self.suggestion_score(cost(candidate), candidate, dictionary),
+            word: candidate.clone(),
+            strategy: SuggestionStrategy::Phonetic,
+        }));
         
-        suggestions.sort_by_key(|(_, distance)| *distance);
-        suggestions
-            .into_iter()
-            .take(self.max_suggestions)
-            .map(|(word, _)| word)
-            .collect()
+        // Best first; the sort is stable, so ties keep the dictionary's order
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        
+        // An unambiguous accent fix goes above everything else
+        if let Some(restored) = dictionary.restore_accents(word) {
+            candidates.retain(|s| s.word != restored);
+            candidates.insert(0, Suggestion {
+                word: restored,
+                strategy: SuggestionStrategy::Accent,
+                score: 1.0,
+            });
+        }
+        candidates.truncate(self.policy.max_suggestions);
+        candidates
     }
     
-    fn edit_distance(&self, a: &str, b: &str) -> usize {
-        crate::util::levenshtein_distance(a, b)
+    // Closer spellings (by keyboard distance), more common words and words the
+    // user has picked before score higher
+    fn suggestion_score(&self, distance: f32, candidate: &str, dictionary: &Dictionary) -> f32 {
+        let closeness = 1.0 / (1.0 + distance);
+        
+        let max_frequency = dictionary.max_frequency();
+        let frequency = if max_frequency > 0 {
+            (dictionary.frequency(candidate) as f32).ln_1p() / (max_frequency as f32).ln_1p()
+        } else {
+            0.0
+        };
+        
+        let accepted = self.accepted_suggestions.get(candidate).copied().unwrap_or(0) as f32;
+        let acceptance = accepted / (accepted + 2.0);
+        
+        DISTANCE_WEIGHT * closeness + FREQUENCY_WEIGHT * frequency + ACCEPTANCE_WEIGHT * acceptance
+    }
+    
+    /// Remember that the user replaced a misspelling with `suggestion`, so it
+    /// ranks higher next time
+    pub fn record_accepted_suggestion(&mut self, suggestion: &str) -> anyhow::Result<()> {
+        let word = suggestion.trim().to_lowercase();
+        if word.is_empty() {
+            return Ok(());
+        }
+        let count = self.accepted_suggestions.entry(word.clone()).or_insert(0);
+        *count = count.saturating_add(1);
+        
+        // Appending keeps this cheap however many words have been accepted;
+        // the lines are folded together the next time the counts are loaded
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.accepted_suggestions_path())?;
+        writeln!(file, "{}\t1", word)?;
+        
+        Ok(())
+    }
+    
+    fn write_accepted_suggestions(&self) -> std::io::Result<()> {
+        let mut counts: Vec<_> = self.accepted_suggestions.iter().collect();
+        counts.sort();
+        let content: String = counts.into_iter().map(|(word, count)| format!("{}\t{}\n", word, count)).collect();
+        fs::write(self.accepted_suggestions_path(), content)
+    }
+    
+    fn accepted_suggestions_path(&self) -> std::path::PathBuf {
+        crate::language::LanguageManager::user_dict_dir()
+            .join(format!("accepted_{}.txt", self.current_language.code()))
     }
     
     pub fn add_word_to_dictionary(&mut self, word: &str) -> anyhow::Result<()> {
@@ -475,9 +1290,8 @@ impl SpellChecker {
         
         let word_lower = sanitized.to_lowercase();
         
-        // Update cache
-        let cache_key = format!("{}_{}", self.current_language.code(), word_lower);
-        self.cache.insert(cache_key, true);
+        // Cached lookups may have missed this word
+        self.cache.clear();
         
         // Update ignore list (remove if present)
         self.ignore_list.remove(&word_lower);
@@ -541,11 +1355,63 @@ impl SpellChecker {
         self.suggestions_enabled = enabled;
     }
     
+    pub fn enable_grammar(&mut self, enabled: bool) {
+        self.grammar_enabled = enabled;
+    }
+    
+    /// Ids of the grammar rules that run for the current language
+    pub fn grammar_rule_ids(&self) -> Vec<&'static str> {
+        self.grammar_rules.rule_ids()
+    }
+    
     pub fn set_case_sensitive(&mut self, sensitive: bool) {
         self.case_sensitive = sensitive;
         self.cache.clear();
     }
     
+    pub fn policy(&self) -> &CheckPolicy {
+        &self.policy
+    }
+    
+    /// Replace the rules for which words are checked and when unknown words
+    /// are reported
+    pub fn set_policy(&mut self, policy: CheckPolicy) {
+        self.policy = policy;
+        self.cache.clear();
+    }
+    
+    /// Accept words typed without their accents ("ete", "naief") as long as
+    /// the dictionary has an accented spelling of them
+    pub fn set_accent_lenient(&mut self, lenient: bool) {
+        self.accent_lenient = lenient;
+        self.cache.clear();
+    }
+    
+    /// Cap the worker threads used to check large documents; 0 uses every core
+    pub fn set_max_threads(&mut self, threads: usize) -> anyhow::Result<()> {
+        if self.thread_pool.as_ref().map_or(0, |pool| pool.current_num_threads()) == threads {
+            return Ok(());
+        }
+        self.thread_pool = if threads == 0 {
+            None
+        } else {
+            Some(Arc::new(rayon::ThreadPoolBuilder::new().num_threads(threads).build()?))
+        };
+        Ok(())
+    }
+    
+    /// Replace how text is split into words. Changing language resets it to
+    /// that language's `UnicodeTokenizer`.
+    pub fn set_tokenizer(&mut self, tokenizer: Arc<dyn Tokenizer>) {
+        self.tokenizer = tokenizer;
+    }
+    
+    /// Costs used to rank suggestions. Changing language resets them to that
+    /// language's keyboard layout.
+    pub fn set_edit_costs(&mut self, costs: EditCosts) {
+        self.edit_costs = costs;
+    }
+    
     pub fn word_count(&self) -> usize {
         match self.get_current_dictionary() {
             Ok(dict) => dict.word_count(),
@@ -582,6 +1448,99 @@ fn has_repeated_characters(word: &str, max_repeats: usize) -> bool {
     false
 }
 
+// A word that was looked at but not checked
+fn skipped_word(token: &Token, line_num: usize, line_offset: usize, word_type: WordType) -> WordCheck {
+    WordCheck {
+        word: token.text.to_lowercase(),
+        original: token.text.to_string(),
+        start: token.bytes.start,
+        end: token.bytes.end,
+        offset: line_offset + token.bytes.start,
+        is_correct: true,
+        skipped: true,
+        suggestions: Vec::new(),
+        line: line_num,
+        column: token.chars.start + 1,
+        confidence: 1.0,
+        word_type,
+        compound: None,
+        identifier: None,
+    }
+}
+
+// Move word and diagnostic positions found in `prose` back to where they
+// are in `original`, the file it came from. Lines don't change.
+fn map_to_original(analysis: &mut DocumentAnalysis, prose: &prose::Prose, original: &str) {
+    relocate(&mut analysis.words, &mut analysis.diagnostics, |offset| prose.to_original(offset), original);
+}
+
+// Move word and diagnostic positions to `target`, a text with the same
+// lines as the one they were found in; `map` takes an offset there to one
+// in `target`
+fn relocate(words: &mut [WordCheck], diagnostics: &mut [Diagnostic], map: impl Fn(usize) -> usize, target: &str) {
+    // (start of its line, column) of an offset in `target`
+    let position = |offset: usize| {
+        let line_start = target[..offset].rfind('\n').map_or(0, |i| i + 1);
+        (line_start, target[line_start..offset].chars().count() + 1)
+    };
+    
+    for word in words {
+        let start = map(word.offset);
+        let end = map(word.offset + (word.end - word.start));
+        let (line_start, column) = position(start);
+        word.offset = start;
+        word.start = start - line_start;
+        word.end = end - line_start;
+        word.column = column;
+    }
+    
+    for diagnostic in diagnostics {
+        let range = map(diagnostic.range.start)..map(diagnostic.range.end);
+        diagnostic.column = position(range.start).1;
+        diagnostic.range = range;
+    }
+}
+
+// `identifier` with the part at `range` replaced by `word`, cased like the
+// part it replaces. Words that can't be written as one part (spaces,
+// hyphens) give None.
+fn replace_identifier_part(identifier: &str, range: Range<usize>, word: &str) -> Option<String> {
+    if !word.chars().all(char::is_alphabetic) {
+        return None;
+    }
+    
+    let part = &identifier[range.clone()];
+    let styled = if part.chars().count() > 1 && part.chars().all(char::is_uppercase) {
+        word.to_uppercase()
+    } else if part.starts_with(char::is_uppercase) {
+        capitalize_first(word)
+    } else {
+        word.to_lowercase()
+    };
+    
+    Some(format!("{}{}{}", &identifier[..range.start], styled, &identifier[range.end..]))
+}
+
+fn capitalize_first(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+        None => String::new(),
+    }
+}
+
+fn lowercase_first(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn count_newlines(text: &str) -> usize {
+    text.bytes().filter(|&b| b == b'\n').count()
+}
+
 fn has_vowels(word: &str) -> bool {
     let vowels = ['a', 'e', 'i', 'o', 'u', 'y', 'A', 'E', 'I', 'O', 'U', 'Y'];
     word.chars().any(|c| vowels.contains(&c))
@@ -593,4 +1552,101 @@ fn has_common_typo_patterns(word: &str) -> bool {
     ];
     
     common_patterns.iter().any(|pattern| word.contains(pattern))
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    type Key = (Vec<(String, usize, usize, usize, bool, bool)>, Vec<(&'static str, Range<usize>, usize, usize)>);
+    
+    fn checker() -> SpellChecker {
+        let mut checker = SpellChecker::new(Language::English).unwrap();
+        checker.set_policy(CheckPolicy { min_confidence: 0.3, ..CheckPolicy::default() });
+        checker
+    }
+    
+    fn key(analysis: &DocumentAnalysis) -> Key {
+        let words = analysis.words
+            .iter()
+            .map(|w| (w.word.clone(), w.offset, w.line, w.column, w.is_correct, w.skipped))
+            .collect();
+        let diagnostics = analysis.diagnostics
+            .iter()
+            .map(|d| (d.rule_id, d.range.clone(), d.line, d.column))
+            .collect();
+        (words, diagnostics)
+    }
+    
+    // Replace `range` of `text` with `replacement`, re-checking only the edit,
+    // and compare with checking the result from scratch
+    fn assert_recheck_matches(text: &str, range: Range<usize>, replacement: &str) {
+        let checker = checker();
+        let previous = checker.check_text(text, None, None);
+        let edit = DocumentEdit::new(text, range.clone(), replacement);
+        let mut edited = text.to_string();
+        edited.replace_range(range, replacement);
+        
+        let (rechecked, lines) = checker.recheck_edit(previous, &edited, &edit, None);
+        assert!(lines.is_some());
+        assert_eq!(key(&rechecked), key(&checker.check_text(&edited, None, None)));
+    }
+    
+    const TEXT: &str = "The first line is fine.\nThe second line has a mispeled word.\nThe third line is fine too";
+    
+    #[test]
+    fn recheck_after_inserting_lines() {
+        let at = TEXT.find("The second").unwrap();
+        assert_recheck_matches(TEXT, at..at, "A new line to recieve.\nAnother new line.\n");
+    }
+    
+    #[test]
+    fn recheck_after_deleting_lines() {
+        let start = TEXT.find("The second").unwrap();
+        let end = TEXT.find("The third").unwrap();
+        assert_recheck_matches(TEXT, start..end, "");
+        assert_recheck_matches(TEXT, start - 1..end - 1, " and more");
+    }
+    
+    #[test]
+    fn recheck_after_changing_a_sentence_end() {
+        let text = "We walked to the park\nthe dog ran ahead. Then the the rain came.\n";
+        let at = text.find('\n').unwrap();
+        assert_recheck_matches(text, at..at, ".");
+        
+        let text = "We walked to the park.\nThe dog ran ahead.\n";
+        let at = text.find('.').unwrap();
+        assert_recheck_matches(text, at..at + 1, "");
+    }
+    
+    #[test]
+    fn recheck_on_the_last_line() {
+        assert_recheck_matches(TEXT, TEXT.len()..TEXT.len(), ", with wrods");
+        let at = TEXT.rfind("fine").unwrap();
+        assert_recheck_matches(TEXT, at..TEXT.len(), "finne");
+    }
+    
+    #[test]
+    fn chunks_check_like_one_thread() {
+        // Three-line paragraphs, so chunk boundaries fall inside sentences
+        // and between the two "a"s of a repetition
+        let paragraph = "This sentance goes on\nacross the break and a\na mispeled word. Next\n";
+        let text = paragraph.repeat(CHUNK_LINES);
+        
+        let mut checker = checker();
+        checker.set_max_threads(1).unwrap();
+        let single = checker.check_text(&text, None, None);
+        checker.set_max_threads(4).unwrap();
+        let parallel = checker.check_text(&text, None, None);
+        assert_eq!(key(&single), key(&parallel));
+        
+        // Every paragraph is checked like the first, which no boundary touches
+        let first = checker.check_text(paragraph, None, None);
+        assert!(first.misspelled_words > 0 && !first.diagnostics.is_empty());
+        assert_eq!(single.misspelled_words, first.misspelled_words * CHUNK_LINES);
+        assert_eq!(single.diagnostics.len(), first.diagnostics.len() * CHUNK_LINES);
+        for (i, word) in single.words.iter().enumerate() {
+            let expected = &first.words[i % first.words.len()];
+            assert_eq!((&word.word, word.column, word.is_correct), (&expected.word, expected.column, expected.is_correct));
+        }
+    }
+}
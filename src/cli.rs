@@ -5,10 +5,16 @@ use colored::*;
 #[cfg(feature = "cli")]
 use indicatif::{ProgressBar, ProgressStyle};
 #[cfg(feature = "cli")]
-use spellchecker::{checker::SpellChecker, language::Language, util::*};
+use spellchecker::{cache::CacheStatus, checker::{Diagnostic, DocumentAnalysis, SpellChecker, Suggestion, WordType}, dictionary::Dictionary, language::Language, policy::CheckPolicy, util::*};
+#[cfg(feature = "cli")]
+use std::io::BufRead;
 #[cfg(feature = "cli")]
 use std::path::PathBuf;
 
+// Inputs larger than this are checked line by line instead of loaded whole
+#[cfg(feature = "cli")]
+const STREAMING_THRESHOLD: u64 = 64 * 1024 * 1024;
+
 #[cfg(feature = "cli")]
 #[derive(Parser)]
 #[command(name = "spellchecker-cli")]
@@ -45,6 +51,12 @@ enum Commands {
         /// Output JSON format
         #[arg(long)]
         json: bool,
+        
+        #[command(flatten)]
+        grammar: GrammarArgs,
+        
+        #[command(flatten)]
+        policy: PolicyArgs,
     },
     
     /// Analyze word frequency
@@ -95,6 +107,12 @@ enum Commands {
         /// Output JSON format
         #[arg(long)]
         json: bool,
+        
+        #[command(flatten)]
+        grammar: GrammarArgs,
+        
+        #[command(flatten)]
+        policy: PolicyArgs,
     },
     
     /// Interactive mode
@@ -103,6 +121,167 @@ enum Commands {
         #[arg(short, long, default_value = "eng")]
         language: String,
     },
+    
+    /// Precompile dictionary caches for faster startup
+    BuildCache {
+        /// Only build the cache for this language (default: all languages)
+        #[arg(short, long)]
+        language: Option<String>,
+        
+        /// Rebuild even if the cache is up to date
+        #[arg(short, long)]
+        force: bool,
+    },
+}
+
+#[cfg(feature = "cli")]
+#[derive(clap::Args)]
+struct GrammarArgs {
+    /// Skip grammar rules (repeated words, a/an, ...)
+    #[arg(long)]
+    no_grammar: bool,
+    
+    /// Only report diagnostics from these rule ids
+    #[arg(long = "rule", value_name = "RULE_ID")]
+    rules: Vec<String>,
+}
+
+#[cfg(feature = "cli")]
+impl GrammarArgs {
+    fn filter(&self, analysis: &mut DocumentAnalysis) {
+        analysis.diagnostics.retain(|d| self.keeps(d));
+    }
+    
+    fn keeps(&self, diagnostic: &Diagnostic) -> bool {
+        self.rules.is_empty() || self.rules.iter().any(|id| id == diagnostic.rule_id)
+    }
+}
+
+#[cfg(feature = "cli")]
+#[derive(clap::Args)]
+struct PolicyArgs {
+    /// Load checking rules from a JSON policy file
+    #[arg(long, value_name = "FILE")]
+    policy: Option<PathBuf>,
+    
+    /// Only report misspellings at or above this confidence (0 to 1)
+    #[arg(long, value_name = "CONFIDENCE")]
+    min_confidence: Option<f32>,
+    
+    /// Only check these word types (normal, proper-noun, acronym, code-identifier, ...)
+    #[arg(long, value_name = "TYPES", value_delimiter = ',')]
+    only_types: Vec<String>,
+    
+    /// Offer at most this many suggestions per word
+    #[arg(long, value_name = "N")]
+    max_suggestions: Option<usize>,
+    
+    /// Accept words typed without their accents ("ete" for "été")
+    #[arg(long)]
+    lenient_accents: bool,
+}
+
+#[cfg(feature = "cli")]
+impl PolicyArgs {
+    fn build(&self) -> anyhow::Result<CheckPolicy> {
+        let mut policy = match &self.policy {
+            Some(path) => CheckPolicy::load(path)?,
+            None => CheckPolicy::default(),
+        };
+        
+        if let Some(min_confidence) = self.min_confidence {
+            policy.min_confidence = min_confidence.clamp(0.0, 1.0);
+        }
+        if let Some(max_suggestions) = self.max_suggestions {
+            policy.max_suggestions = max_suggestions;
+        }
+        if !self.only_types.is_empty() {
+            let types = self.only_types.iter()
+                .map(|name| name.parse())
+                .collect::<anyhow::Result<Vec<WordType>>>()?;
+            policy.check_only(&types);
+        }
+        
+        Ok(policy)
+    }
+}
+
+#[cfg(feature = "cli")]
+fn join_suggestions(suggestions: &[Suggestion]) -> String {
+    suggestions.iter().map(|s| s.word.as_str()).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(feature = "cli")]
+fn print_diagnostics(analysis: &DocumentAnalysis) {
+    if analysis.diagnostics.is_empty() {
+        return;
+    }
+    
+    println!("\n{}", "Grammar issues:".yellow().bold().underline());
+    for diagnostic in &analysis.diagnostics {
+        print_diagnostic(diagnostic);
+    }
+}
+
+#[cfg(feature = "cli")]
+fn print_diagnostic(diagnostic: &Diagnostic) {
+    println!("\n  Line {}: {} [{}]", diagnostic.line, diagnostic.message, diagnostic.rule_id.dimmed());
+    match diagnostic.replacement.as_deref() {
+        Some("") => println!("    💡 Remove '{}'", diagnostic.text),
+        Some(replacement) => println!("    💡 Replace with: {}", replacement.green()),
+        None => {}
+    }
+}
+
+// Check input without loading it at once, printing problems as they are
+// found. JSON output is one report object per line, then the totals.
+#[cfg(feature = "cli")]
+fn check_streaming(
+    checker: &SpellChecker,
+    reader: impl BufRead,
+    filename: Option<&str>,
+    suggest: bool,
+    json: bool,
+    grammar: &GrammarArgs,
+) -> anyhow::Result<()> {
+    let mut stream = checker.check_stream(reader, filename)?;
+    let mut diagnostics_shown = 0;
+    
+    for report in stream.by_ref() {
+        let mut report = report?;
+        report.diagnostics.retain(|d| grammar.keeps(d));
+        diagnostics_shown += report.diagnostics.len();
+        
+        if json {
+            println!("{}", serde_json::to_string(&report)?);
+            continue;
+        }
+        
+        for word in &report.misspelled {
+            print!("Line {}: '{}'", word.line, word.word.red());
+            if suggest && !word.suggestions.is_empty() {
+                print!(" → {}", join_suggestions(&word.suggestions).green());
+            }
+            println!();
+        }
+        for diagnostic in &report.diagnostics {
+            print_diagnostic(diagnostic);
+        }
+    }
+    
+    let totals = stream.totals();
+    if json {
+        println!("{}", serde_json::to_string(totals)?);
+    } else {
+        println!("\n{}", "Results:".bold().underline());
+        println!("  📄 Lines checked: {}", totals.lines_checked);
+        println!("  📊 Total words: {}", totals.total_words);
+        println!("  ❌ Misspelled: {}", totals.misspelled_words);
+        println!("  ✅ Accuracy: {:.1}%", totals.accuracy());
+        println!("  ✎ Grammar issues: {}", diagnostics_shown);
+    }
+    
+    Ok(())
 }
 
 #[cfg(feature = "cli")]
@@ -110,8 +289,7 @@ fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
     
     match cli.command {
-        Commands::Check { file, language, suggest, stats, case_sensitive, json } => {
-            let content = std::fs::read_to_string(&file)?;
+        Commands::Check { file, language, suggest, stats, case_sensitive, json, grammar, policy } => {
             let language = Language::from_code(&language);
             
             if !json {
@@ -122,8 +300,21 @@ fn main() -> anyhow::Result<()> {
             let mut checker = SpellChecker::new(language)?;
             checker.enable_suggestions(suggest);
             checker.set_case_sensitive(case_sensitive);
+            checker.set_accent_lenient(policy.lenient_accents);
+            checker.set_policy(policy.build()?);
+            checker.enable_grammar(!grammar.no_grammar);
+            
+            if std::fs::metadata(&file)?.len() > STREAMING_THRESHOLD {
+                if !json {
+                    println!("{}", "Large input: checking line by line".dimmed());
+                }
+                let reader = std::io::BufReader::new(std::fs::File::open(&file)?);
+                return check_streaming(&checker, reader, Some(&file.to_string_lossy()), suggest, json, &grammar);
+            }
             
-            let analysis = checker.check_document(&content, Some(&file.to_string_lossy()));
+            let content = std::fs::read_to_string(&file)?;
+            let mut analysis = checker.check_document(&content, Some(&file.to_string_lossy()));
+            grammar.filter(&mut analysis);
             
             if json {
                 println!("{}", serde_json::to_string_pretty(&analysis)?);
@@ -140,7 +331,7 @@ fn main() -> anyhow::Result<()> {
                     for word in analysis.words.iter().filter(|w| !w.is_correct) {
                         println!("\n  Line {}: '{}'", word.line, word.word.red().bold());
                         if suggest && !word.suggestions.is_empty() {
-                            println!("    💡 Suggestions: {}", word.suggestions.join(", ").green());
+                            println!("    💡 Suggestions: {}", join_suggestions(&word.suggestions).green());
                         }
                     }
                     println!("\n{}", format!("Total errors: {}", analysis.misspelled_words).red());
@@ -148,6 +339,8 @@ fn main() -> anyhow::Result<()> {
                     println!("\n{}", "✓ No spelling errors found!".green().bold());
                 }
                 
+                print_diagnostics(&analysis);
+                
                 if stats {
                     let reading_time = reading_time(&content);
                     let is_cjk = matches!(language, Language::Chinese | Language::Japanese | Language::Korean);
@@ -226,8 +419,8 @@ fn main() -> anyhow::Result<()> {
             );
             
             let mut dict_content = String::new();
-            for word in unique_words {
-                dict_content.push_str(&word);
+            for word in &unique_words {
+                dict_content.push_str(word);
                 dict_content.push('\n');
                 pb.inc(1);
             }
@@ -242,44 +435,26 @@ fn main() -> anyhow::Result<()> {
             println!("   Min word length: {}", min_length);
         }
         
-        Commands::Stdin { language, suggest, json } => {
-            use std::io::{self, Read};
-            
-            let mut content = String::new();
-            io::stdin().read_to_string(&mut content)?;
-            
-            if content.trim().is_empty() {
-                eprintln!("No input provided");
-                return Ok(());
-            }
-            
+        Commands::Stdin { language, suggest, json, grammar, policy } => {
             let language = Language::from_code(&language);
             let mut checker = SpellChecker::new(language)?;
             checker.enable_suggestions(suggest);
+            checker.set_accent_lenient(policy.lenient_accents);
+            checker.set_policy(policy.build()?);
+            checker.enable_grammar(!grammar.no_grammar);
             
-            let analysis = checker.check_document(&content, None);
+            // Piped input may never end, so it is always checked as it arrives
+            let mut reader = std::io::stdin().lock();
+            if reader.fill_buf()?.is_empty() {
+                eprintln!("No input provided");
+                return Ok(());
+            }
             
-            if json {
-                println!("{}", serde_json::to_string_pretty(&analysis)?);
-            } else {
+            if !json {
                 println!("{}", "Spell Check Results:".bold());
                 println!("Language: {}", language.name());
-                println!("Words checked: {}", analysis.total_words);
-                println!("Unique words: {}", analysis.unique_words);
-                println!("Errors found: {}", analysis.misspelled_words);
-                println!("Accuracy: {:.1}%", analysis.accuracy);
-                
-                if analysis.misspelled_words > 0 {
-                    println!("\nErrors:");
-                    for word in analysis.words.iter().filter(|w| !w.is_correct) {
-                        print!("Line {}: '{}'", word.line, word.word.red());
-                        if suggest && !word.suggestions.is_empty() {
-                            print!(" → {}", word.suggestions.join(", ").green());
-                        }
-                        println!();
-                    }
-                }
             }
+            check_streaming(&checker, reader, None, suggest, json, &grammar)?;
         }
         
         Commands::Interactive { language } => {
@@ -313,8 +488,7 @@ fn main() -> anyhow::Result<()> {
                     break;
                 }
                 
-                if input.starts_with("check ") {
-                    let text = &input[6..];
+                if let Some(text) = input.strip_prefix("check ") {
                     let analysis = checker.check_document(text, None);
                     
                     println!("Words: {}, Errors: {}, Accuracy: {:.1}%", 
@@ -324,19 +498,17 @@ fn main() -> anyhow::Result<()> {
                         for word in analysis.words.iter().filter(|w| !w.is_correct) {
                             println!("  '{}' at line {}", word.word.red(), word.line);
                             if !word.suggestions.is_empty() {
-                                println!("    Suggestions: {}", word.suggestions.join(", ").green());
+                                println!("    Suggestions: {}", join_suggestions(&word.suggestions).green());
                             }
                         }
                     }
-                } else if input.starts_with("add ") {
-                    let word = &input[4..];
+                } else if let Some(word) = input.strip_prefix("add ") {
                     if let Err(e) = checker.add_word_to_dictionary(word) {
                         println!("Error: {}", e);
                     } else {
                         println!("Added '{}' to dictionary", word.green());
                     }
-                } else if input.starts_with("ignore ") {
-                    let word = &input[7..];
+                } else if let Some(word) = input.strip_prefix("ignore ") {
                     if let Err(e) = checker.ignore_word(word) {
                         println!("Error: {}", e);
                     } else {
@@ -347,6 +519,33 @@ fn main() -> anyhow::Result<()> {
                 }
             }
         }
+        
+        Commands::BuildCache { language, force } => {
+            let languages = match language {
+                Some(code) => vec![Language::from_code(&code)],
+                None => Language::all()
+                    .into_iter()
+                    .filter(|lang| *lang != Language::AutoDetect)
+                    .collect(),
+            };
+            
+            for language in languages {
+                match Dictionary::build_cache(language, force) {
+                    Ok(CacheStatus::Built(path)) => {
+                        println!("✅ {}: built {}", language.name(), path.display());
+                    }
+                    Ok(CacheStatus::UpToDate(path)) => {
+                        println!("✓ {}: up to date ({})", language.name(), path.display());
+                    }
+                    Ok(CacheStatus::NoSources) => {
+                        println!("{}", format!("- {}: no dictionary files found", language.name()).dimmed());
+                    }
+                    Err(e) => {
+                        eprintln!("{}", format!("❌ {}: {}", language.name(), e).red());
+                    }
+                }
+            }
+        }
     }
     
     Ok(())
@@ -0,0 +1,189 @@
+use crate::language::Language;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Parts shorter than this are too likely to match by accident ("in", "en")
+pub const MIN_PART_CHARS: usize = 3;
+/// Parts from word lists that don't mark compound parts must be at least
+/// this long, so run-together short words ("dasist") aren't accepted
+pub const MIN_UNMARKED_PART_CHARS: usize = 4;
+/// Real compounds rarely have more parts; allowing more accepts too many
+/// run-together typos
+pub const MAX_PARTS: usize = 3;
+// Longer inputs aren't worth the search
+const MAX_WORD_CHARS: usize = 64;
+
+/// Where a part sits in a compound, which Hunspell dictionaries restrict
+/// with COMPOUNDBEGIN, COMPOUNDMIDDLE and COMPOUNDEND
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompoundPosition {
+    Begin,
+    Middle,
+    End,
+}
+
+/// One dictionary word inside a closed compound
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CompoundPart {
+    pub word: String,
+    /// Linking element joining this part to the next ("s" in "Arbeitszimmer")
+    pub linking: Option<&'static str>,
+}
+
+/// Splits closed compounds into dictionary words, allowing the linking
+/// elements a language puts between them
+#[derive(Debug, Clone)]
+pub struct CompoundSplitter {
+    linking: &'static [&'static str],
+}
+
+impl CompoundSplitter {
+    /// Only languages that write compounds closed get a splitter
+    pub fn for_language(language: Language) -> Option<Self> {
+        let linking: &'static [&'static str] = match language {
+            Language::German => &["s", "es", "n", "en"],
+            Language::Afrikaans => &["s", "e"],
+            _ => return None,
+        };
+        
+        Some(Self { linking })
+    }
+    
+    /// Split `word` into two or more parts that `is_part` accepts in their
+    /// position, preferring the longest first part. `word` should already be
+    /// normalized.
+    pub fn split(&self, word: &str, is_part: impl Fn(&str, CompoundPosition) -> bool) -> Option<Vec<CompoundPart>> {
+        let boundaries: Vec<usize> = word
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(word.len()))
+            .collect();
+        
+        if boundaries.len() - 1 > MAX_WORD_CHARS || boundaries.len() - 1 < MIN_PART_CHARS * 2 {
+            return None;
+        }
+        
+        let mut search = Search {
+            word,
+            boundaries: &boundaries,
+            linking: self.linking,
+            is_part: &is_part,
+            dead_ends: HashSet::new(),
+        };
+        
+        let mut parts = Vec::new();
+        search.split_from(0, &mut parts).then_some(parts)
+    }
+    
+    /// Whether `word` can end a compound, as a part or a compound of them
+    pub fn accepts(&self, word: &str, is_part: impl Fn(&str, CompoundPosition) -> bool) -> bool {
+        is_part(word, CompoundPosition::End) || self.split(word, is_part).is_some()
+    }
+    
+    /// Whether `prefix` can start a compound, maybe with a trailing linking
+    /// element
+    pub fn accepts_prefix(&self, prefix: &str, is_part: impl Fn(&str, CompoundPosition) -> bool) -> bool {
+        let starts = |stem: &str| is_part(stem, CompoundPosition::Begin) || self.split(stem, &is_part).is_some();
+        starts(prefix)
+            || self.linking.iter().any(|link| {
+                prefix
+                    .strip_suffix(link)
+                    .is_some_and(|stem| stem.chars().count() >= MIN_PART_CHARS && starts(stem))
+            })
+    }
+}
+
+struct Search<'a, F: Fn(&str, CompoundPosition) -> bool> {
+    word: &'a str,
+    // Byte offset of every char boundary, including the end of the word
+    boundaries: &'a [usize],
+    linking: &'static [&'static str],
+    is_part: &'a F,
+    // (char index, parts so far) combinations already known to fail
+    dead_ends: HashSet<(usize, usize)>,
+}
+
+impl<F: Fn(&str, CompoundPosition) -> bool> Search<'_, F> {
+    fn split_from(&mut self, start: usize, parts: &mut Vec<CompoundPart>) -> bool {
+        let char_count = self.boundaries.len() - 1;
+        if parts.len() >= MAX_PARTS || self.dead_ends.contains(&(start, parts.len())) {
+            return false;
+        }
+        
+        for end in (start + MIN_PART_CHARS..=char_count).rev() {
+            let part = &self.word[self.boundaries[start]..self.boundaries[end]];
+            if end == char_count {
+                // The whole word on its own isn't a compound
+                if !parts.is_empty() && (self.is_part)(part, CompoundPosition::End) {
+                    parts.push(CompoundPart { word: part.to_string(), linking: None });
+                    return true;
+                }
+                continue;
+            }
+            
+            let position = if parts.is_empty() { CompoundPosition::Begin } else { CompoundPosition::Middle };
+            if parts.len() + 1 >= MAX_PARTS || !(self.is_part)(part, position) {
+                continue;
+            }
+            
+            let rest = &self.word[self.boundaries[end]..];
+            let links = std::iter::once(None).chain(
+                self.linking
+                    .iter()
+                    .filter(|link| rest.starts_with(*link) && rest.len() > link.len())
+                    .map(|link| Some(*link)),
+            );
+            
+            for link in links {
+                let next = end + link.map_or(0, |l| l.chars().count());
+                if char_count - next < MIN_PART_CHARS {
+                    continue;
+                }
+                
+                parts.push(CompoundPart { word: part.to_string(), linking: link });
+                if self.split_from(next, parts) {
+                    return true;
+                }
+                parts.pop();
+            }
+        }
+        
+        self.dead_ends.insert((start, parts.len()));
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn split(words: &[&str], word: &str) -> Option<Vec<String>> {
+        let splitter = CompoundSplitter::for_language(Language::German).unwrap();
+        let parts = splitter.split(word, |part, _| words.contains(&part))?;
+        Some(parts.into_iter().map(|part| part.word).collect())
+    }
+    
+    #[test]
+    fn compounds_split_at_linking_elements() {
+        assert_eq!(split(&["arbeit", "zimmer"], "arbeitszimmer").unwrap(), ["arbeit", "zimmer"]);
+    }
+    
+    #[test]
+    fn parts_are_capped() {
+        let words = ["haus", "tür", "schloss", "feder"];
+        assert!(split(&words, "haustürschloss").is_some());
+        assert!(split(&words, "haustürschlossfeder").is_none());
+    }
+    
+    #[test]
+    fn parts_must_be_allowed_in_their_position() {
+        let splitter = CompoundSplitter::for_language(Language::German).unwrap();
+        let is_part = |part: &str, position| match part {
+            "haus" => position == CompoundPosition::Begin,
+            "tür" => position == CompoundPosition::End,
+            _ => false,
+        };
+        assert!(splitter.split("haustür", is_part).is_some());
+        assert!(splitter.split("türhaus", is_part).is_none());
+    }
+}
@@ -1,113 +1,455 @@
+use crate::cache::{self, CacheStatus};
+use crate::compound::{self, CompoundPart, CompoundPosition, CompoundSplitter};
+use crate::hunspell::{self, CompoundRules};
 use crate::language::{Language, LanguageManager};
+use crate::phonetic::{PhoneticEncoder, PhoneticIndex};
+use crate::segment::{self, Segment};
+use crate::symspell::SymSpellIndex;
+use crate::util;
+use crate::wordlist::WordList;
 use dashmap::DashMap;
-use regex::Regex;
-use std::collections::HashSet;
+use once_cell::sync::OnceCell;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+// Word data loaded from dictionary files. It never changes after loading,
+// so every clone of a Dictionary shares the same copy.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DictionaryData {
+    pub(crate) words: WordList,
+    pub(crate) no_suggest_words: HashSet<String>,
+    pub(crate) forbidden_words: HashSet<String>,
+    pub(crate) compound_rules: CompoundRules,
+}
+
+// Files a dictionary is compiled from
+struct DictionarySources {
+    word_list: Option<PathBuf>,
+    hunspell: Option<(PathBuf, PathBuf)>,
+}
+
+impl DictionarySources {
+    fn find(language_manager: &LanguageManager, language: &Language) -> Self {
+        Self {
+            word_list: language_manager.get_dictionary_path(language),
+            hunspell: language_manager.get_hunspell_paths(language),
+        }
+    }
+    
+    // Load order, which is also the order the cache records them in
+    fn paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self.word_list.iter().cloned().collect();
+        if let Some((aff_path, dic_path)) = &self.hunspell {
+            paths.push(aff_path.clone());
+            paths.push(dic_path.clone());
+        }
+        paths
+    }
+    
+    fn primary_path(&self) -> Option<PathBuf> {
+        self.word_list
+            .clone()
+            .or_else(|| self.hunspell.as_ref().map(|(_, dic_path)| dic_path.clone()))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Dictionary {
-    words: HashSet<String>,
+    data: Arc<DictionaryData>,
+    // Words added by the user stay mutable on top of the shared data, as do
+    // dictionary words the user removed
+    user_words: HashSet<String>,
+    removed_words: HashSet<String>,
     ignored_words: HashSet<String>,
-    word_pattern: Regex,
+    // Shared between clones so the index is only ever built once per
+    // dictionary. They cover the shared data only; the few user words are
+    // looked through at lookup time, so adding one doesn't rebuild them.
+    suggestion_index: Arc<OnceCell<SymSpellIndex>>,
+    phonetic_index: Arc<OnceCell<PhoneticIndex>>,
+    // Accented words keyed by their folded spelling ("geeet" -> ["geëet"])
+    accent_index: Arc<OnceCell<HashMap<String, Vec<String>>>>,
+    compound_splitter: Option<CompoundSplitter>,
+    phonetic_encoder: Option<PhoneticEncoder>,
     min_word_length: usize,
     language: Language,
     is_loaded: bool,
     word_count_cache: usize,
     ignored_count_cache: usize,
+    max_frequency: u64,
     file_path: Option<PathBuf>,
 }
 
 impl Dictionary {
     pub fn new(language: Language) -> Self {
-        let word_pattern = Self::get_word_pattern_for_language(&language);
-        
         Self {
-            words: HashSet::new(),
+            data: Arc::new(DictionaryData::default()),
+            user_words: HashSet::new(),
+            removed_words: HashSet::new(),
             ignored_words: HashSet::new(),
-            word_pattern,
+            suggestion_index: Arc::new(OnceCell::new()),
+            phonetic_index: Arc::new(OnceCell::new()),
+            accent_index: Arc::new(OnceCell::new()),
+            compound_splitter: CompoundSplitter::for_language(language),
+            phonetic_encoder: PhoneticEncoder::for_language(language),
             min_word_length: 2,
             language,
             is_loaded: false,
             word_count_cache: 0,
             ignored_count_cache: 0,
+            max_frequency: 0,
             file_path: None,
         }
     }
     
-    fn get_word_pattern_for_language(language: &Language) -> Regex {
-        match language {
-            Language::Chinese | Language::Japanese => {
-                Regex::new(r"[\p{Han}\p{Hiragana}\p{Katakana}a-zA-Z0-9'-]+").unwrap()
-            }
-            Language::Korean => {
-                Regex::new(r"[\p{Hangul}a-zA-Z0-9'-]+").unwrap()
-            }
-            Language::Russian => {
-                Regex::new(r"[\p{Cyrillic}a-zA-Z0-9'-]+").unwrap()
-            }
-            _ => {
-                Regex::new(r"[\p{L}0-9'-]+").unwrap()
-            }
-        }
-    }
-    
     pub fn load(&mut self) -> anyhow::Result<()> {
         if self.is_loaded {
             return Ok(());
         }
         
         let language_manager = LanguageManager::new();
+        let sources = DictionarySources::find(&language_manager, &self.language);
+        let paths = sources.paths();
+        
+        // Prefer the compiled cache; fall back to parsing the source files
+        match cache::load(&self.language, &paths).filter(|_| !paths.is_empty()) {
+            Some(data) => {
+                self.data = Arc::new(data);
+                self.file_path = sources.primary_path();
+            }
+            None => {
+                self.load_sources(&sources)?;
+                if !paths.is_empty() {
+                    // Without a writable cache directory we just parse again next time
+                    cache::store(&self.language, &paths, &self.data).ok();
+                }
+            }
+        }
         
-        // Try to load main dictionary
-        if let Some(dict_path) = language_manager.get_dictionary_path(&self.language) {
-            println!("Loading dictionary for {} from: {:?}", self.language.name(), dict_path);
-            self.load_file(&dict_path)?;
-            self.file_path = Some(dict_path);
-        } else {
-            println!("No dictionary file found for {}. Creating empty dictionary.", self.language.name());
+        if self.file_path.is_none() {
+            eprintln!("Warning: No dictionary file found for {}. Using an empty dictionary.", self.language.name());
         }
         
-        // Load user-added words
+        // Load user-added and removed words
         self.load_user_words();
+        self.removed_words = self.load_word_file("removed");
         
         // Load ignored words
         self.load_ignored_words();
         
         self.is_loaded = true;
-        self.word_count_cache = self.words.len();
+        self.words_changed();
         self.ignored_count_cache = self.ignored_words.len();
         
-        println!("Loaded {} words ({} ignored) for {}", 
-            self.word_count_cache, self.ignored_count_cache, self.language.name());
+        Ok(())
+    }
+    
+    fn load_sources(&mut self, sources: &DictionarySources) -> anyhow::Result<()> {
+        if let Some(dict_path) = &sources.word_list {
+            self.load_file(dict_path)?;
+        }
+        
+        // Hunspell pairs are loaded on top of any flat word list
+        if let Some((aff_path, dic_path)) = &sources.hunspell {
+            self.load_hunspell(aff_path, dic_path)?;
+        }
         
+        self.file_path = sources.primary_path();
         Ok(())
     }
     
+    /// Compile the binary cache for `language` unless an up-to-date one exists
+    pub fn build_cache(language: Language, force: bool) -> anyhow::Result<CacheStatus> {
+        let sources = DictionarySources::find(&LanguageManager::new(), &language);
+        let paths = sources.paths();
+        
+        if paths.is_empty() {
+            return Ok(CacheStatus::NoSources);
+        }
+        
+        if !force && cache::load(&language, &paths).is_some() {
+            return Ok(CacheStatus::UpToDate(cache::cache_path(&language)));
+        }
+        
+        let mut dict = Dictionary::new(language);
+        dict.load_sources(&sources)?;
+        cache::store(&language, &paths, &dict.data).map(CacheStatus::Built)
+    }
+    
     pub fn load_file(&mut self, path: &Path) -> anyhow::Result<()> {
         let file = File::open(path)?;
         let reader = BufReader::new(file);
         
-        let mut new_words = HashSet::new();
+        let mut new_words = Vec::new();
         
         for line in reader.lines() {
             let line = line?;
-            let word = line.trim();
+            let (word, count) = split_frequency(line.trim());
             
             if !word.is_empty() && word.len() >= self.min_word_length {
                 let normalized = self.normalize_word(word);
-                new_words.insert(normalized);
+                new_words.push((normalized, count));
             }
         }
         
-        self.words.extend(new_words);
-        self.word_count_cache = self.words.len();
+        let data = Arc::make_mut(&mut self.data);
+        data.words = data.words.union_counts(new_words)?;
+        self.words_changed();
+        
+        Ok(())
+    }
+    
+    /// Load a Hunspell `.aff`/`.dic` pair, expanding affix rules into surface forms
+    pub fn load_hunspell(&mut self, aff_path: &Path, dic_path: &Path) -> anyhow::Result<()> {
+        let expanded = hunspell::load_pair(aff_path, dic_path, |word| self.normalize_word(word))?;
+        
+        let min_word_length = self.min_word_length;
+        let data = Arc::make_mut(&mut self.data);
+        
+        // Free the expanded forms before merging, so both never peak together
+        let forms = WordList::from_sorted(expanded.words.iter().filter(|w| w.len() >= min_word_length))?;
+        drop(expanded.words);
+        data.words = data.words.merge(&forms)?;
+        data.no_suggest_words.extend(expanded.no_suggest);
+        data.forbidden_words.extend(expanded.forbidden);
+        
+        let compounds = expanded.compounds;
+        data.compound_rules.min_length = compounds.min_length;
+        data.compound_rules.anywhere.extend(compounds.anywhere);
+        data.compound_rules.begin.extend(compounds.begin);
+        data.compound_rules.middle.extend(compounds.middle);
+        data.compound_rules.end.extend(compounds.end);
+        
+        self.words_changed();
         
         Ok(())
     }
     
+    // The shared data changed, so the indexes over it are out of date
+    fn words_changed(&mut self) {
+        self.count_words();
+        self.max_frequency = self.data.words.max_frequency();
+        self.suggestion_index = Arc::new(OnceCell::new());
+        self.phonetic_index = Arc::new(OnceCell::new());
+        self.accent_index = Arc::new(OnceCell::new());
+    }
+    
+    fn count_words(&mut self) {
+        self.word_count_cache = self.data.words.len()
+            + self.user_words.iter().filter(|w| !self.data.words.contains(w)).count()
+            - self.removed_words.iter().filter(|w| self.data.words.contains(w)).count();
+    }
+    
+    /// Symmetric-delete index over the suggestible words of the dictionary
+    /// files, built on first use. User words aren't in it.
+    pub fn suggestion_index(&self) -> &SymSpellIndex {
+        self.suggestion_index.get_or_init(|| {
+            let words = self.data.words.words();
+            SymSpellIndex::new(words.iter().filter(|w| self.is_suggestible(w)).map(|w| w.as_str()))
+        })
+    }
+    
+    // Suggestible dictionary and user words within `max_distance` edits of
+    // `word`, closest first
+    fn lookup(&self, word: &str, max_distance: usize) -> Vec<(String, usize)> {
+        let max_distance = max_distance.min(self.suggestion_index().max_distance());
+        let length = word.chars().count();
+        let mut candidates: Vec<(String, usize)> = self.suggestion_index()
+            .lookup(word, max_distance)
+            .into_iter()
+            .filter(|(candidate, _)| !self.removed_words.contains(*candidate))
+            .map(|(candidate, distance)| (candidate.to_string(), distance))
+            .collect();
+        
+        for user_word in self.user_words.iter().filter(|w| w.chars().count().abs_diff(length) <= max_distance) {
+            let distance = crate::keyboard::osa_distance(word, user_word);
+            if distance <= max_distance && self.is_suggestible(user_word) {
+                candidates.push((user_word.clone(), distance));
+            }
+        }
+        
+        candidates.sort_unstable_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        candidates.dedup();
+        candidates
+    }
+    
+    /// Suggestible words that sound like `word`, however far apart their
+    /// spellings are. Empty for languages without phonetic rules.
+    pub fn phonetic_candidates(&self, word: &str) -> Vec<String> {
+        let Some(encoder) = self.phonetic_encoder else {
+            return Vec::new();
+        };
+        
+        let index = self.phonetic_index.get_or_init(|| {
+            let words = self.data.words.words();
+            PhoneticIndex::new(encoder, words.iter().filter(|w| self.is_suggestible(w)).map(|w| w.as_str()))
+        });
+        
+        let normalized = self.normalize_word(word);
+        let keys = encoder.keys(&normalized);
+        let mut candidates: Vec<String> = index
+            .lookup(&normalized)
+            .into_iter()
+            .filter(|candidate| !self.removed_words.contains(*candidate))
+            .map(String::from)
+            .collect();
+        candidates.extend(
+            self.user_words
+                .iter()
+                .filter(|w| self.is_suggestible(w) && encoder.keys(w).iter().any(|key| keys.contains(key)))
+                .cloned(),
+        );
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+    
+    /// Suggestible accented words that `word` spells without its accents
+    /// ("ete" -> "été"). A hyphen may stand in for a diaeresis, as in
+    /// Afrikaans "ge-eet" for "geëet".
+    pub fn accent_matches(&self, word: &str) -> Vec<String> {
+        let index = self.accent_index.get_or_init(|| {
+            let words = self.data.words.words();
+            let mut index: HashMap<String, Vec<String>> = HashMap::new();
+            for word in &words {
+                if util::has_diacritics(word) && self.is_suggestible(word) {
+                    index.entry(accent_key(word)).or_default().push(word.clone());
+                }
+            }
+            for matches in index.values_mut() {
+                matches.sort_unstable();
+                matches.dedup();
+            }
+            index
+        });
+        
+        let normalized = self.normalize_word(word);
+        let key = accent_key(&normalized);
+        let mut matches: Vec<String> = index
+            .get(&key)
+            .into_iter()
+            .flatten()
+            .filter(|m| **m != normalized && !self.removed_words.contains(*m))
+            .cloned()
+            .collect();
+        matches.extend(self.user_words.iter().filter(|w| {
+            **w != normalized && util::has_diacritics(w) && self.is_suggestible(w) && accent_key(w) == key
+        }).cloned());
+        matches.sort_unstable();
+        matches.dedup();
+        matches
+    }
+    
+    /// The accented spelling of `word`, if exactly one dictionary word matches it
+    pub fn restore_accents(&self, word: &str) -> Option<String> {
+        let mut matches = self.accent_matches(word);
+        if matches.len() == 1 {
+            matches.pop()
+        } else {
+            None
+        }
+    }
+    
+    /// Every dictionary word within `max_distance` edits, closest first.
+    /// For compounding languages this includes compounds with one part corrected.
+    pub fn suggestion_candidates(&self, word: &str, max_distance: usize) -> Vec<(String, usize)> {
+        let word = self.normalize_word(word);
+        let mut candidates = self.lookup(&word, max_distance);
+        
+        if self.compound_splitter.is_some() {
+            candidates.extend(self.compound_candidates(&word, max_distance));
+            // At the same distance, whole dictionary words beat built-up compounds
+            candidates.sort_by_cached_key(|(candidate, distance)| {
+                (*distance, !self.has_word(candidate), candidate.clone())
+            });
+            
+            let mut seen = HashSet::new();
+            candidates.retain(|(candidate, _)| seen.insert(candidate.clone()));
+        }
+        
+        candidates
+    }
+    
+    // Split the word in two and correct whichever half isn't already valid
+    fn compound_candidates(&self, word: &str, max_distance: usize) -> Vec<(String, usize)> {
+        let Some(splitter) = &self.compound_splitter else {
+            return Vec::new();
+        };
+        
+        let is_part = |part: &str, position| self.is_compound_part(part, position);
+        let mut candidates = Vec::new();
+        
+        for (i, _) in word.char_indices().skip(compound::MIN_PART_CHARS) {
+            let (left, right) = word.split_at(i);
+            if right.chars().count() < compound::MIN_PART_CHARS {
+                break;
+            }
+            
+            if splitter.accepts_prefix(left, is_part) {
+                for (candidate, distance) in self.lookup(right, max_distance) {
+                    candidates.push((format!("{}{}", left, candidate), distance));
+                }
+            }
+            
+            if splitter.accepts(right, is_part) {
+                for (candidate, distance) in self.lookup(left, max_distance) {
+                    candidates.push((format!("{}{}", candidate, right), distance));
+                }
+            }
+        }
+        
+        // A corrected half can be too short to stand as a part on its own
+        candidates.retain(|(candidate, _)| splitter.split(candidate, is_part).is_some());
+        candidates
+    }
+    
+    /// The parts a word was accepted as, if it is a compound rather than a
+    /// dictionary word in its own right
+    pub fn compound_split(&self, word: &str) -> Option<Vec<CompoundPart>> {
+        let splitter = self.compound_splitter.as_ref()?;
+        let normalized = self.normalize_word(word);
+        
+        if self.has_word(&normalized) {
+            return None;
+        }
+        
+        splitter.split(&normalized, |part, position| self.is_compound_part(part, position))
+    }
+    
+    /// Split a run of Chinese or Japanese text into dictionary words, marking
+    /// the pieces no word covers
+    pub fn segment(&self, text: &str) -> Vec<Segment> {
+        segment::segment(text, |part| {
+            (self.has_word(part) || self.ignored_words.contains(part))
+                && !self.data.forbidden_words.contains(part)
+        })
+    }
+    
+    /// How common `word` is according to the dictionary's frequency column,
+    /// 0 if it has none
+    pub fn frequency(&self, word: &str) -> u64 {
+        self.data.words.frequency(&self.normalize_word(word))
+    }
+    
+    pub fn max_frequency(&self) -> u64 {
+        self.max_frequency
+    }
+    
+    // Hunspell dictionaries mark which words may be compounded, and where;
+    // plain word lists don't, so there only longer words may join up
+    fn is_compound_part(&self, part: &str, position: CompoundPosition) -> bool {
+        if self.data.forbidden_words.contains(part) {
+            return false;
+        }
+        let rules = &self.data.compound_rules;
+        if !rules.is_empty() {
+            return rules.allows(part, position);
+        }
+        part.chars().count() >= compound::MIN_UNMARKED_PART_CHARS && self.has_word(part)
+    }
+    
     fn normalize_word(&self, word: &str) -> String {
         match self.language {
             Language::Chinese | Language::Japanese | Language::Korean => {
@@ -120,61 +462,46 @@ impl Dictionary {
     }
     
     fn load_user_words(&mut self) {
-        let mut path = LanguageManager::user_dict_dir();
-        path.push(format!("user_{}.txt", self.language.code()));
-        
-        if let Ok(file) = File::open(&path) {
-            let reader = BufReader::new(file);
-            for line in reader.lines() {
-                if let Ok(word) = line {
-                    let word = word.trim().to_string();
-                    if !word.is_empty() {
-                        self.words.insert(self.normalize_word(&word));
-                    }
-                }
-            }
-        }
+        self.user_words = self.load_word_file("user");
     }
     
     fn save_user_words(&self) -> anyhow::Result<()> {
-        let mut path = LanguageManager::user_dict_dir();
-        path.push(format!("user_{}.txt", self.language.code()));
-        
-        let mut file = File::create(&path)?;
-        let mut sorted_words: Vec<&String> = self.words.iter().collect();
-        sorted_words.sort();
-        
-        for word in sorted_words {
-            writeln!(file, "{}", word)?;
-        }
-        
-        Ok(())
+        self.save_word_file("user", &self.user_words)
     }
     
     fn load_ignored_words(&mut self) {
+        self.ignored_words = self.load_word_file("ignored");
+        self.ignored_count_cache = self.ignored_words.len();
+    }
+    
+    fn save_ignored_words(&self) -> anyhow::Result<()> {
+        self.save_word_file("ignored", &self.ignored_words)
+    }
+    
+    // The words in the user's `{kind}_{language}.txt`, one per line
+    fn load_word_file(&self, kind: &str) -> HashSet<String> {
         let mut path = LanguageManager::user_dict_dir();
-        path.push(format!("ignored_{}.txt", self.language.code()));
+        path.push(format!("{}_{}.txt", kind, self.language.code()));
         
+        let mut words = HashSet::new();
         if let Ok(file) = File::open(&path) {
             let reader = BufReader::new(file);
-            for line in reader.lines() {
-                if let Ok(word) = line {
-                    let word = word.trim().to_string();
-                    if !word.is_empty() {
-                        self.ignored_words.insert(self.normalize_word(&word));
-                    }
+            for word in reader.lines().map_while(Result::ok) {
+                let word = word.trim().to_string();
+                if !word.is_empty() {
+                    words.insert(self.normalize_word(&word));
                 }
             }
-            self.ignored_count_cache = self.ignored_words.len();
         }
+        words
     }
     
-    fn save_ignored_words(&self) -> anyhow::Result<()> {
+    fn save_word_file(&self, kind: &str, words: &HashSet<String>) -> anyhow::Result<()> {
         let mut path = LanguageManager::user_dict_dir();
-        path.push(format!("ignored_{}.txt", self.language.code()));
+        path.push(format!("{}_{}.txt", kind, self.language.code()));
         
         let mut file = File::create(&path)?;
-        let mut sorted_words: Vec<&String> = self.ignored_words.iter().collect();
+        let mut sorted_words: Vec<&String> = words.iter().collect();
         sorted_words.sort();
         
         for word in sorted_words {
@@ -197,35 +524,54 @@ impl Dictionary {
             return true;
         }
         
+        // FORBIDDENWORD entries are rejected even when affix rules would generate them
+        if self.data.forbidden_words.contains(&normalized) {
+            return false;
+        }
+        
         // Skip words that look like code identifiers in code context
         if is_code_context && self.is_likely_code_identifier(word) {
             return true;
         }
         
         // Skip words with numbers (except in CJK)
-        if !matches!(self.language, Language::Chinese | Language::Japanese | Language::Korean) {
-            if word.chars().any(|c| c.is_ascii_digit()) && word.len() > 3 {
-                // Allow numbers in longer words (like "word123")
-                let letter_count = word.chars().filter(|c| c.is_alphabetic()).count();
-                if letter_count < 3 {
-                    return true;
-                }
+        if !matches!(self.language, Language::Chinese | Language::Japanese | Language::Korean)
+            && word.chars().any(|c| c.is_ascii_digit()) && word.len() > 3
+        {
+            // Allow numbers in longer words (like "word123")
+            let letter_count = word.chars().filter(|c| c.is_alphabetic()).count();
+            if letter_count < 3 {
+                return true;
             }
         }
         
         // Check in dictionary
-        match self.language {
+        let found = match self.language {
             Language::Chinese | Language::Japanese | Language::Korean => {
-                self.words.contains(&normalized)
+                self.has_word(&normalized)
             }
             _ => {
                 if case_sensitive {
-                    self.words.contains(word)
+                    self.has_word(word)
                 } else {
-                    self.words.contains(&normalized)
+                    self.has_word(&normalized)
                 }
             }
-        }
+        };
+        
+        let compounds = &self.data.compound_rules;
+        found
+            || (!compounds.is_empty() && compounds.matches(&normalized))
+            || self.compound_split(&normalized).is_some()
+    }
+    
+    fn has_word(&self, word: &str) -> bool {
+        self.user_words.contains(word) || (self.data.words.contains(word) && !self.removed_words.contains(word))
+    }
+    
+    /// Whether a word may be offered as a suggestion (Hunspell NOSUGGEST/FORBIDDENWORD)
+    pub fn is_suggestible(&self, word: &str) -> bool {
+        !self.data.no_suggest_words.contains(word) && !self.data.forbidden_words.contains(word)
     }
     
     pub fn is_likely_code_identifier(&self, word: &str) -> bool {
@@ -255,12 +601,41 @@ impl Dictionary {
         self.ignored_count_cache
     }
     
-    pub fn get_words(&self) -> &HashSet<String> {
-        &self.words
+    /// The shared, immutable word list loaded from dictionary files
+    pub fn word_list(&self) -> &WordList {
+        &self.data.words
+    }
+    
+    pub fn user_words(&self) -> &HashSet<String> {
+        &self.user_words
+    }
+    
+    /// Dictionary and user words starting with `prefix`, sorted
+    pub fn words_with_prefix(&self, prefix: &str, limit: usize) -> Vec<String> {
+        let prefix = self.normalize_word(prefix);
+        let mut words = self.data.words.with_prefix(&prefix, limit + self.removed_words.len());
+        words.retain(|w| !self.removed_words.contains(w));
+        words.extend(self.user_words.iter().filter(|w| w.starts_with(&prefix)).cloned());
+        words.sort();
+        words.dedup();
+        words.truncate(limit);
+        words
     }
     
-    pub fn get_word_pattern(&self) -> &Regex {
-        &self.word_pattern
+    /// Dictionary and user words within `distance` edits, found by walking the FST
+    pub fn fuzzy_words(&self, word: &str, distance: u32) -> Vec<String> {
+        let word = self.normalize_word(word);
+        let mut words = self.data.words.fuzzy(&word, distance);
+        words.retain(|w| !self.removed_words.contains(w));
+        words.extend(
+            self.user_words
+                .iter()
+                .filter(|w| crate::util::levenshtein_distance(&word, w) <= distance as usize)
+                .cloned(),
+        );
+        words.sort();
+        words.dedup();
+        words
     }
     
     pub fn language(&self) -> &Language {
@@ -275,8 +650,11 @@ impl Dictionary {
         let normalized = self.normalize_word(word.trim());
         
         if !normalized.is_empty() && normalized.len() >= self.min_word_length {
-            self.words.insert(normalized.clone());
-            self.word_count_cache = self.words.len();
+            self.user_words.insert(normalized.clone());
+            if self.removed_words.remove(&normalized) {
+                self.save_word_file("removed", &self.removed_words)?;
+            }
+            self.count_words();
             
             self.ignored_words.remove(&normalized);
             self.ignored_count_cache = self.ignored_words.len();
@@ -307,21 +685,39 @@ impl Dictionary {
         Ok(())
     }
     
-    pub fn remove_word(&mut self, word: &str) -> bool {
-        let removed = self.words.remove(word);
-        if removed {
-            self.word_count_cache = self.words.len();
+    /// Remove a user word, or mark a word of the dictionary files as
+    /// removed; either is saved. Returns whether the word was known.
+    pub fn remove_word(&mut self, word: &str) -> anyhow::Result<bool> {
+        let word = self.normalize_word(word.trim());
+        let removed_user_word = self.user_words.remove(&word);
+        // The shared list is immutable, so its words are removed by a list on top
+        let removed_listed_word = self.data.words.contains(&word) && self.removed_words.insert(word);
+        
+        if removed_user_word {
+            self.save_user_words()?;
         }
-        removed
+        if removed_listed_word {
+            self.save_word_file("removed", &self.removed_words)?;
+        }
+        self.count_words();
+        Ok(removed_user_word || removed_listed_word)
     }
     
     pub fn save_to_file(&self, path: &Path) -> anyhow::Result<()> {
         let mut file = File::create(path)?;
-        let mut sorted_words: Vec<&String> = self.words.iter().collect();
-        sorted_words.sort();
-        
-        for word in sorted_words {
-            writeln!(file, "{}", word)?;
+        let mut sorted_words = self.data.words.counts();
+        sorted_words.retain(|(word, _)| !self.removed_words.contains(word));
+        sorted_words.extend(self.user_words.iter().map(|w| (w.clone(), 0)));
+        // Highest count first within each word, so dedup keeps it
+        sorted_words.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+        sorted_words.dedup_by(|a, b| a.0 == b.0);
+        
+        for (word, count) in sorted_words {
+            if count > 0 {
+                writeln!(file, "{}\t{}", word, count)?;
+            } else {
+                writeln!(file, "{}", word)?;
+            }
         }
         
         Ok(())
@@ -338,7 +734,7 @@ impl Dictionary {
 
 #[derive(Clone)]
 pub struct DictionaryManager {
-    dictionaries: Arc<DashMap<Language, Dictionary>>,
+    dictionaries: Arc<DashMap<Language, Arc<Dictionary>>>,
     language_manager: LanguageManager,
 }
 
@@ -359,14 +755,17 @@ impl DictionaryManager {
         }
     }
     
-    pub fn get_dictionary(&self, language: &Language) -> anyhow::Result<Dictionary> {
+    /// Returns a shared handle to the dictionary, loading it on first use.
+    /// Cloning the handle is cheap; the word data itself is never copied.
+    pub fn get_dictionary(&self, language: &Language) -> anyhow::Result<Arc<Dictionary>> {
         if let Some(dict) = self.dictionaries.get(language) {
-            return Ok(dict.clone());
+            return Ok(Arc::clone(&dict));
         }
         
         let mut dict = Dictionary::new(*language);
         dict.load()?;
-        self.dictionaries.insert(*language, dict.clone());
+        let dict = Arc::new(dict);
+        self.dictionaries.insert(*language, Arc::clone(&dict));
         
         Ok(dict)
     }
@@ -374,51 +773,44 @@ impl DictionaryManager {
     pub fn reload_dictionary(&mut self, language: &Language) -> anyhow::Result<()> {
         let mut dict = Dictionary::new(*language);
         dict.load()?;
-        self.dictionaries.insert(*language, dict);
+        self.dictionaries.insert(*language, Arc::new(dict));
         Ok(())
     }
     
     pub fn add_custom_dictionary(&mut self, path: PathBuf, language: Language) -> anyhow::Result<()> {
         let mut dict = Dictionary::new(language);
         dict.import_from_file(&path)?;
-        self.dictionaries.insert(language, dict);
+        self.dictionaries.insert(language, Arc::new(dict));
         Ok(())
     }
     
-    pub fn add_word_to_dictionary(&mut self, word: &str, language: Language) -> anyhow::Result<()> {
+    // Mutating a dictionary copies only the user overlay; the word data stays shared
+    fn update_dictionary(
+        &mut self,
+        language: Language,
+        update: impl FnOnce(&mut Dictionary) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
         if let Some(mut dict) = self.dictionaries.get_mut(&language) {
-            dict.add_word(word)
+            update(Arc::make_mut(&mut dict))
         } else {
             let mut dict = Dictionary::new(language);
             dict.load()?;
-            dict.add_word(word)?;
-            self.dictionaries.insert(language, dict);
+            update(&mut dict)?;
+            self.dictionaries.insert(language, Arc::new(dict));
             Ok(())
         }
     }
     
+    pub fn add_word_to_dictionary(&mut self, word: &str, language: Language) -> anyhow::Result<()> {
+        self.update_dictionary(language, |dict| dict.add_word(word))
+    }
+    
     pub fn ignore_word(&mut self, word: &str, language: Language) -> anyhow::Result<()> {
-        if let Some(mut dict) = self.dictionaries.get_mut(&language) {
-            dict.ignore_word(word)
-        } else {
-            let mut dict = Dictionary::new(language);
-            dict.load()?;
-            dict.ignore_word(word)?;
-            self.dictionaries.insert(language, dict);
-            Ok(())
-        }
+        self.update_dictionary(language, |dict| dict.ignore_word(word))
     }
     
     pub fn clear_ignored_words(&mut self, language: Language) -> anyhow::Result<()> {
-        if let Some(mut dict) = self.dictionaries.get_mut(&language) {
-            dict.clear_ignored_words()
-        } else {
-            let mut dict = Dictionary::new(language);
-            dict.load()?;
-            dict.clear_ignored_words()?;
-            self.dictionaries.insert(language, dict);
-            Ok(())
-        }
+        self.update_dictionary(language, |dict| dict.clear_ignored_words())
     }
     
     pub fn import_dictionary(&mut self, path: PathBuf, language: Language) -> anyhow::Result<()> {
@@ -446,7 +838,108 @@ impl DictionaryManager {
         self.language_manager.set_language(language);
     }
     
-    pub fn get_cached_dictionary(&self, language: &Language) -> Option<Dictionary> {
-        self.dictionaries.get(language).map(|d| d.value().clone())
+    pub fn get_cached_dictionary(&self, language: &Language) -> Option<Arc<Dictionary>> {
+        self.dictionaries.get(language).map(|d| Arc::clone(d.value()))
     }
-}
\ No newline at end of file
+}
+
+// Word list lines are either a bare word or a word followed by a frequency
+// count field ("the\t5621" or "the,5621"). A space doesn't start a field,
+// so "boeing 747" is a word of its own.
+fn split_frequency(line: &str) -> (&str, u64) {
+    line.rsplit_once(['\t', ','])
+        .and_then(|(word, count)| Some((word.trim(), count.trim().parse().ok()?)))
+        .unwrap_or((line, 0))
+}
+
+// Folded form an accented word is indexed under. Hyphens are dropped so a
+// hyphen written in place of a diaeresis still matches.
+fn accent_key(word: &str) -> String {
+    util::fold_diacritics(word).replace('-', "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn frequency_is_a_trailing_field() {
+        assert_eq!(split_frequency("the\t5621"), ("the", 5621));
+        assert_eq!(split_frequency("the,5621"), ("the", 5621));
+        assert_eq!(split_frequency("the"), ("the", 0));
+        assert_eq!(split_frequency("boeing 747"), ("boeing 747", 0));
+        assert_eq!(split_frequency("boeing 747\t12"), ("boeing 747", 12));
+    }
+    
+    fn english(words: &[&str]) -> Dictionary {
+        let mut dictionary = Dictionary::new(Language::English);
+        Arc::make_mut(&mut dictionary.data).words = WordList::from_words(words.iter().copied()).unwrap();
+        dictionary.words_changed();
+        dictionary
+    }
+    
+    #[test]
+    fn user_words_are_looked_up_beside_the_index() {
+        let mut dictionary = english(&["house", "mouse"]);
+        assert_eq!(dictionary.suggestion_index().len(), 2);
+        dictionary.user_words.insert("haus".to_string());
+        dictionary.count_words();
+        
+        assert_eq!(dictionary.word_count(), 3);
+        let candidates: Vec<String> = dictionary.lookup("hause", 1).into_iter().map(|(word, _)| word).collect();
+        assert_eq!(candidates, ["haus", "house"]);
+    }
+    
+    #[test]
+    fn removed_words_are_gone_everywhere() {
+        let mut dictionary = english(&["house", "mouse"]);
+        dictionary.removed_words.insert("house".to_string());
+        dictionary.count_words();
+        
+        assert_eq!(dictionary.word_count(), 1);
+        assert!(!dictionary.contains("house", false, false));
+        assert!(dictionary.lookup("hous", 1).is_empty());
+    }
+    
+    #[test]
+    fn the_overlay_survives_a_rebuild() {
+        let mut dictionary = english(&["house", "mouse"]);
+        dictionary.user_words.insert("haus".to_string());
+        dictionary.removed_words.insert("mouse".to_string());
+        dictionary.count_words();
+        assert_eq!(dictionary.words_with_prefix("h", 10), ["haus", "house"]);
+        
+        // Loading another word file builds a new FST under the same overlay
+        let data = Arc::make_mut(&mut dictionary.data);
+        data.words = data.words.union_counts([("hose", 3), ("mouse", 0)]).unwrap();
+        dictionary.words_changed();
+        
+        assert_eq!(dictionary.word_count(), 3);
+        assert!(dictionary.contains("haus", false, false));
+        assert!(dictionary.contains("hose", false, false));
+        assert!(!dictionary.contains("mouse", false, false));
+        assert_eq!(dictionary.frequency("hose"), 3);
+        assert_eq!(dictionary.words_with_prefix("h", 10), ["haus", "hose", "house"]);
+        assert_eq!(dictionary.fuzzy_words("mouse", 1), ["house"]);
+        assert_eq!(dictionary.fuzzy_words("hous", 1), ["haus", "house"]);
+        
+        let candidates: Vec<String> = dictionary.lookup("hause", 1).into_iter().map(|(word, _)| word).collect();
+        assert_eq!(candidates, ["haus", "house"]);
+    }
+    
+    #[test]
+    fn a_removed_word_comes_back_as_a_user_word() {
+        let mut dictionary = english(&["house", "mouse"]);
+        dictionary.removed_words.insert("house".to_string());
+        dictionary.count_words();
+        assert!(!dictionary.contains("house", false, false));
+        
+        dictionary.removed_words.remove("house");
+        dictionary.user_words.insert("house".to_string());
+        dictionary.count_words();
+        
+        assert_eq!(dictionary.word_count(), 2);
+        assert!(dictionary.contains("house", false, false));
+        assert_eq!(dictionary.words_with_prefix("ho", 10), ["house"]);
+    }
+}
@@ -1,6 +1,7 @@
-use crate::checker::{DocumentAnalysis, WordCheck, WordType};
+use crate::checker::{DocumentAnalysis, RecheckedLines, WordCheck, WordType};
+use crate::prose::programming_language;
 use eframe::egui;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 #[derive(Clone)]
 pub struct TextEditor {
@@ -8,10 +9,14 @@ pub struct TextEditor {
     font_size: f32,
     show_whitespace: bool,
     wrap_lines: bool,
-    error_cache: HashMap<usize, WordCheck>,
-    last_analysis: Option<DocumentAnalysis>,
+    // Misspelled words keyed by line number
+    error_cache: BTreeMap<usize, Vec<WordCheck>>,
     programming_language: Option<String>,
     scroll_offset: f32,
+    // The cursor after the last edit, as egui's char index, and the last
+    // (char index, byte offset) it was looked up at
+    cursor_char: Option<usize>,
+    cursor_found: (usize, usize),
 }
 
 impl Default for TextEditor {
@@ -27,21 +32,19 @@ impl TextEditor {
             font_size: 14.0,
             show_whitespace: false,
             wrap_lines: true,
-            error_cache: HashMap::new(),
-            last_analysis: None,
+            error_cache: BTreeMap::new(),
             programming_language: None,
             scroll_offset: 0.0,
+            cursor_char: None,
+            cursor_found: (0, 0),
         }
     }
     
-    pub fn set_analysis(&mut self, analysis: DocumentAnalysis) {
-        self.last_analysis = Some(analysis.clone());
+    pub fn set_analysis(&mut self, analysis: &DocumentAnalysis) {
         self.error_cache.clear();
         
-        for word in &analysis.words {
-            if !word.is_correct {
-                self.error_cache.insert(word.start, word.clone());
-            }
+        for word in analysis.words.iter().filter(|w| !w.is_correct) {
+            self.error_cache.entry(word.line).or_default().push(word.clone());
         }
         
         // Detect programming language from file type
@@ -50,26 +53,35 @@ impl TextEditor {
         }
     }
     
+    pub fn clear_analysis(&mut self) {
+        self.error_cache.clear();
+    }
+    
+    /// Update the error cache after `SpellChecker::recheck_edit`, touching only
+    /// the `lines` it re-checked and re-keying the ones after them
+    pub fn apply_edit(&mut self, analysis: &DocumentAnalysis, lines: RecheckedLines) {
+        let RecheckedLines { first: first_line, old_last: old_last_line, new_last: new_last_line } = lines;
+        
+        let mut tail = self.error_cache.split_off(&first_line);
+        let after = tail.split_off(&(old_last_line + 1));
+        
+        for (line, mut errors) in after {
+            let line = line + new_last_line - old_last_line;
+            for error in &mut errors {
+                error.line = line;
+            }
+            self.error_cache.insert(line, errors);
+        }
+        
+        let from = analysis.words.partition_point(|w| w.line < first_line);
+        let to = analysis.words.partition_point(|w| w.line <= new_last_line);
+        for word in analysis.words[from..to].iter().filter(|w| !w.is_correct) {
+            self.error_cache.entry(word.line).or_default().push(word.clone());
+        }
+    }
+    
     pub fn detect_programming_language(&mut self, filename: &str) {
-        self.programming_language = match filename.rsplit('.').next() {
-            Some("rs") => Some("rust".to_string()),
-            Some("py") => Some("python".to_string()),
-            Some("js") | Some("ts") | Some("jsx") | Some("tsx") => Some("javascript".to_string()),
-            Some("java") => Some("java".to_string()),
-            Some("cpp") | Some("cc") | Some("cxx") | Some("c") => Some("cpp".to_string()),
-            Some("go") => Some("go".to_string()),
-            Some("rb") => Some("ruby".to_string()),
-            Some("php") => Some("php".to_string()),
-            Some("html") | Some("htm") => Some("html".to_string()),
-            Some("css") => Some("css".to_string()),
-            Some("md") => Some("markdown".to_string()),
-            Some("json") => Some("json".to_string()),
-            Some("toml") => Some("toml".to_string()),
-            Some("yaml") | Some("yml") => Some("yaml".to_string()),
-            Some("xml") => Some("xml".to_string()),
-            Some("sh") | Some("bash") => Some("shell".to_string()),
-            _ => None,
-        };
+        self.programming_language = programming_language(filename).map(str::to_string);
     }
     
     pub fn show(
@@ -78,12 +90,7 @@ impl TextEditor {
         content: &mut String,
         modified: &mut bool,
         show_line_numbers: bool,
-        analysis: &Option<DocumentAnalysis>,
     ) -> egui::Response {
-        if let Some(analysis) = analysis {
-            self.set_analysis(analysis.clone());
-        }
-        
         let available_rect = ui.available_rect_before_wrap();
         
         let line_numbers_width = if show_line_numbers {
@@ -128,12 +135,15 @@ impl TextEditor {
                 text_edit = text_edit.desired_rows(10);
             }
             
-            let edit_response = ui.add(text_edit);
-            if edit_response.changed() {
+            let output = text_edit.show(ui);
+            if output.response.changed() {
                 *modified = true;
+                
+                // Remember where the user is typing (egui reports a char index)
+                self.cursor_char = output.cursor_range.map(|range| range.primary.ccursor.index);
             }
             
-            edit_response
+            output.response
         }).inner
     }
     
@@ -193,10 +203,20 @@ impl TextEditor {
         
         let char_width = self.font_size * 0.6;
         
+        let clip_rect = ui.clip_rect();
+        
         for (line_idx, line) in lines.iter().enumerate() {
             let line_y = rect.top() + (line_idx as f32 * self.line_height);
             let text_x = rect.left() + line_numbers_width + 5.0;
             
+            // Only lay out lines that can actually be seen
+            if line_y + self.line_height < clip_rect.top() {
+                continue;
+            }
+            if line_y > clip_rect.bottom() {
+                break;
+            }
+            
             // Draw regular text
             painter.text(
                 egui::pos2(text_x, line_y + (self.line_height * 0.7)),
@@ -206,13 +226,7 @@ impl TextEditor {
                 text_color,
             );
             
-            if let Some(analysis) = &self.last_analysis {
-                let line_num = line_idx + 1;
-                let line_errors: Vec<&WordCheck> = analysis.words
-                    .iter()
-                    .filter(|w| !w.is_correct && w.line == line_num)
-                    .collect();
-                
+            if let Some(line_errors) = self.error_cache.get(&(line_idx + 1)) {
                 for error in line_errors {
                     let error_start_in_line = error.column.saturating_sub(1);
                     let error_x = text_x + (error_start_in_line as f32 * char_width);
@@ -269,7 +283,7 @@ impl TextEditor {
     }
     
     pub fn set_font_size(&mut self, size: f32) {
-        self.font_size = size.max(8.0).min(36.0);
+        self.font_size = size.clamp(8.0, 36.0);
         self.line_height = size * 1.6;
     }
     
@@ -282,15 +296,66 @@ impl TextEditor {
     }
     
     pub fn get_error_at_position(&self, line: usize, column: usize) -> Option<&WordCheck> {
-        if let Some(analysis) = &self.last_analysis {
-            analysis.words.iter()
-                .find(|w| !w.is_correct && w.line == line && w.column <= column && column <= w.column + w.word.len())
-        } else {
-            None
+        self.error_cache.get(&line)?
+            .iter()
+            .find(|w| w.column <= column && column <= w.column + w.word.len())
+    }
+    
+    /// Note that the document changed from byte `offset` on without the
+    /// cursor being looked up
+    pub fn forget_cursor_after(&mut self, offset: usize) {
+        if self.cursor_found.1 > offset {
+            self.cursor_found = (0, 0);
         }
     }
     
+    /// Byte offset in `content` of the cursor after the last edit made in
+    /// the editor. `content` is the same as when the cursor was last looked
+    /// up before byte `unchanged_before`, so the search starts from there
+    /// rather than from the start of the document.
+    pub fn cursor_offset(&mut self, content: &str, unchanged_before: usize) -> Option<usize> {
+        let index = self.cursor_char?;
+        let (mut chars, mut bytes) = self.cursor_found;
+        if bytes > unchanged_before || !content.is_char_boundary(bytes) {
+            (chars, bytes) = (0, 0);
+        }
+        
+        while chars < index {
+            let Some(c) = content[bytes..].chars().next() else { break };
+            bytes += c.len_utf8();
+            chars += 1;
+        }
+        while chars > index {
+            let Some(c) = content[..bytes].chars().next_back() else { break };
+            bytes -= c.len_utf8();
+            chars -= 1;
+        }
+        
+        self.cursor_found = (chars, bytes);
+        Some(bytes)
+    }
+    
     pub fn scroll_to_line(&mut self, line: usize) {
         self.scroll_offset = (line as f32 - 5.0).max(0.0) * self.line_height;
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn cursor_is_found_from_where_it_was_last() {
+        let mut editor = TextEditor::new();
+        editor.cursor_char = Some(3);
+        assert_eq!(editor.cursor_offset("héllo", 0), Some(4));
+        
+        // Typing "ö" at the cursor leaves the text before it as it was
+        editor.cursor_char = Some(4);
+        assert_eq!(editor.cursor_offset("hélölo", 4), Some(6));
+        
+        // An edit before the last position means starting over
+        editor.cursor_char = Some(2);
+        assert_eq!(editor.cursor_offset("ééélölo", 0), Some(4));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::prose::{lines, string_value_spans, ProseSpan};
+use crate::selector::{PathSegment, ValueFilter};
+
+// What the text at a position of a message is, inside a placeable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Context {
+    /// Variable references, function calls and string literals of `{ }`
+    Expression,
+    /// The variants of a select expression, `[key]` followed by text
+    Variants,
+}
+
+/// The prose in a Fluent resource (`.ftl`): the text of messages, terms
+/// and their attributes picked by `filter`. A message `hello` is at
+/// `$.hello`, its attribute `.title` at `$.hello.title`, a term `-brand`
+/// at `$.-brand`.
+///
+/// Comments, identifiers, placeables (`{ $name }`, `{ -brand }`) and the
+/// keys of select expressions are left out; the text of their variants is
+/// checked.
+pub fn prose_spans(text: &str, filter: &ValueFilter) -> Vec<ProseSpan> {
+    let mut spans = Vec::new();
+    let mut path: Vec<PathSegment> = Vec::new();
+    let mut checked = false;
+    let mut contexts: Vec<Context> = Vec::new();
+    
+    for (line_start, line) in lines(text) {
+        let trimmed = line.trim_start();
+        let mut start = line_start + (line.len() - trimmed.len());
+        let mut pattern = trimmed;
+        
+        if trimmed.len() == line.len() && !trimmed.is_empty() {
+            // Anything in the first column starts an entry or a comment
+            contexts.clear();
+            match entry(trimmed) {
+                Some((id, offset)) => {
+                    path = vec![PathSegment::Key(id.to_string())];
+                    checked = filter.checks(&path);
+                    start += offset;
+                    pattern = &trimmed[offset..];
+                }
+                None => {
+                    path.clear();
+                    continue;
+                }
+            }
+        } else if contexts.is_empty() && !path.is_empty() {
+            if let Some((attribute, offset)) = trimmed.strip_prefix('.').and_then(entry) {
+                path.truncate(1);
+                path.push(PathSegment::Key(attribute.to_string()));
+                checked = filter.checks(&path);
+                start += offset + 1;
+                pattern = &trimmed[offset + 1..];
+            }
+        }
+        if path.is_empty() {
+            continue;
+        }
+        
+        // The key of a variant: "[one]", "*[other]"
+        if contexts.last() == Some(&Context::Variants) {
+            let variant = pattern.strip_prefix('*').unwrap_or(pattern);
+            if variant.starts_with('[') {
+                if let Some(end) = variant.find(']') {
+                    let offset = pattern.len() - variant.len() + end + 1;
+                    start += offset;
+                    pattern = &pattern[offset..];
+                }
+            }
+        }
+        
+        let bytes = pattern.as_bytes();
+        let mut run_start = 0;
+        let mut i = 0;
+        let push_run = |spans: &mut Vec<ProseSpan>, from: usize, to: usize| {
+            if checked && to > from {
+                spans.extend(string_value_spans(text, start + from..start + to));
+            }
+        };
+        while i < bytes.len() {
+            let in_text = contexts.last() != Some(&Context::Expression);
+            match bytes[i] {
+                b'{' => {
+                    if in_text {
+                        push_run(&mut spans, run_start, i);
+                    }
+                    contexts.push(Context::Expression);
+                }
+                b'}' => {
+                    if in_text {
+                        push_run(&mut spans, run_start, i);
+                    }
+                    contexts.pop();
+                    run_start = i + 1;
+                }
+                b'"' if !in_text => {
+                    // A string literal
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != b'"' {
+                        if bytes[i] == b'\\' {
+                            i += 1;
+                        }
+                        i += 1;
+                    }
+                }
+                b'-' if !in_text && bytes.get(i + 1) == Some(&b'>') => {
+                    contexts.pop();
+                    contexts.push(Context::Variants);
+                    i += 1;
+                    run_start = i + 1;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        if contexts.last() != Some(&Context::Expression) {
+            push_run(&mut spans, run_start, bytes.len());
+        }
+    }
+    
+    spans
+}
+
+// The identifier of "id = value" and where its value starts
+fn entry(line: &str) -> Option<(&str, usize)> {
+    let name = line.strip_prefix('-').unwrap_or(line);
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let len = line.len() - name.len()
+        + name
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(name.len());
+    let after = &line[len..];
+    let value = after.trim_start().strip_prefix('=')?;
+    Some((&line[..len], line.len() - value.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn prose<'a>(text: &'a str, filter: &ValueFilter) -> Vec<&'a str> {
+        prose_spans(text, filter)
+            .into_iter()
+            .map(|span| text[span.range].trim())
+            .collect()
+    }
+    
+    #[test]
+    fn placeables_and_identifiers_are_left_out() {
+        let text = "# Comment\nhello = Hello { $name }, welcome\n";
+        assert_eq!(prose(text, &ValueFilter::default()), ["Hello", ", welcome"]);
+    }
+    
+    #[test]
+    fn variants_are_checked_without_their_keys() {
+        let text = "emails = { $count ->\n    [one] One email\n   *[other] Many emails\n}\n";
+        let words = prose(text, &ValueFilter::default()).concat();
+        assert!(words.contains("One email"));
+        assert!(words.contains("Many emails"));
+        assert!(!words.contains("one]"));
+        assert!(!words.contains("other"));
+        assert!(!words.contains("count"));
+    }
+    
+    #[test]
+    fn attributes_have_their_own_path() {
+        let text = "login = Log in\n    .title = Sign in here\n";
+        let filter = ValueFilter::new(&[], &["$.login.title".to_string()]);
+        assert_eq!(prose(text, &filter), ["Log in"]);
+    }
+}
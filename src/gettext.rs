@@ -0,0 +1,156 @@
+use crate::language::Language;
+use crate::prose::{lines, without_placeholders, ProseKind, ProseSpan};
+use std::ops::Range;
+
+// Which part of an entry the strings on a line belong to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    /// `msgid` and `msgid_plural`
+    Original,
+    /// `msgstr` and `msgstr[n]`
+    Translation,
+    /// `msgctxt`, the header and anything unknown
+    Skipped,
+}
+
+/// The prose in a gettext catalog (`.po`, `.pot`): the original strings,
+/// in `source_language`, and their translations, in the language named by
+/// the `Language:` header.
+///
+/// Left out are comments, references and flags, obsolete entries,
+/// `msgctxt`, the header itself, escapes and format placeholders such as
+/// `%s`, `%(name)s` and `{name}`. Translations are left out as well when
+/// the header names a language the checker doesn't know.
+pub fn prose_spans(text: &str, source_language: Language) -> Vec<ProseSpan> {
+    entry_spans(text, source_language, translation_language(text))
+}
+
+/// The language of the translations in a catalog starting with `text`:
+/// None leaves them out, Some(None) checks them in the checker's own
+/// language, which is what a catalog without a header gets.
+pub fn translation_language(text: &str) -> Option<Option<Language>> {
+    match header_language(text) {
+        None | Some("") => Some(None),
+        Some(tag) => Language::from_tag(tag).map(Some),
+    }
+}
+
+/// The prose of `text`, whole entries of a catalog whose translations are
+/// in `translation_language`
+pub fn entry_spans(text: &str, source_language: Language, translation_language: Option<Option<Language>>) -> Vec<ProseSpan> {
+    let mut spans = Vec::new();
+    let mut field = Field::Skipped;
+    let mut original_is_empty = true;
+    
+    for (line_start, line) in lines(text) {
+        let trimmed = line.trim_start();
+        let content_start = line_start + (line.len() - trimmed.len());
+        
+        let string_start = if trimmed.starts_with('"') {
+            content_start
+        } else {
+            let keyword_len = trimmed.find([' ', '\t', '"']).unwrap_or(trimmed.len());
+            field = match &trimmed[..keyword_len] {
+                "msgid" => {
+                    original_is_empty = true;
+                    Field::Original
+                }
+                "msgid_plural" => Field::Original,
+                // The translation of the empty msgid is the header
+                keyword if keyword.starts_with("msgstr") && !original_is_empty => Field::Translation,
+                _ => Field::Skipped,
+            };
+            match trimmed.find('"') {
+                Some(quote) => content_start + quote,
+                None => continue,
+            }
+        };
+        
+        let Some(string) = string_content(text, string_start, line_start + line.len()) else {
+            continue;
+        };
+        if field == Field::Original && !string.is_empty() {
+            original_is_empty = false;
+        }
+        
+        let language = match field {
+            Field::Original => Some(source_language),
+            Field::Translation => match translation_language {
+                Some(language) => language,
+                None => continue,
+            },
+            Field::Skipped => continue,
+        };
+        for part in without_placeholders(text, string) {
+            if !text[part.clone()].trim().is_empty() {
+                spans.push(ProseSpan { language, ..ProseSpan::new(part, ProseKind::Text) });
+            }
+        }
+    }
+    
+    spans
+}
+
+// The byte range inside the quoted string starting at `quote`, which ends
+// on the same line
+fn string_content(text: &str, quote: usize, line_end: usize) -> Option<Range<usize>> {
+    let bytes = text.as_bytes();
+    let mut i = quote + 1;
+    while i < line_end {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'"' => return Some(quote + 1..i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// The value of the "Language:" line in the header, the translation of the
+// first empty msgid
+fn header_language(text: &str) -> Option<&str> {
+    let header_start = text.find("msgid \"\"")?;
+    let header = &text[header_start..];
+    let header = &header[..header.find("\n\n").unwrap_or(header.len())];
+    let value = &header[header.find("Language:")? + "Language:".len()..];
+    let value = &value[..value.find(['\\', '"']).unwrap_or(value.len())];
+    Some(value.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn spans(text: &str) -> Vec<(&str, Option<Language>)> {
+        prose_spans(text, Language::English)
+            .into_iter()
+            .map(|span| (&text[span.range], span.language))
+            .collect()
+    }
+    
+    #[test]
+    fn originals_and_translations_carry_their_languages() {
+        let text = "msgid \"\"\nmsgstr \"\"\n\"Language: fr\\n\"\n\n#: src/main.rs:10\nmsgctxt \"menu\"\nmsgid \"Open file\"\nmsgstr \"Ouvrir le fichier\"\n";
+        assert_eq!(spans(text), [
+            ("Open file", Some(Language::English)),
+            ("Ouvrir le fichier", Some(Language::French)),
+        ]);
+    }
+    
+    #[test]
+    fn placeholders_and_obsolete_entries_are_left_out() {
+        let text = "msgid \"Hello %s, welcome\"\nmsgstr \"\"\n\n#~ msgid \"Old text\"\n#~ msgstr \"\"\n";
+        let words: String = spans(text).into_iter().map(|(text, _)| text).collect();
+        assert!(words.contains("Hello"));
+        assert!(words.contains("welcome"));
+        assert!(!words.contains("%s"));
+        assert!(!words.contains("Old"));
+    }
+    
+    #[test]
+    fn unknown_translation_languages_are_left_out() {
+        let text = "msgid \"\"\nmsgstr \"\"\n\"Language: tlh\\n\"\n\nmsgid \"Hello\"\nmsgstr \"nuqneH\"\n";
+        assert_eq!(spans(text), [("Hello", Some(Language::English))]);
+    }
+}
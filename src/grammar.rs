@@ -0,0 +1,484 @@
+use crate::checker::{Diagnostic, DiagnosticKind, WordCheck, WordType};
+use crate::language::Language;
+use crate::ngram::BigramModel;
+
+/// A check that runs over the word stream once spelling is done
+pub trait Rule: Send + Sync {
+    fn id(&self) -> &'static str;
+    
+    fn check(&self, words: &[WordCheck], text: &str, diagnostics: &mut Vec<Diagnostic>);
+}
+
+/// The set of rules that apply to one language
+pub struct RuleEngine {
+    rules: Vec<Box<dyn Rule>>,
+}
+
+impl RuleEngine {
+    pub fn for_language(language: Language) -> Self {
+        let mut rules: Vec<Box<dyn Rule>> = vec![Box::new(RepeatedWordRule::new(language))];
+        
+        if language == Language::English {
+            rules.push(Box::new(ArticleRule));
+        }
+        
+        if let Some(rule) = RealWordRule::for_language(language) {
+            rules.push(Box::new(rule));
+        }
+        
+        Self { rules }
+    }
+    
+    pub fn rule_ids(&self) -> Vec<&'static str> {
+        self.rules.iter().map(|rule| rule.id()).collect()
+    }
+    
+    /// Run every rule and return the diagnostics in document order
+    pub fn check(&self, words: &[WordCheck], text: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        
+        for rule in &self.rules {
+            rule.check(words, text, &mut diagnostics);
+        }
+        
+        diagnostics.sort_by_key(|d| (d.range.start, d.range.end));
+        diagnostics
+    }
+}
+
+/// Flags "the the", including when the repeat starts on the next line
+pub struct RepeatedWordRule {
+    // Doubled words that are often intentional ("had had", "that that")
+    allowed: &'static [&'static str],
+}
+
+impl RepeatedWordRule {
+    pub fn new(language: Language) -> Self {
+        let allowed: &'static [&'static str] = match language {
+            Language::English => &["had", "that"],
+            Language::Afrikaans => &["nie"],
+            Language::French => &["nous", "vous"],
+            Language::German => &["die", "der", "das"],
+            _ => &[],
+        };
+        
+        Self { allowed }
+    }
+}
+
+impl Rule for RepeatedWordRule {
+    fn id(&self) -> &'static str {
+        "repeated-word"
+    }
+    
+    fn check(&self, words: &[WordCheck], text: &str, diagnostics: &mut Vec<Diagnostic>) {
+        for pair in words.windows(2) {
+            let (first, second) = (&pair[0], &pair[1]);
+            
+            if matches!(first.word_type, WordType::Number | WordType::Symbol)
+                || first.word != second.word
+                || self.allowed.contains(&first.word.as_str())
+            {
+                continue;
+            }
+            
+            let Some(gap) = gap_between(first, second, text) else {
+                continue;
+            };
+            
+            // Remove the repeat together with the space before it, unless that
+            // space is a line break, in which case keep the break and eat the
+            // spaces after the word instead
+            let range = if gap.contains('\n') {
+                let trailing = text[word_end(second)..]
+                    .bytes()
+                    .take_while(|&b| b == b' ' || b == b'\t')
+                    .count();
+                second.offset..word_end(second) + trailing
+            } else {
+                word_end(first)..word_end(second)
+            };
+            
+            diagnostics.push(Diagnostic {
+                rule_id: self.id(),
+                kind: DiagnosticKind::Grammar,
+                message: format!("The word '{}' is repeated", second.original),
+                text: second.original.clone(),
+                line: second.line,
+                column: second.column,
+                range,
+                replacement: Some(String::new()),
+            });
+        }
+    }
+}
+
+/// English "a"/"an" agreement with the sound of the following word
+pub struct ArticleRule;
+
+impl Rule for ArticleRule {
+    fn id(&self) -> &'static str {
+        "en-a-an"
+    }
+    
+    fn check(&self, words: &[WordCheck], text: &str, diagnostics: &mut Vec<Diagnostic>) {
+        for pair in words.windows(2) {
+            let (article, next) = (&pair[0], &pair[1]);
+            
+            let uses_an = match article.word.as_str() {
+                "a" => false,
+                "an" => true,
+                _ => continue,
+            };
+            
+            if gap_between(article, next, text).is_none() {
+                continue;
+            }
+            
+            let Some(wants_an) = starts_with_vowel_sound(&next.original) else {
+                continue;
+            };
+            
+            if wants_an == uses_an {
+                continue;
+            }
+            
+            // Keep the article's capitalisation ("A apple" -> "An apple")
+            let mut replacement = if wants_an { "an" } else { "a" }.to_string();
+            if article.original.starts_with('A') {
+                replacement.replace_range(..1, "A");
+            }
+            
+            diagnostics.push(Diagnostic {
+                rule_id: self.id(),
+                kind: DiagnosticKind::Grammar,
+                message: format!("Use '{}' before '{}'", replacement.to_lowercase(), next.original),
+                text: article.original.clone(),
+                line: article.line,
+                column: article.column,
+                range: article.offset..word_end(article),
+                replacement: Some(replacement),
+            });
+        }
+    }
+}
+
+// Correctly spelled words that are easily written in place of each other
+const ENGLISH_CONFUSIONS: &[&[&str]] = &[
+    &["their", "there", "they're"],
+    &["your", "you're"],
+    &["its", "it's"],
+    &["whose", "who's"],
+    &["then", "than"],
+    &["loose", "lose"],
+    &["affect", "effect"],
+    &["accept", "except"],
+    &["to", "too"],
+    &["weather", "whether"],
+    &["quiet", "quite"],
+    &["principal", "principle"],
+    &["passed", "past"],
+    &["breath", "breathe"],
+];
+
+const AFRIKAANS_CONFUSIONS: &[&[&str]] = &[
+    &["wil", "wel"],
+    &["se", "sê"],
+    &["hê", "he"],
+    &["nog", "nou"],
+];
+
+const FRENCH_CONFUSIONS: &[&[&str]] = &[
+    &["a", "à"],
+    &["ou", "où"],
+    &["et", "est"],
+    &["ces", "ses"],
+    &["son", "sont"],
+    &["ce", "se"],
+];
+
+const GERMAN_CONFUSIONS: &[&[&str]] = &[
+    &["das", "dass"],
+    &["seit", "seid"],
+    &["wider", "wieder"],
+    &["wen", "wenn"],
+];
+
+// How much more likely (as a log ratio) an alternative must be before the
+// written word is flagged; ln(20)
+const MIN_LOG_RATIO: f64 = 3.0;
+
+/// Flags a correctly spelled word when another member of its confusion set
+/// is far more likely between the same neighbours ("loose the game"),
+/// judged by the language's bigram counts
+pub struct RealWordRule {
+    model: BigramModel,
+    confusions: &'static [&'static [&'static str]],
+}
+
+impl RealWordRule {
+    /// Only available for languages with confusion sets and an n-gram count file
+    pub fn for_language(language: Language) -> Option<Self> {
+        let confusions: &'static [&'static [&'static str]] = match language {
+            Language::English => ENGLISH_CONFUSIONS,
+            Language::Afrikaans => AFRIKAANS_CONFUSIONS,
+            Language::French => FRENCH_CONFUSIONS,
+            Language::German => GERMAN_CONFUSIONS,
+            _ => return None,
+        };
+        
+        let model = BigramModel::for_language(language).filter(|model| !model.is_empty())?;
+        Some(Self::new(model, confusions))
+    }
+    
+    pub fn new(model: BigramModel, confusions: &'static [&'static [&'static str]]) -> Self {
+        Self { model, confusions }
+    }
+    
+    fn log_probability(&self, previous: Option<&str>, word: &str, next: Option<&str>) -> f64 {
+        previous.map_or(0.0, |p| self.model.log_probability(p, word))
+            + next.map_or(0.0, |n| self.model.log_probability(word, n))
+    }
+    
+    // Only suggest words the corpus has actually seen next to these neighbours
+    fn seen_in_context(&self, previous: Option<&str>, word: &str, next: Option<&str>) -> bool {
+        previous.is_some_and(|p| self.model.bigram(p, word) > 0)
+            || next.is_some_and(|n| self.model.bigram(word, n) > 0)
+    }
+}
+
+impl Rule for RealWordRule {
+    fn id(&self) -> &'static str {
+        "real-word"
+    }
+    
+    fn check(&self, words: &[WordCheck], text: &str, diagnostics: &mut Vec<Diagnostic>) {
+        for (i, word) in words.iter().enumerate() {
+            // Short words are skipped by policy, yet many confusions are
+            // between them ("a"/"à", "ou"/"où", "to"/"too")
+            let short = word.skipped && word.word_type == WordType::ShortWord;
+            if (word.skipped && !short) || !word.is_correct {
+                continue;
+            }
+            
+            let Some(set) = self.confusions.iter().find(|set| set.contains(&word.word.as_str())) else {
+                continue;
+            };
+            
+            // Neighbours only count within the same clause
+            let previous = i
+                .checked_sub(1)
+                .map(|p| &words[p])
+                .filter(|p| gap_between(p, word, text).is_some())
+                .map(|p| p.word.as_str());
+            let next = words
+                .get(i + 1)
+                .filter(|n| gap_between(word, n, text).is_some())
+                .map(|n| n.word.as_str());
+            if previous.is_none() && next.is_none() {
+                continue;
+            }
+            
+            let written = self.log_probability(previous, &word.word, next);
+            let best = set
+                .iter()
+                .filter(|alternative| **alternative != word.word && self.seen_in_context(previous, alternative, next))
+                .map(|alternative| (*alternative, self.log_probability(previous, alternative, next)))
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            
+            let Some((alternative, likelihood)) = best else {
+                continue;
+            };
+            if likelihood - written < MIN_LOG_RATIO {
+                continue;
+            }
+            
+            let replacement = match_case(&word.original, alternative);
+            diagnostics.push(Diagnostic {
+                rule_id: self.id(),
+                kind: DiagnosticKind::RealWord,
+                message: format!("'{}' may be the wrong word here; did you mean '{}'?", word.original, replacement),
+                text: word.original.clone(),
+                line: word.line,
+                column: word.column,
+                range: word.offset..word_end(word),
+                replacement: Some(replacement),
+            });
+        }
+    }
+}
+
+// Words that follow a letter name ("plan A is"), never an article
+const NOT_AFTER_ARTICLE: &[&str] = &[
+    "and", "or", "is", "are", "was", "were", "in", "on", "of", "at", "as",
+    "if", "it", "its", "to", "into", "onto", "up", "out", "off", "etc",
+];
+
+// Vowel letters that are pronounced with a consonant sound
+const CONSONANT_SOUND_PREFIXES: &[&str] = &[
+    "unic", "unif", "unio", "uniq", "unis", "unit", "univ", "use", "usu",
+    "usa", "uti", "ure", "uro", "uku", "ubiq", "eu", "ewe", "one", "once",
+    "ouija",
+];
+
+// Consonant letters that are silent or read as a vowel sound
+const VOWEL_SOUND_PREFIXES: &[&str] = &["hour", "honest", "honor", "honour", "heir"];
+
+// Initialisms read letter by letter start with a vowel sound for these letters
+const VOWEL_SOUND_LETTERS: &str = "AEFHILMNORSX";
+
+/// Whether `word` starts with a vowel sound, or `None` when it can't be told
+/// (numbers, symbols, words that never follow an article)
+fn starts_with_vowel_sound(word: &str) -> Option<bool> {
+    let first = word.chars().next()?;
+    if !first.is_alphabetic() {
+        return None;
+    }
+    
+    let lower = word.to_lowercase();
+    if NOT_AFTER_ARTICLE.contains(&lower.as_str()) {
+        return None;
+    }
+    
+    let letter_count = word.chars().count();
+    if letter_count > 1 && letter_count <= 5 && word.chars().all(|c| c.is_uppercase()) {
+        return Some(VOWEL_SOUND_LETTERS.contains(first));
+    }
+    
+    if CONSONANT_SOUND_PREFIXES.iter().any(|p| lower.starts_with(p)) {
+        return Some(false);
+    }
+    if VOWEL_SOUND_PREFIXES.iter().any(|p| lower.starts_with(p)) {
+        return Some(true);
+    }
+    
+    Some(matches!(lower.chars().next(), Some('a' | 'e' | 'i' | 'o' | 'u')))
+}
+
+// The text between two words, if it is nothing but whitespace
+fn gap_between<'a>(first: &WordCheck, second: &WordCheck, text: &'a str) -> Option<&'a str> {
+    let gap = text.get(word_end(first)..second.offset)?;
+    (!gap.is_empty() && gap.chars().all(char::is_whitespace)).then_some(gap)
+}
+
+fn word_end(word: &WordCheck) -> usize {
+    word.offset + (word.end - word.start)
+}
+
+// Give `replacement` the capitalisation of `original` ("Their" -> "There")
+fn match_case(original: &str, replacement: &str) -> String {
+    if original.chars().count() > 1 && original.chars().all(|c| !c.is_lowercase()) {
+        return replacement.to_uppercase();
+    }
+    
+    let mut chars = replacement.chars();
+    match (original.starts_with(char::is_uppercase), chars.next()) {
+        (true, Some(first)) => first.to_uppercase().chain(chars).collect(),
+        _ => replacement.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // Words of `text` as the checker reports them, with short words skipped
+    fn words(text: &str) -> Vec<WordCheck> {
+        text.split_whitespace()
+            .map(|word| {
+                let offset = word.as_ptr() as usize - text.as_ptr() as usize;
+                let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+                let short = word.chars().count() < 3;
+                let word_type = if word.chars().all(|c| c.is_ascii_digit()) {
+                    WordType::Number
+                } else if short {
+                    WordType::ShortWord
+                } else {
+                    WordType::Normal
+                };
+                WordCheck {
+                    word: word.to_lowercase(),
+                    original: word.to_string(),
+                    start: offset,
+                    end: offset + word.len(),
+                    offset,
+                    is_correct: true,
+                    skipped: short,
+                    suggestions: Vec::new(),
+                    line: text[..offset].matches('\n').count() + 1,
+                    column: offset - line_start + 1,
+                    confidence: 1.0,
+                    word_type,
+                    compound: None,
+                    identifier: None,
+                }
+            })
+            .collect()
+    }
+    
+    // `text` with every replacement `rule` proposes applied
+    fn fixed(rule: &dyn Rule, text: &str) -> String {
+        let mut diagnostics = Vec::new();
+        rule.check(&words(text), text, &mut diagnostics);
+        
+        let mut fixed = text.to_string();
+        for diagnostic in diagnostics.iter().rev() {
+            fixed.replace_range(diagnostic.range.clone(), diagnostic.replacement.as_deref().unwrap());
+        }
+        fixed
+    }
+    
+    fn replacements(text: &str) -> Vec<String> {
+        let counts = "il\t200\nva\t100\na\t50\nà\t500\net\t50\nest\t500\nva à\t80\nil est\t90\nil a\t90\n";
+        let model = BigramModel::parse(counts.as_bytes()).unwrap();
+        let mut diagnostics = Vec::new();
+        RealWordRule::new(model, FRENCH_CONFUSIONS).check(&words(text), text, &mut diagnostics);
+        diagnostics.into_iter().filter_map(|d| d.replacement).collect()
+    }
+    
+    #[test]
+    fn short_words_in_confusion_sets() {
+        assert_eq!(replacements("il va a Paris"), ["à"]);
+        assert_eq!(replacements("il et grand"), ["est"]);
+        assert!(replacements("il va à Paris").is_empty());
+    }
+    
+    #[test]
+    fn repeated_words() {
+        let rule = RepeatedWordRule::new(Language::English);
+        
+        assert_eq!(fixed(&rule, "the the house"), "the house");
+        assert_eq!(fixed(&rule, "we saw the house house"), "we saw the house");
+        assert_eq!(fixed(&rule, "The the house"), "The house");
+        assert_eq!(fixed(&rule, "she had had enough"), "she had had enough");
+        assert_eq!(fixed(&rule, "page 4 4 of"), "page 4 4 of");
+    }
+    
+    #[test]
+    fn repetition_across_a_line_break() {
+        let rule = RepeatedWordRule::new(Language::English);
+        let text = "we walked to the\nthe  house";
+        let mut diagnostics = Vec::new();
+        rule.check(&words(text), text, &mut diagnostics);
+        
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 1));
+        // The break stays; the repeat goes with the spaces after it
+        assert_eq!(fixed(&rule, text), "we walked to the\nhouse");
+    }
+    
+    #[test]
+    fn article_agrees_with_the_sound_that_follows() {
+        assert_eq!(fixed(&ArticleRule, "a apple and an pear"), "an apple and a pear");
+        assert_eq!(fixed(&ArticleRule, "A apple"), "An apple");
+        assert_eq!(fixed(&ArticleRule, "a hour"), "an hour");
+        assert_eq!(fixed(&ArticleRule, "an university"), "a university");
+        assert_eq!(fixed(&ArticleRule, "an one-off"), "a one-off");
+        assert_eq!(fixed(&ArticleRule, "a FBI agent"), "an FBI agent");
+        assert_eq!(fixed(&ArticleRule, "an hour"), "an hour");
+        assert_eq!(fixed(&ArticleRule, "a university"), "a university");
+        assert_eq!(fixed(&ArticleRule, "an honest a European"), "an honest a European");
+        assert_eq!(fixed(&ArticleRule, "a\napple"), "an\napple");
+        assert_eq!(fixed(&ArticleRule, "a 8 and a, apple"), "a 8 and a, apple");
+    }
+}
@@ -1,6 +1,7 @@
-use crate::checker::{DocumentAnalysis, SpellChecker};
+use crate::checker::{DocumentAnalysis, DocumentEdit, SpellChecker, WordType};
 use crate::editor::TextEditor;
 use crate::language::{Language, LanguageManager};
+use crate::policy::CheckPolicy;
 use crate::sidebar::Sidebar;
 use crate::theme::AtomTheme;
 use crate::{open_repository, open_sponsor_page};
@@ -32,6 +33,10 @@ pub struct AppState {
     pub show_settings: bool,
     pub enable_syntax_highlighting: bool,
     pub check_interval_ms: u64,
+    pub accent_lenient: bool,
+    pub check_policy: CheckPolicy,
+    /// Worker threads for checking large documents, 0 for all cores
+    pub check_threads: usize,
 }
 
 impl Default for AppState {
@@ -56,6 +61,9 @@ impl Default for AppState {
             show_settings: false,
             enable_syntax_highlighting: true,
             check_interval_ms: 1500,
+            accent_lenient: false,
+            check_policy: CheckPolicy::default(),
+            check_threads: 0,
         }
     }
 }
@@ -73,11 +81,16 @@ pub struct SpellCheckerApp {
     analysis: Option<DocumentAnalysis>,
     pending_add_word: Option<String>,
     pending_ignore_word: Option<String>,
-    pending_replace: Option<(String, String)>,
+    // (find, replace, whether a suggestion was chosen)
+    pending_replace: Option<(String, String, bool)>,
+    pending_fix: Option<(std::ops::Range<usize>, String)>,
     pending_import_dict: bool,
     pending_export_dict: bool,
     pending_clear_ignored: bool,
-    last_spell_check: Option<DocumentAnalysis>,
+    // The text `analysis` was produced from, used to work out what changed
+    checked_content: String,
+    // Offset of a word skipped while it was being typed, checked once typing pauses
+    deferred_word: Option<usize>,
     show_notification: Option<(String, egui::Color32)>,
     notification_timer: Instant,
 }
@@ -104,7 +117,14 @@ impl SpellCheckerApp {
         let language_manager = LanguageManager::new();
         
         let spell_checker = match SpellChecker::new(state.selected_language) {
-            Ok(checker) => Arc::new(std::sync::Mutex::new(checker)),
+            Ok(mut checker) => {
+                checker.set_accent_lenient(state.accent_lenient);
+                checker.set_policy(state.check_policy.clone());
+                if let Err(e) = checker.set_max_threads(state.check_threads) {
+                    eprintln!("Failed to set checker threads: {}", e);
+                }
+                Arc::new(std::sync::Mutex::new(checker))
+            }
             Err(e) => {
                 eprintln!("Failed to create spell checker: {}", e);
                 Arc::new(std::sync::Mutex::new(
@@ -132,10 +152,12 @@ impl SpellCheckerApp {
             pending_add_word: None,
             pending_ignore_word: None,
             pending_replace: None,
+            pending_fix: None,
             pending_import_dict: false,
             pending_export_dict: false,
             pending_clear_ignored: false,
-            last_spell_check: None,
+            checked_content: String::new(),
+            deferred_word: None,
             show_notification: None,
             notification_timer: Instant::now(),
         }
@@ -164,26 +186,107 @@ impl SpellCheckerApp {
             }
         }
         
+        // The whole path, as translation files may be named by their
+        // directory ("values-fr/strings.xml")
         let filename = self.state.current_file
             .as_ref()
-            .and_then(|p| p.file_name())
-            .and_then(|n| n.to_str());
+            .and_then(|p| p.to_str());
         
         let analysis = {
             let checker = self.spell_checker.lock().unwrap();
             checker.check_document(&self.state.document_content, filename)
         };
         
-        self.analysis = Some(analysis.clone());
+        self.text_editor.set_analysis(&analysis);
+        self.text_editor.forget_cursor_after(0);
+        self.checked_content.clone_from(&self.state.document_content);
+        self.deferred_word = None;
+        self.finish_check(analysis, start_time);
+    }
+    
+    // Re-check only the lines changed since the last check, skipping the
+    // word at the cursor when `at_cursor`
+    fn recheck_edit(&mut self, edit: Option<DocumentEdit>, at_cursor: bool) {
+        let Some(mut previous) = self.analysis.take() else {
+            self.check_spelling();
+            return;
+        };
+        
+        let edit = match edit.or_else(|| DocumentEdit::between(&self.checked_content, &self.state.document_content)) {
+            Some(edit) => edit,
+            None => {
+                self.analysis = Some(previous);
+                return;
+            }
+        };
+        
+        let start_time = Instant::now();
+        let cursor = if at_cursor {
+            self.text_editor.cursor_offset(&self.state.document_content, edit.range.start)
+        } else {
+            self.text_editor.forget_cursor_after(edit.range.start);
+            None
+        };
+        
+        // A word left unchecked on another line is finished now that the user moved on
+        if let Some(offset) = self.deferred_word.take() {
+            let touch = DocumentEdit::touch(offset);
+            let line = touch.first_line(&self.checked_content);
+            let first_line = edit.first_line(&self.checked_content);
+            
+            if line < first_line || line > first_line + edit.removed_lines {
+                previous = recheck_lines(
+                    &self.spell_checker,
+                    &mut self.text_editor,
+                    previous,
+                    &self.checked_content,
+                    &touch,
+                    None,
+                );
+            }
+        }
+        
+        let analysis = recheck_lines(
+            &self.spell_checker,
+            &mut self.text_editor,
+            previous,
+            &self.state.document_content,
+            &edit,
+            cursor,
+        );
+        
+        self.checked_content.clone_from(&self.state.document_content);
+        self.deferred_word = cursor;
+        self.finish_check(analysis, start_time);
+    }
+    
+    // Called once typing pauses: check the deferred word and re-detect the language
+    fn finish_pending_check(&mut self) {
+        let Some(offset) = self.deferred_word.take() else {
+            self.last_check_time = Instant::now();
+            return;
+        };
+        
+        if self.state.auto_detect_language {
+            let detected = self.language_manager.detect_language(&self.state.document_content);
+            if Some(detected) != self.stats.detected_language {
+                self.check_spelling();
+                return;
+            }
+        }
+        
+        self.recheck_edit(Some(DocumentEdit::touch(offset.min(self.state.document_content.len()))), false);
+    }
+    
+    fn finish_check(&mut self, analysis: DocumentAnalysis, start_time: Instant) {
         self.stats.total_words = analysis.total_words;
         self.stats.errors = analysis.misspelled_words;
         self.stats.last_check_duration = start_time.elapsed();
         self.stats.check_count += 1;
         self.stats.total_characters = self.state.document_content.chars().count();
-        self.stats.total_lines = self.state.document_content.lines().count();
+        self.stats.total_lines = analysis.lines_checked;
         
-        self.text_editor.set_analysis(analysis.clone());
-        self.last_spell_check = Some(analysis);
+        self.analysis = Some(analysis);
         self.last_check_time = Instant::now();
     }
     
@@ -256,13 +359,9 @@ impl SpellCheckerApp {
     }
     
     fn handle_file_drop(&mut self, ctx: &egui::Context) {
-        if !ctx.input(|i| i.raw.hovered_files.is_empty()) {
-            self.is_dragging_file = true;
-        } else {
-            self.is_dragging_file = false;
-        }
+        self.is_dragging_file = !ctx.input(|i| i.raw.hovered_files.is_empty());
         
-        if ctx.input(|i| i.raw.dropped_files.len() > 0) {
+        if ctx.input(|i| !i.raw.dropped_files.is_empty()) {
             if let Some(file) = ctx.input(|i| i.raw.dropped_files[0].path.clone()) {
                 if let Err(e) = self.open_file(file) {
                     self.show_notification(format!("Failed to open file: {}", e), egui::Color32::RED);
@@ -271,11 +370,7 @@ impl SpellCheckerApp {
             self.drop_highlight = false;
         }
         
-        if ctx.input(|i| i.pointer.any_down()) && self.is_dragging_file {
-            self.drop_highlight = true;
-        } else {
-            self.drop_highlight = false;
-        }
+        self.drop_highlight = ctx.input(|i| i.pointer.any_down()) && self.is_dragging_file;
     }
     
     fn handle_pending_actions(&mut self) {
@@ -304,15 +399,30 @@ impl SpellCheckerApp {
             self.check_spelling();
         }
         
-        if let Some((find, replace)) = self.pending_replace.take() {
+        if let Some((find, replace, from_suggestion)) = self.pending_replace.take() {
             if !find.is_empty() {
                 self.state.document_content = self.state.document_content.replace(&find, &replace);
                 self.state.is_document_modified = true;
+                // Only suggestions count towards ranking, not Find/Replace text
+                if from_suggestion {
+                    if let Ok(mut checker) = self.spell_checker.lock() {
+                        checker.record_accepted_suggestion(&replace).ok();
+                    }
+                }
                 self.check_spelling();
                 self.show_notification(format!("Replaced '{}' with '{}'", find, replace), egui::Color32::GREEN);
             }
         }
         
+        if let Some((range, replacement)) = self.pending_fix.take() {
+            let content = &self.state.document_content;
+            if content.is_char_boundary(range.start) && range.end <= content.len() && content.is_char_boundary(range.end) {
+                self.state.document_content.replace_range(range, &replacement);
+                self.state.is_document_modified = true;
+                self.recheck_edit(None, false);
+            }
+        }
+        
         if self.pending_import_dict {
             self.pending_import_dict = false;
             if let Some(path) = FileDialog::new()
@@ -412,6 +522,88 @@ impl SpellCheckerApp {
         self.state.show_about = show_about;
     }
     
+    // Returns whether any checking rule changed
+    fn show_policy_settings(&mut self, ui: &mut egui::Ui) -> bool {
+        let policy = &mut self.state.check_policy;
+        let mut changed = false;
+        
+        ui.heading("Checking");
+        
+        egui::Grid::new("policy_grid")
+            .num_columns(2)
+            .spacing([20.0, 10.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Minimum confidence:");
+                changed |= ui.add(egui::Slider::new(&mut policy.min_confidence, 0.0..=1.0)).changed();
+                ui.end_row();
+                
+                ui.label("Minimum word length:");
+                changed |= ui.add(egui::DragValue::new(&mut policy.min_word_length).clamp_range(1..=10)).changed();
+                ui.end_row();
+                
+                ui.label("Maximum suggestions:");
+                changed |= ui.add(egui::DragValue::new(&mut policy.max_suggestions).clamp_range(1..=20)).changed();
+                ui.end_row();
+                
+                ui.label("Checker threads:");
+                let threads = ui.add(egui::DragValue::new(&mut self.state.check_threads)
+                    .clamp_range(0..=64)
+                    .custom_formatter(|n, _| if n == 0.0 { "All cores".to_string() } else { format!("{}", n) }));
+                // Each value builds a thread pool, so a drag only applies once let go
+                if threads.drag_stopped() || (threads.changed() && !threads.dragged()) {
+                    if let Err(e) = self.spell_checker.lock().unwrap().set_max_threads(self.state.check_threads) {
+                        eprintln!("Failed to set checker threads: {}", e);
+                    }
+                }
+                ui.end_row();
+                
+                ui.label("Suggestion distance:");
+                changed |= ui.add(egui::DragValue::new(&mut policy.max_suggestion_distance)
+                    .clamp_range(1..=crate::symspell::DEFAULT_MAX_DISTANCE)
+                    .suffix(" edits")).changed();
+                ui.end_row();
+            });
+        
+        ui.add_space(6.0);
+        
+        egui::Grid::new("word_type_grid")
+            .num_columns(5)
+            .spacing([20.0, 6.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Word type");
+                ui.strong("Check");
+                ui.strong("Weight");
+                ui.strong("In code");
+                ui.strong("Accept plausible");
+                ui.end_row();
+                
+                for word_type in WordType::ALL {
+                    let rules = policy.for_type_mut(&word_type);
+                    ui.label(word_type.label());
+                    changed |= ui.checkbox(&mut rules.check, "").changed();
+                    changed |= ui.add(egui::DragValue::new(&mut rules.weight)
+                        .clamp_range(0.0..=2.0)
+                        .speed(0.05)).changed();
+                    match &mut rules.code_weight {
+                        Some(code_weight) => {
+                            changed |= ui.add(egui::DragValue::new(code_weight)
+                                .clamp_range(0.0..=2.0)
+                                .speed(0.05)).changed();
+                        }
+                        None => {
+                            ui.label("");
+                        }
+                    }
+                    changed |= ui.checkbox(&mut rules.accept_plausible, "").changed();
+                    ui.end_row();
+                }
+            });
+        
+        changed
+    }
+    
     fn show_settings_dialog(&mut self, ctx: &egui::Context) {
         let mut show_settings = self.state.show_settings;
         
@@ -458,6 +650,13 @@ impl SpellCheckerApp {
                         ui.checkbox(&mut self.state.enable_syntax_highlighting, "");
                         ui.end_row();
                         
+                        ui.label("Accept words without accents:");
+                        if ui.checkbox(&mut self.state.accent_lenient, "").changed() {
+                            self.spell_checker.lock().unwrap().set_accent_lenient(self.state.accent_lenient);
+                            self.check_spelling();
+                        }
+                        ui.end_row();
+                        
                         ui.label("Font size:");
                         ui.add(egui::DragValue::new(&mut self.state.font_size)
                             .clamp_range(8.0..=36.0)
@@ -467,6 +666,13 @@ impl SpellCheckerApp {
                 
                 ui.separator();
                 
+                if self.show_policy_settings(ui) {
+                    self.spell_checker.lock().unwrap().set_policy(self.state.check_policy.clone());
+                    self.check_spelling();
+                }
+                
+                ui.separator();
+                
                 ui.horizontal(|ui| {
                     if ui.button("Reset to Defaults").clicked() {
                         // Create a default state instead of trying to reconstruct the app
@@ -474,6 +680,10 @@ impl SpellCheckerApp {
                         self.text_editor.set_font_size(self.state.font_size);
                         self.text_editor.set_wrap_lines(self.state.wrap_text);
                         self.check_interval = std::time::Duration::from_millis(self.state.check_interval_ms);
+                        let mut checker = self.spell_checker.lock().unwrap();
+                        checker.set_accent_lenient(self.state.accent_lenient);
+                        checker.set_policy(self.state.check_policy.clone());
+                        checker.set_max_threads(self.state.check_threads).ok();
                     }
                     
                     if ui.button("Save").clicked() {
@@ -579,6 +789,7 @@ impl SpellCheckerApp {
                     self.state.document_content.clear();
                     self.state.is_document_modified = true;
                     self.analysis = None;
+                    self.text_editor.clear_analysis();
                     ui.close_menu();
                 }
             });
@@ -849,6 +1060,7 @@ impl SpellCheckerApp {
                         &mut self.pending_add_word,
                         &mut self.pending_ignore_word,
                         &mut self.pending_replace,
+                        &mut self.pending_fix,
                         &mut self.pending_import_dict,
                         &mut self.pending_export_dict,
                         &mut self.pending_clear_ignored,
@@ -876,11 +1088,10 @@ impl SpellCheckerApp {
                 &mut self.state.document_content,
                 &mut self.state.is_document_modified,
                 self.state.show_line_numbers,
-                &self.analysis,
             );
             
             if editor_response.changed && self.state.auto_check {
-                self.check_spelling();
+                self.recheck_edit(None, true);
             }
             
             self.show_notification_overlay(ui);
@@ -888,6 +1099,27 @@ impl SpellCheckerApp {
     }
 }
 
+// Runs an incremental check and keeps the editor's error cache in step with it
+fn recheck_lines(
+    spell_checker: &std::sync::Mutex<SpellChecker>,
+    text_editor: &mut TextEditor,
+    previous: DocumentAnalysis,
+    text: &str,
+    edit: &DocumentEdit,
+    cursor: Option<usize>,
+) -> DocumentAnalysis {
+    let (analysis, lines) = spell_checker.lock().unwrap().recheck_edit(previous, text, edit, cursor);
+    
+    // Without the lines re-checked, lines away from the edit may have
+    // changed, so the whole cache must be rebuilt
+    match lines {
+        Some(lines) => text_editor.apply_edit(&analysis, lines),
+        None => text_editor.set_analysis(&analysis),
+    }
+    
+    analysis
+}
+
 impl eframe::App for SpellCheckerApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         self.handle_pending_actions();
@@ -916,7 +1148,7 @@ impl eframe::App for SpellCheckerApp {
         });
         
         if self.state.auto_check && self.last_check_time.elapsed() > self.check_interval {
-            self.check_spelling();
+            self.finish_pending_check();
         }
         
         ctx.request_repaint();
@@ -0,0 +1,643 @@
+use crate::compound::{CompoundPosition, MAX_PARTS};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+// Hunspell flags are stored as plain integers regardless of the FLAG mode
+// used by the .aff file (single char, two-char "long", numeric or UTF-8).
+type Flag = u32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlagMode {
+    Char,
+    Long,
+    Numeric,
+    Utf8,
+}
+
+impl FlagMode {
+    fn parse(&self, flags: &str) -> Vec<Flag> {
+        match self {
+            // Non-ASCII flags are one flag each in either mode
+            FlagMode::Char | FlagMode::Utf8 => flags.chars().map(Flag::from).collect(),
+            FlagMode::Long => {
+                let chars: Vec<char> = flags.chars().collect();
+                chars
+                    .chunks(2)
+                    .map(|pair| {
+                        let first = pair[0] as Flag;
+                        let second = pair.get(1).map(|c| *c as Flag).unwrap_or(0);
+                        (first << 16) | second
+                    })
+                    .collect()
+            }
+            FlagMode::Numeric => flags
+                .split(',')
+                .filter_map(|n| n.trim().parse::<Flag>().ok())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ConditionPart {
+    Any,
+    Char(char),
+    Set(Vec<char>, bool),
+}
+
+impl ConditionPart {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            ConditionPart::Any => true,
+            ConditionPart::Char(expected) => *expected == c,
+            ConditionPart::Set(chars, negated) => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+/// Affix condition such as `[^aeiou]y` or `.`
+#[derive(Debug, Clone)]
+struct Condition {
+    parts: Vec<ConditionPart>,
+}
+
+impl Condition {
+    fn parse(pattern: &str) -> Self {
+        let mut parts = Vec::new();
+        let mut chars = pattern.chars().peekable();
+        
+        while let Some(c) = chars.next() {
+            match c {
+                '.' => parts.push(ConditionPart::Any),
+                '[' => {
+                    let negated = chars.peek() == Some(&'^');
+                    if negated {
+                        chars.next();
+                    }
+                    let mut set = Vec::new();
+                    for c in chars.by_ref() {
+                        if c == ']' {
+                            break;
+                        }
+                        set.push(c);
+                    }
+                    parts.push(ConditionPart::Set(set, negated));
+                }
+                c => parts.push(ConditionPart::Char(c)),
+            }
+        }
+        
+        Self { parts }
+    }
+    
+    fn matches_start(&self, word: &[char]) -> bool {
+        word.len() >= self.parts.len() &&
+            self.parts.iter().zip(word.iter()).all(|(part, c)| part.matches(*c))
+    }
+    
+    fn matches_end(&self, word: &[char]) -> bool {
+        word.len() >= self.parts.len() &&
+            self.parts.iter().rev().zip(word.iter().rev()).all(|(part, c)| part.matches(*c))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AffixEntry {
+    strip: String,
+    add: String,
+    condition: Condition,
+    continuation: Vec<Flag>,
+}
+
+#[derive(Debug, Clone)]
+struct AffixClass {
+    cross_product: bool,
+    entries: Vec<AffixEntry>,
+}
+
+/// Parsed contents of a Hunspell `.aff` file
+#[derive(Debug, Clone)]
+pub struct AffixRules {
+    flag_mode: FlagMode,
+    prefixes: HashMap<Flag, AffixClass>,
+    suffixes: HashMap<Flag, AffixClass>,
+    aliases: Vec<Vec<Flag>>,
+    compound_flag: Option<Flag>,
+    compound_begin: Option<Flag>,
+    compound_middle: Option<Flag>,
+    compound_end: Option<Flag>,
+    only_in_compound: Option<Flag>,
+    compound_min: usize,
+    no_suggest: Option<Flag>,
+    forbidden_word: Option<Flag>,
+    need_affix: Option<Flag>,
+}
+
+impl Default for AffixRules {
+    fn default() -> Self {
+        Self {
+            flag_mode: FlagMode::Char,
+            prefixes: HashMap::new(),
+            suffixes: HashMap::new(),
+            aliases: Vec::new(),
+            compound_flag: None,
+            compound_begin: None,
+            compound_middle: None,
+            compound_end: None,
+            only_in_compound: None,
+            compound_min: 3,
+            no_suggest: None,
+            forbidden_word: None,
+            need_affix: None,
+        }
+    }
+}
+
+/// Words that may appear as parts of a closed compound, grouped by position
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompoundRules {
+    pub anywhere: HashSet<String>,
+    pub begin: HashSet<String>,
+    pub middle: HashSet<String>,
+    pub end: HashSet<String>,
+    pub min_length: usize,
+}
+
+impl CompoundRules {
+    pub fn is_empty(&self) -> bool {
+        self.anywhere.is_empty() && self.begin.is_empty() &&
+            self.middle.is_empty() && self.end.is_empty()
+    }
+    
+    /// Whether `part` may stand at `position` in a compound
+    pub fn allows(&self, part: &str, position: CompoundPosition) -> bool {
+        if self.anywhere.contains(part) {
+            return true;
+        }
+        match position {
+            CompoundPosition::Begin => self.begin.contains(part),
+            CompoundPosition::Middle => self.middle.contains(part),
+            CompoundPosition::End => self.end.contains(part),
+        }
+    }
+    
+    /// Check whether a word can be built from two or more compound parts
+    pub fn matches(&self, word: &str) -> bool {
+        let chars: Vec<char> = word.chars().collect();
+        self.matches_from(&chars, 0, 0)
+    }
+    
+    fn matches_from(&self, chars: &[char], start: usize, depth: usize) -> bool {
+        let min = self.min_length.max(1);
+        let remaining = chars.len() - start;
+        
+        // Hunspell allows arbitrarily many parts, but more than a few are
+        // more likely run-together words than a real compound
+        if depth >= MAX_PARTS || remaining < min {
+            return false;
+        }
+        
+        for end in (start + min)..=chars.len() {
+            let part: String = chars[start..end].iter().collect();
+            
+            if end == chars.len() {
+                if depth > 0 && self.allows(&part, CompoundPosition::End) {
+                    return true;
+                }
+                continue;
+            }
+            
+            let position = if depth == 0 { CompoundPosition::Begin } else { CompoundPosition::Middle };
+            if self.allows(&part, position) && self.matches_from(chars, end, depth + 1) {
+                return true;
+            }
+        }
+        
+        false
+    }
+}
+
+/// Word forms expanded from a `.dic` file, kept in one text buffer.
+///
+/// Affix expansion multiplies the stems many times over (10 million forms is
+/// not unusual for richly inflected languages), and every form is held until
+/// the word list is compiled. A `HashSet<String>` costs about 100 bytes per
+/// form at that size; here a form costs its text plus 16 bytes. Once
+/// `finish`ed the forms are sorted and free of duplicates, ready to be
+/// streamed into an FST.
+#[derive(Debug, Clone, Default)]
+pub struct Forms {
+    text: String,
+    // Start and length in `text`
+    spans: Vec<(usize, usize)>,
+}
+
+impl Forms {
+    fn push(&mut self, form: &str) {
+        self.spans.push((self.text.len(), form.len()));
+        self.text.push_str(form);
+    }
+    
+    fn get(&self, (start, len): (usize, usize)) -> &str {
+        &self.text[start..start + len]
+    }
+    
+    fn finish(&mut self) {
+        let mut spans = std::mem::take(&mut self.spans);
+        spans.sort_unstable_by(|a, b| self.get(*a).cmp(self.get(*b)));
+        spans.dedup_by(|a, b| self.get(*a) == self.get(*b));
+        spans.shrink_to_fit();
+        self.spans = spans;
+    }
+    
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+    
+    pub fn contains(&self, word: &str) -> bool {
+        self.spans.binary_search_by(|span| self.get(*span).cmp(word)).is_ok()
+    }
+    
+    /// The forms in lexicographic order
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.spans.iter().map(|span| self.get(*span))
+    }
+}
+
+/// Everything produced by expanding a Hunspell `.aff`/`.dic` pair
+#[derive(Debug, Clone, Default)]
+pub struct HunspellWords {
+    pub words: Forms,
+    pub no_suggest: HashSet<String>,
+    pub forbidden: HashSet<String>,
+    pub compounds: CompoundRules,
+}
+
+/// Read a Hunspell file, honouring the `SET` encoding declared in the .aff file
+fn read_encoded(path: &Path, encoding: Option<&str>) -> anyhow::Result<String> {
+    let bytes = fs::read(path)?;
+    
+    let encoding = encoding
+        .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    
+    let (text, _, _) = encoding.decode(&bytes);
+    Ok(text.into_owned())
+}
+
+fn detect_encoding(aff_path: &Path) -> Option<String> {
+    let bytes = fs::read(aff_path).ok()?;
+    let text = String::from_utf8_lossy(&bytes);
+    
+    text.lines()
+        .find_map(|line| line.trim().strip_prefix("SET "))
+        .map(|label| label.trim().to_string())
+}
+
+impl AffixRules {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let encoding = detect_encoding(path);
+        let content = read_encoded(path, encoding.as_deref())?;
+        Ok(Self::parse(&content))
+    }
+    
+    pub fn parse(content: &str) -> Self {
+        let mut rules = AffixRules::default();
+        
+        // FLAG changes how every other flag is read, so it must be known first
+        for line in content.lines() {
+            let mut fields = line.split_whitespace();
+            if fields.next() == Some("FLAG") {
+                rules.flag_mode = match fields.next() {
+                    Some("long") => FlagMode::Long,
+                    Some("num") => FlagMode::Numeric,
+                    Some("UTF-8") => FlagMode::Utf8,
+                    _ => FlagMode::Char,
+                };
+            }
+        }
+        
+        let mut lines = content.lines();
+        while let Some(line) = lines.next() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.is_empty() || fields[0].starts_with('#') {
+                continue;
+            }
+            
+            match fields[0] {
+                // The first AF line carries the alias count and the lines
+                // after it the aliases, which look the same with numeric flags
+                "AF" if fields.len() >= 2 => {
+                    let count = fields[1].parse::<usize>().unwrap_or(0);
+                    for _ in 0..count {
+                        let Some(alias_line) = lines.next() else { break };
+                        let alias: Vec<&str> = alias_line.split_whitespace().collect();
+                        if alias.len() >= 2 && alias[0] == "AF" {
+                            rules.aliases.push(rules.flag_mode.parse(alias[1]));
+                        }
+                    }
+                }
+                "PFX" | "SFX" if fields.len() >= 4 => {
+                    let Some(flag) = rules.single_flag(fields[1]) else { continue };
+                    let cross_product = fields[2] == "Y";
+                    let count = fields[3].parse::<usize>().unwrap_or(0);
+                    
+                    let mut entries = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        let Some(entry_line) = lines.next() else { break };
+                        if let Some(entry) = rules.parse_entry(entry_line) {
+                            entries.push(entry);
+                        }
+                    }
+                    
+                    let class = AffixClass { cross_product, entries };
+                    if fields[0] == "PFX" {
+                        rules.prefixes.insert(flag, class);
+                    } else {
+                        rules.suffixes.insert(flag, class);
+                    }
+                }
+                "COMPOUNDFLAG" if fields.len() >= 2 => rules.compound_flag = rules.single_flag(fields[1]),
+                "COMPOUNDBEGIN" if fields.len() >= 2 => rules.compound_begin = rules.single_flag(fields[1]),
+                "COMPOUNDMIDDLE" if fields.len() >= 2 => rules.compound_middle = rules.single_flag(fields[1]),
+                "COMPOUNDEND" if fields.len() >= 2 => rules.compound_end = rules.single_flag(fields[1]),
+                "ONLYINCOMPOUND" if fields.len() >= 2 => rules.only_in_compound = rules.single_flag(fields[1]),
+                "NOSUGGEST" if fields.len() >= 2 => rules.no_suggest = rules.single_flag(fields[1]),
+                "FORBIDDENWORD" if fields.len() >= 2 => rules.forbidden_word = rules.single_flag(fields[1]),
+                "NEEDAFFIX" | "PSEUDOROOT" if fields.len() >= 2 => rules.need_affix = rules.single_flag(fields[1]),
+                "COMPOUNDMIN" if fields.len() >= 2 => {
+                    rules.compound_min = fields[1].parse().unwrap_or(3);
+                }
+                _ => {}
+            }
+        }
+        
+        rules
+    }
+    
+    fn single_flag(&self, field: &str) -> Option<Flag> {
+        self.flag_mode.parse(field).into_iter().next()
+    }
+    
+    fn parse_entry(&self, line: &str) -> Option<AffixEntry> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 4 {
+            return None;
+        }
+        
+        let strip = if fields[2] == "0" { String::new() } else { fields[2].to_string() };
+        
+        let (add, continuation) = match fields[3].split_once('/') {
+            Some((add, flags)) => (add, self.resolve_flags(flags)),
+            None => (fields[3], Vec::new()),
+        };
+        let add = if add == "0" { String::new() } else { add.to_string() };
+        
+        let condition = Condition::parse(fields.get(4).copied().unwrap_or("."));
+        
+        Some(AffixEntry { strip, add, condition, continuation })
+    }
+    
+    /// Parse a flag field, expanding `AF` aliases when the file defines them
+    fn resolve_flags(&self, flags: &str) -> Vec<Flag> {
+        if !self.aliases.is_empty() {
+            if let Ok(index) = flags.parse::<usize>() {
+                return self.aliases.get(index.wrapping_sub(1)).cloned().unwrap_or_default();
+            }
+        }
+        self.flag_mode.parse(flags)
+    }
+    
+    fn has(flags: &[Flag], flag: Option<Flag>) -> bool {
+        flag.map(|f| flags.contains(&f)).unwrap_or(false)
+    }
+    
+    fn apply_suffix(entry: &AffixEntry, word: &str) -> Option<String> {
+        let chars: Vec<char> = word.chars().collect();
+        if !entry.condition.matches_end(&chars) || !word.ends_with(&entry.strip) {
+            return None;
+        }
+        let stem = &word[..word.len() - entry.strip.len()];
+        if stem.is_empty() && entry.add.is_empty() {
+            return None;
+        }
+        Some(format!("{}{}", stem, entry.add))
+    }
+    
+    fn apply_prefix(entry: &AffixEntry, word: &str) -> Option<String> {
+        let chars: Vec<char> = word.chars().collect();
+        if !entry.condition.matches_start(&chars) || !word.starts_with(&entry.strip) {
+            return None;
+        }
+        let stem = &word[entry.strip.len()..];
+        if stem.is_empty() && entry.add.is_empty() {
+            return None;
+        }
+        Some(format!("{}{}", entry.add, stem))
+    }
+    
+    /// Generate every surface form of a stem with the given flags.
+    /// Each form is returned with the flags it carries (stem flags plus any
+    /// continuation flags of the affixes that produced it).
+    pub fn expand(&self, stem: &str, flags: &[Flag]) -> Vec<(String, Vec<Flag>)> {
+        let mut forms = Vec::new();
+        
+        if !Self::has(flags, self.need_affix) {
+            forms.push((stem.to_string(), flags.to_vec()));
+        }
+        
+        // Suffixes, with one level of continuation (twofold suffixes)
+        let mut suffixed: Vec<(String, bool, Vec<Flag>)> = Vec::new();
+        for flag in flags {
+            let Some(class) = self.suffixes.get(flag) else { continue };
+            for entry in &class.entries {
+                let Some(form) = Self::apply_suffix(entry, stem) else { continue };
+                
+                for cont in &entry.continuation {
+                    if let Some(cont_class) = self.suffixes.get(cont) {
+                        for cont_entry in &cont_class.entries {
+                            if let Some(twofold) = Self::apply_suffix(cont_entry, &form) {
+                                forms.push((twofold, flags.to_vec()));
+                            }
+                        }
+                    }
+                }
+                
+                if !Self::has(&entry.continuation, self.need_affix) {
+                    let mut form_flags = flags.to_vec();
+                    form_flags.extend(entry.continuation.iter().copied());
+                    forms.push((form.clone(), form_flags.clone()));
+                }
+                suffixed.push((form, class.cross_product, entry.continuation.clone()));
+            }
+        }
+        
+        // Prefixes, combined with cross-product suffixes
+        for flag in flags {
+            let Some(class) = self.prefixes.get(flag) else { continue };
+            for entry in &class.entries {
+                if let Some(form) = Self::apply_prefix(entry, stem) {
+                    forms.push((form, flags.to_vec()));
+                }
+                
+                if !class.cross_product {
+                    continue;
+                }
+                for (suffixed_form, suffix_cross, _) in &suffixed {
+                    if *suffix_cross {
+                        if let Some(form) = Self::apply_prefix(entry, suffixed_form) {
+                            forms.push((form, flags.to_vec()));
+                        }
+                    }
+                }
+            }
+        }
+        
+        forms
+    }
+    
+    /// Expand a `.dic` word list using these affix rules
+    pub fn expand_dic(&self, dic_content: &str, normalize: impl Fn(&str) -> String) -> HunspellWords {
+        let mut result = HunspellWords {
+            compounds: CompoundRules {
+                min_length: self.compound_min,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        
+        for (index, line) in dic_content.lines().enumerate() {
+            let line = line.trim();
+            
+            // The first line holds the approximate word count
+            if line.is_empty() || (index == 0 && line.parse::<usize>().is_ok()) {
+                continue;
+            }
+            
+            // Morphological fields follow a tab or space and are ignored here
+            let entry = line.split(['\t', ' ']).next().unwrap_or("");
+            let (stem, flags) = split_dic_entry(entry);
+            if stem.is_empty() {
+                continue;
+            }
+            let flags = flags.map(|f| self.resolve_flags(f)).unwrap_or_default();
+            
+            for (form, form_flags) in self.expand(&stem, &flags) {
+                let form = normalize(&form);
+                
+                if Self::has(&form_flags, self.forbidden_word) {
+                    result.forbidden.insert(form);
+                    continue;
+                }
+                if Self::has(&form_flags, self.no_suggest) {
+                    result.no_suggest.insert(form.clone());
+                }
+                
+                if Self::has(&form_flags, self.compound_flag) {
+                    result.compounds.anywhere.insert(form.clone());
+                }
+                if Self::has(&form_flags, self.compound_begin) {
+                    result.compounds.begin.insert(form.clone());
+                }
+                if Self::has(&form_flags, self.compound_middle) {
+                    result.compounds.middle.insert(form.clone());
+                }
+                if Self::has(&form_flags, self.compound_end) {
+                    result.compounds.end.insert(form.clone());
+                }
+                
+                if !Self::has(&form_flags, self.only_in_compound) {
+                    result.words.push(&form);
+                }
+            }
+        }
+        
+        result.words.finish();
+        result
+    }
+}
+
+/// Split a `.dic` entry into stem and flags, honouring `\/` escapes in the stem
+fn split_dic_entry(entry: &str) -> (String, Option<&str>) {
+    let bytes = entry.as_bytes();
+    let mut stem = String::new();
+    let mut i = 0;
+    
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && bytes.get(i + 1) == Some(&b'/') {
+            stem.push('/');
+            i += 2;
+            continue;
+        }
+        if bytes[i] == b'/' {
+            return (stem, Some(&entry[i + 1..]));
+        }
+        let ch = entry[i..].chars().next().unwrap();
+        stem.push(ch);
+        i += ch.len_utf8();
+    }
+    
+    (stem, None)
+}
+
+/// Load and expand a Hunspell dictionary pair
+pub fn load_pair(aff_path: &Path, dic_path: &Path, normalize: impl Fn(&str) -> String) -> anyhow::Result<HunspellWords> {
+    let encoding = detect_encoding(aff_path);
+    let rules = AffixRules::load(aff_path)?;
+    let dic_content = read_encoded(dic_path, encoding.as_deref())?;
+    Ok(rules.expand_dic(&dic_content, normalize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn expand(aff: &str, dic: &str) -> HunspellWords {
+        AffixRules::parse(aff).expand_dic(dic, str::to_string)
+    }
+    
+    #[test]
+    fn non_ascii_flags_are_single_flags() {
+        let words = expand("SFX é Y 1\nSFX é 0 s .\nCOMPOUNDFLAG ü\n", "2\nhaus/éü\ntür\n");
+        assert!(words.words.contains("hauss"));
+        assert!(words.compounds.anywhere.contains("haus"));
+        assert!(!words.compounds.anywhere.contains("tür"));
+    }
+    
+    #[test]
+    fn long_and_numeric_flags() {
+        let words = expand("FLAG long\nSFX Aa Y 1\nSFX Aa 0 s .\n", "1\ncat/Aa\n");
+        assert!(words.words.contains("cats"));
+        
+        let words = expand("FLAG num\nSFX 101 Y 1\nSFX 101 0 s .\nCOMPOUNDFLAG 7\n", "1\ndog/7,101\n");
+        assert!(words.words.contains("dogs"));
+        assert!(words.compounds.anywhere.contains("dog"));
+    }
+    
+    #[test]
+    fn numeric_aliases_are_not_taken_for_the_count() {
+        let aff = "FLAG num\nAF 2\nAF 5\nAF 5,101\nSFX 101 Y 1\nSFX 101 0 s .\nCOMPOUNDFLAG 5\n";
+        let words = expand(aff, "2\ndog/1\ncat/2\n");
+        assert!(words.compounds.anywhere.contains("dog"));
+        assert!(!words.words.contains("dogs"));
+        assert!(words.words.contains("cats"));
+    }
+    
+    #[test]
+    fn forms_come_out_sorted_once() {
+        // "houses" comes from both "hous" and "house"
+        let words = expand("SFX A Y 2\nSFX A 0 s .\nSFX A 0 es .\n", "3\nhous/A\nbus/A\nhouse/A\n");
+        assert_eq!(
+            words.words.iter().collect::<Vec<_>>(),
+            ["bus", "buses", "buss", "hous", "house", "housees", "houses", "houss"]
+        );
+        assert_eq!(words.words.len(), 8);
+        assert!(!words.words.contains("houes"));
+    }
+}
@@ -0,0 +1,259 @@
+use crate::prose::{string_value_spans, ProseSpan};
+use crate::selector::{PathSegment, ValueFilter};
+use std::ops::Range;
+
+// Values nested deeper than this are skipped rather than recursed into, as
+// serde_json does, so a run of brackets can't overflow the stack
+const MAX_DEPTH: usize = 128;
+
+/// The prose in a JSON document: the string values picked by `filter`.
+///
+/// Keys, numbers, booleans and null are left out, as are values that look
+/// like keys, paths or URLs, and placeholders inside values. Comments and
+/// unquoted keys (JSON5) are tolerated.
+pub fn prose_spans(text: &str, filter: &ValueFilter) -> Vec<ProseSpan> {
+    let mut scan = Scan {
+        text,
+        pos: 0,
+        path: Vec::new(),
+        filter,
+        spans: Vec::new(),
+    };
+    while scan.pos < text.len() {
+        let before = scan.pos;
+        scan.value();
+        // Junk after the document, or a stray closing bracket
+        if scan.pos == before {
+            scan.pos += 1;
+        }
+    }
+    scan.spans
+}
+
+struct Scan<'a> {
+    text: &'a str,
+    pos: usize,
+    path: Vec<PathSegment>,
+    filter: &'a ValueFilter,
+    spans: Vec<ProseSpan>,
+}
+
+impl Scan<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+    
+    fn value(&mut self) {
+        self.skip_space();
+        match self.peek() {
+            Some(b'{' | b'[') if self.path.len() >= MAX_DEPTH => self.skip_nested(),
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"' | b'\'') => {
+                let string = self.string();
+                if self.filter.checks(&self.path) {
+                    self.spans.extend(string_value_spans(self.text, string));
+                }
+            }
+            Some(_) => {
+                // Numbers, true, false and null
+                let rest = &self.text[self.pos..];
+                self.pos += rest
+                    .find(|c: char| c.is_whitespace() || matches!(c, ',' | ']' | '}' | ':'))
+                    .unwrap_or(rest.len());
+            }
+            None => {}
+        }
+    }
+    
+    fn object(&mut self) {
+        self.pos += 1;
+        loop {
+            self.skip_space();
+            match self.peek() {
+                None => return,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return;
+                }
+                Some(b',') => {
+                    self.pos += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            
+            let key = match self.peek() {
+                Some(b'"' | b'\'') => {
+                    let key = self.string();
+                    self.text[key].to_string()
+                }
+                _ => {
+                    let rest = &self.text[self.pos..];
+                    let len = rest
+                        .find(|c: char| c.is_whitespace() || matches!(c, ':' | ',' | '}'))
+                        .unwrap_or(rest.len());
+                    self.pos += len;
+                    if len == 0 {
+                        // A ':' with no key
+                        self.pos += 1;
+                        continue;
+                    }
+                    rest[..len].to_string()
+                }
+            };
+            
+            self.skip_space();
+            if self.peek() == Some(b':') {
+                self.pos += 1;
+            }
+            self.path.push(PathSegment::Key(key));
+            self.value();
+            self.path.pop();
+        }
+    }
+    
+    fn array(&mut self) {
+        self.pos += 1;
+        let mut index = 0;
+        loop {
+            self.skip_space();
+            match self.peek() {
+                None => return,
+                Some(b']') => {
+                    self.pos += 1;
+                    return;
+                }
+                Some(b',') => {
+                    self.pos += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            
+            let before = self.pos;
+            self.path.push(PathSegment::Index(index));
+            self.value();
+            self.path.pop();
+            index += 1;
+            if self.pos == before {
+                self.pos += 1;
+            }
+        }
+    }
+    
+    // Move past the object or array at `pos` without looking at its values
+    fn skip_nested(&mut self) {
+        let mut depth = 0;
+        while let Some(byte) = self.peek() {
+            match byte {
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.pos += 1;
+                        return;
+                    }
+                }
+                b'"' | b'\'' => {
+                    self.string();
+                    continue;
+                }
+                b'/' => {
+                    let before = self.pos;
+                    self.skip_space();
+                    if self.pos > before {
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+    
+    // The content of the string at `pos`, moving past it
+    fn string(&mut self) -> Range<usize> {
+        let bytes = self.text.as_bytes();
+        let quote = bytes[self.pos];
+        let start = self.pos + 1;
+        let mut i = start;
+        while i < bytes.len() && bytes[i] != quote && bytes[i] != b'\n' {
+            if bytes[i] == b'\\' {
+                i += 1;
+            }
+            i += 1;
+        }
+        let end = i.min(bytes.len());
+        self.pos = (end + 1).min(bytes.len());
+        start..end
+    }
+    
+    // Whitespace and comments
+    fn skip_space(&mut self) {
+        loop {
+            let rest = &self.text[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if let Some(comment) = trimmed.strip_prefix("/*") {
+                self.pos += comment.find("*/").map_or(trimmed.len(), |end| end + 4);
+            } else {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn prose<'a>(text: &'a str, filter: &ValueFilter) -> Vec<&'a str> {
+        prose_spans(text, filter)
+            .into_iter()
+            .map(|span| &text[span.range])
+            .collect()
+    }
+    
+    #[test]
+    fn string_values_are_prose() {
+        let text = r#"{"title": "Hello world", "count": 3, "enabled": true, "icon": "images/icon.png", "list": ["One item"]}"#;
+        assert_eq!(prose(text, &ValueFilter::default()), ["Hello world", "One item"]);
+    }
+    
+    #[test]
+    fn placeholders_are_left_out() {
+        let text = r#"{"greeting": "Hello {name}, welcome"}"#;
+        assert_eq!(prose(text, &ValueFilter::default()), ["Hello ", ", welcome"]);
+    }
+    
+    #[test]
+    fn comments_and_unquoted_keys_are_tolerated() {
+        let text = "{\n  // A comment\n  title: \"Hello world\",\n}";
+        assert_eq!(prose(text, &ValueFilter::default()), ["Hello world"]);
+    }
+    
+    #[test]
+    fn filter_picks_values() {
+        let text = r#"{"messages": {"hi": "Hello"}, "meta": {"author": "Someone"}}"#;
+        let filter = ValueFilter::new(&["$.messages".to_string()], &[]);
+        assert_eq!(prose(text, &filter), ["Hello"]);
+    }
+    
+    #[test]
+    fn deep_nesting_is_skipped() {
+        let nested = |depth: usize, value: &str| format!("{}{}{}", "[".repeat(depth), value, "]".repeat(depth));
+        
+        let text = format!(r#"{{"a": {}, "b": "After it"}}"#, nested(MAX_DEPTH - 1, r#""Deep enough""#));
+        assert_eq!(prose(&text, &ValueFilter::default()), ["Deep enough", "After it"]);
+        
+        let text = format!(r#"{{"a": {}, "b": "After it"}}"#, nested(MAX_DEPTH, r#""Too deep ]""#));
+        assert_eq!(prose(&text, &ValueFilter::default()), ["After it"]);
+        
+        // Unclosed, as well as far past the limit
+        let text = "[".repeat(200_000);
+        assert!(prose(&text, &ValueFilter::default()).is_empty());
+    }
+}
@@ -0,0 +1,208 @@
+use crate::language::Language;
+use std::collections::HashMap;
+
+// Hitting a neighbouring key is the most common substitution by far
+const ADJACENT_KEY_COST: f32 = 0.6;
+
+/// Physical key arrangement used to judge how likely a substitution is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyboardLayout {
+    Qwerty,
+    Azerty,
+    Qwertz,
+}
+
+impl KeyboardLayout {
+    pub fn for_language(language: Language) -> Self {
+        match language {
+            Language::French => KeyboardLayout::Azerty,
+            Language::German => KeyboardLayout::Qwertz,
+            _ => KeyboardLayout::Qwerty,
+        }
+    }
+    
+    // Letter rows from top to bottom. Each row sits about half a key to the
+    // right of the one above it.
+    fn rows(&self) -> &'static [&'static str] {
+        match self {
+            KeyboardLayout::Qwerty => &["qwertyuiop", "asdfghjkl", "zxcvbnm"],
+            KeyboardLayout::Azerty => &["azertyuiop", "qsdfghjklm", "wxcvbn"],
+            KeyboardLayout::Qwertz => &["qwertzuiopü", "asdfghjklöä", "yxcvbnm"],
+        }
+    }
+    
+    /// Every pair of keys that touch, in both orders
+    pub fn adjacent_pairs(&self) -> Vec<(char, char)> {
+        let rows: Vec<Vec<char>> = self.rows().iter().map(|row| row.chars().collect()).collect();
+        let mut pairs = Vec::new();
+        
+        for (r, row) in rows.iter().enumerate() {
+            for (i, &key) in row.iter().enumerate() {
+                let mut neighbours = Vec::new();
+                if let Some(&next) = row.get(i + 1) {
+                    neighbours.push(next);
+                }
+                // The row below is shifted right, so key i sits above keys i - 1 and i
+                if let Some(below) = rows.get(r + 1) {
+                    neighbours.extend(i.checked_sub(1).and_then(|j| below.get(j)));
+                    neighbours.extend(below.get(i));
+                }
+                
+                for neighbour in neighbours {
+                    pairs.push((key, neighbour));
+                    pairs.push((neighbour, key));
+                }
+            }
+        }
+        
+        pairs
+    }
+}
+
+/// Costs for a Damerau-Levenshtein distance that treats a slip onto a
+/// neighbouring key or a swapped pair of letters as smaller mistakes than an
+/// arbitrary edit
+#[derive(Debug, Clone)]
+pub struct EditCosts {
+    pub insertion: f32,
+    pub deletion: f32,
+    pub transposition: f32,
+    pub substitution: f32,
+    // Per-key overrides of `substitution`, stored in both orders
+    substitutions: HashMap<(char, char), f32>,
+}
+
+impl Default for EditCosts {
+    fn default() -> Self {
+        Self {
+            insertion: 1.0,
+            deletion: 1.0,
+            transposition: 0.5,
+            substitution: 1.0,
+            substitutions: HashMap::new(),
+        }
+    }
+}
+
+impl EditCosts {
+    /// Costs with neighbouring keys on `layout` cheaper to confuse
+    pub fn for_layout(layout: KeyboardLayout) -> Self {
+        let mut costs = Self::default();
+        for (a, b) in layout.adjacent_pairs() {
+            costs.substitutions.insert((a, b), ADJACENT_KEY_COST);
+        }
+        costs
+    }
+    
+    pub fn for_language(language: Language) -> Self {
+        Self::for_layout(KeyboardLayout::for_language(language))
+    }
+    
+    /// Override the cost of substituting `a` for `b` (and `b` for `a`)
+    pub fn set_substitution(&mut self, a: char, b: char, cost: f32) {
+        self.substitutions.insert((a, b), cost);
+        self.substitutions.insert((b, a), cost);
+    }
+    
+    pub fn substitution_cost(&self, a: char, b: char) -> f32 {
+        if a == b {
+            return 0.0;
+        }
+        self.substitutions.get(&(a, b)).copied().unwrap_or(self.substitution)
+    }
+    
+    /// Weighted distance between two words, allowing adjacent transpositions
+    /// ("teh" -> "the") as a single edit. This is the optimal string alignment
+    /// variant: a transposed pair isn't edited again.
+    pub fn distance(&self, a: &str, b: &str) -> f32 {
+        let a: Vec<char> = a.chars().collect();
+        let b: Vec<char> = b.chars().collect();
+        
+        // Three rows are enough, since a transposition looks back two characters
+        let mut before_prev: Vec<f32> = vec![0.0; b.len() + 1];
+        let mut prev: Vec<f32> = (0..=b.len()).map(|j| j as f32 * self.insertion).collect();
+        let mut curr: Vec<f32> = vec![0.0; b.len() + 1];
+        
+        for i in 1..=a.len() {
+            curr[0] = i as f32 * self.deletion;
+            
+            for j in 1..=b.len() {
+                let mut best = (prev[j] + self.deletion)
+                    .min(curr[j - 1] + self.insertion)
+                    .min(prev[j - 1] + self.substitution_cost(a[i - 1], b[j - 1]));
+                
+                if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] && a[i - 1] != a[i - 2] {
+                    best = best.min(before_prev[j - 2] + self.transposition);
+                }
+                
+                curr[j] = best;
+            }
+            
+            std::mem::swap(&mut before_prev, &mut prev);
+            std::mem::swap(&mut prev, &mut curr);
+        }
+        
+        prev[b.len()]
+    }
+}
+
+/// Number of edits between two words, counting an adjacent transposition
+/// ("teh" -> "the") as one, like `EditCosts::distance` with every cost 1
+pub fn osa_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    
+    let mut before_prev: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr: Vec<usize> = vec![0; b.len() + 1];
+    
+    for i in 1..=a.len() {
+        curr[0] = i;
+        
+        for j in 1..=b.len() {
+            let mut best = (prev[j] + 1)
+                .min(curr[j - 1] + 1)
+                .min(prev[j - 1] + usize::from(a[i - 1] != b[j - 1]));
+            
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(before_prev[j - 2] + 1);
+            }
+            
+            curr[j] = best;
+        }
+        
+        std::mem::swap(&mut before_prev, &mut prev);
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn neighbouring_keys_are_cheaper_to_confuse() {
+        let costs = EditCosts::for_layout(KeyboardLayout::Qwerty);
+        // 'r' sits next to 'e'; 'p' is across the keyboard
+        assert!(costs.distance("hrllo", "hello") < costs.distance("hpllo", "hello"));
+        assert_eq!(costs.distance("hpllo", "hello"), costs.substitution);
+        
+        // On AZERTY 'w' and 'x' are neighbours, on QWERTY they aren't
+        let azerty = EditCosts::for_layout(KeyboardLayout::Azerty);
+        assert!(azerty.substitution_cost('w', 'x') < costs.substitution_cost('w', 'x'));
+    }
+    
+    #[test]
+    fn a_transposition_is_one_edit() {
+        let costs = EditCosts::default();
+        assert_eq!(costs.distance("teh", "the"), costs.transposition);
+        assert_eq!(osa_distance("teh", "the"), 1);
+        assert_eq!(osa_distance("recieve", "receive"), 1);
+        // A transposition and another typo are still within two edits
+        assert_eq!(osa_distance("teh", "then"), 2);
+        assert_eq!(osa_distance("kitten", "sitting"), 3);
+        assert_eq!(osa_distance("", "abc"), 3);
+    }
+}
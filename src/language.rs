@@ -95,22 +95,50 @@ impl Language {
         }
     }
     
+    pub fn ngram_filename(&self) -> Option<String> {
+        match self {
+            Language::AutoDetect => None,
+            _ => Some(format!("ngrams({}).txt", self.code())),
+        }
+    }
+    
     pub fn from_code(code: &str) -> Self {
+        Self::try_from_code(code).unwrap_or(Language::English)
+    }
+    
+    pub fn try_from_code(code: &str) -> Option<Self> {
         match code.to_lowercase().as_str() {
-            "eng" | "en" | "english" => Language::English,
-            "afr" | "af" | "afrikaans" => Language::Afrikaans,
-            "fra" | "fr" | "french" => Language::French,
-            "spa" | "es" | "spanish" => Language::Spanish,
-            "deu" | "de" | "german" => Language::German,
-            "zho" | "zh" | "chinese" => Language::Chinese,
-            "ita" | "it" | "italian" => Language::Italian,
-            "por" | "pt" | "portuguese" => Language::Portuguese,
-            "rus" | "ru" | "russian" => Language::Russian,
-            "jpn" | "ja" | "japanese" => Language::Japanese,
-            "kor" | "ko" | "korean" => Language::Korean,
-            "auto" | "autodetect" => Language::AutoDetect,
-            _ => Language::English,
+            "eng" | "en" | "english" => Some(Language::English),
+            "afr" | "af" | "afrikaans" => Some(Language::Afrikaans),
+            "fra" | "fr" | "french" => Some(Language::French),
+            "spa" | "es" | "spanish" => Some(Language::Spanish),
+            "deu" | "de" | "german" => Some(Language::German),
+            "zho" | "zh" | "chinese" => Some(Language::Chinese),
+            "ita" | "it" | "italian" => Some(Language::Italian),
+            "por" | "pt" | "portuguese" => Some(Language::Portuguese),
+            "rus" | "ru" | "russian" => Some(Language::Russian),
+            "jpn" | "ja" | "japanese" => Some(Language::Japanese),
+            "kor" | "ko" | "korean" => Some(Language::Korean),
+            "auto" | "autodetect" => Some(Language::AutoDetect),
+            _ => None,
+        }
+    }
+    
+    /// The language of a BCP 47 tag or locale name such as `fr`, `fr-CA`
+    /// or `pt_BR`; only the primary subtag counts
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let code = tag.trim().split(['_', '-', '.', '@']).next().unwrap_or(tag);
+        Self::try_from_code(code).filter(|lang| *lang != Language::AutoDetect)
+    }
+    
+    /// Guess the language of a Hunspell file from its stem,
+    /// e.g. `en_US`, `de-DE_frami`, `fr` or `dictionary(afr)`
+    pub fn from_dictionary_stem(stem: &str) -> Option<Self> {
+        if let Some(code) = stem.strip_prefix("dictionary(").and_then(|s| s.strip_suffix(')')) {
+            return Self::try_from_code(code);
         }
+        
+        Self::from_tag(stem)
     }
     
     pub fn detect_from_text(text: &str) -> Vec<(Language, f32)> {
@@ -226,6 +254,8 @@ pub struct LanguageManager {
     current_language: Language,
     #[serde(skip)]
     dictionary_paths: HashMap<Language, PathBuf>,
+    #[serde(skip)]
+    hunspell_paths: HashMap<Language, (PathBuf, PathBuf)>,
 }
 
 impl Default for LanguageManager {
@@ -240,6 +270,7 @@ impl LanguageManager {
             available_languages: Language::all(),
             current_language: Language::English,
             dictionary_paths: HashMap::new(),
+            hunspell_paths: HashMap::new(),
         };
         
         manager.scan_dictionaries();
@@ -269,6 +300,21 @@ impl LanguageManager {
                             }
                         }
                     }
+                    
+                    // Hunspell dictionaries come as a .dic word list plus a .aff rule file
+                    if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("dic") {
+                        let aff_path = path.with_extension("aff");
+                        if !aff_path.is_file() {
+                            continue;
+                        }
+                        
+                        if let Some(language) = path.file_stem()
+                            .and_then(|n| n.to_str())
+                            .and_then(Language::from_dictionary_stem)
+                        {
+                            self.hunspell_paths.entry(language).or_insert((aff_path, path.clone()));
+                        }
+                    }
                 }
             }
         }
@@ -311,26 +357,36 @@ impl LanguageManager {
                     }
                 }
                 
-                if let Some(filename) = lang.dictionary_filename() {
-                    let locations = vec![
-                        Self::dictionary_dir().join(&filename),
-                        PathBuf::from("src/dictionary").join(&filename),
-                        PathBuf::from("dictionary").join(&filename),
-                        Self::user_dict_dir().join(&filename),
-                    ];
-                    
-                    for path in locations {
-                        if path.exists() {
-                            return Some(path);
-                        }
-                    }
-                }
-                
-                None
+                lang.dictionary_filename().and_then(|filename| Self::find_data_file(&filename))
             }
         }
     }
     
+    /// The bigram count file for a language, searched for in the same places
+    /// as word lists
+    pub fn get_ngram_path(language: &Language) -> Option<PathBuf> {
+        language.ngram_filename().and_then(|filename| Self::find_data_file(&filename))
+    }
+    
+    fn find_data_file(filename: &str) -> Option<PathBuf> {
+        let locations = vec![
+            Self::dictionary_dir().join(filename),
+            PathBuf::from("src/dictionary").join(filename),
+            PathBuf::from("dictionary").join(filename),
+            Self::user_dict_dir().join(filename),
+        ];
+        
+        locations.into_iter().find(|path| path.exists())
+    }
+    
+    /// Returns the (.aff, .dic) pair for a language if one was found
+    pub fn get_hunspell_paths(&self, language: &Language) -> Option<(PathBuf, PathBuf)> {
+        self.hunspell_paths
+            .get(language)
+            .filter(|(aff, dic)| aff.exists() && dic.exists())
+            .cloned()
+    }
+    
     pub fn set_language(&mut self, language: Language) {
         self.current_language = language;
     }
@@ -0,0 +1,571 @@
+use crate::language::Language;
+use crate::prose::{ProseKind, ProseSpan};
+use std::ops::Range;
+
+// Commands whose braced argument is prose
+const TEXT_COMMANDS: &[&str] = &[
+    "part", "chapter", "section", "subsection", "subsubsection", "paragraph",
+    "subparagraph", "title", "subtitle", "caption", "footnote", "footnotetext",
+    "marginpar", "thanks", "emph", "textit", "textbf", "textsl", "textsc", "textup",
+    "textmd", "textrm", "textsf", "textnormal", "underline", "uline", "mbox", "fbox",
+    "text", "intertext", "enquote", "frametitle", "framesubtitle",
+];
+// Text arguments nested deeper than this ("\emph{\textbf{...}}") are
+// skipped rather than scanned, so a run of them can't overflow the stack
+const MAX_ARGUMENT_DEPTH: usize = 64;
+// Commands whose argument is a URL or path, read up to the first '}'
+const URL_COMMANDS: &[&str] = &["url", "nolinkurl", "path", "doi"];
+// Environments whose content is never prose
+const VERBATIM_ENVIRONMENTS: &[&str] = &[
+    "verbatim", "verbatim*", "Verbatim", "lstlisting", "minted", "comment",
+    "tikzpicture", "filecontents", "filecontents*",
+];
+const MATH_ENVIRONMENTS: &[&str] = &[
+    "equation", "equation*", "align", "align*", "alignat", "alignat*", "gather",
+    "gather*", "multline", "multline*", "flalign", "flalign*", "eqnarray",
+    "eqnarray*", "math", "displaymath",
+];
+// Letters written as commands: "Stra\ss e", "\o"
+const LETTER_COMMANDS: &[(&str, &str)] = &[
+    ("ss", "ß"), ("ae", "æ"), ("AE", "Æ"), ("oe", "œ"), ("OE", "Œ"), ("o", "ø"),
+    ("O", "Ø"), ("aa", "å"), ("AA", "Å"), ("l", "ł"), ("L", "Ł"),
+];
+// Accents and the letters they go on, in the same order as the results:
+// "\'e", "\`a", "\^o", "\"u", "\~n", "\c{c}"
+const ACCENTS: &[(char, &str, &str)] = &[
+    ('\'', "aeiouycnAEIOUYCN", "áéíóúýćńÁÉÍÓÚÝĆŃ"),
+    ('`', "aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+    ('^', "aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+    ('"', "aeiouyAEIOUY", "äëïöüÿÄËÏÖÜŸ"),
+    ('~', "anoANO", "ãñõÃÑÕ"),
+    ('c', "cC", "çÇ"),
+];
+
+/// The prose in a LaTeX document: running text of the body, the arguments
+/// of text commands (`\section`, `\emph`, `\caption`, `\footnote`, ...) and
+/// the text of `\href`.
+///
+/// Left out are comments, control sequences with their other arguments,
+/// inline and display math, math environments, `verbatim`, `lstlisting`
+/// and similar environments, and `\verb`. Accented letters written as
+/// commands ("\'e", "\ss") are decoded. The language set by
+/// `\usepackage[...]{babel}`, `\selectlanguage`, `otherlanguage` or
+/// `\foreignlanguage` is carried by the spans.
+pub fn prose_spans(text: &str) -> Vec<ProseSpan> {
+    let mut scan = Scan {
+        text,
+        pos: 0,
+        piece_start: 0,
+        // Without \begin{document} the whole file is body
+        in_body: !text.contains("\\begin{document}"),
+        arguments: 0,
+        language: None,
+        other_languages: Vec::new(),
+        spans: Vec::new(),
+    };
+    scan.content(text.len());
+    scan.spans
+}
+
+struct Scan<'a> {
+    text: &'a str,
+    pos: usize,
+    // Start of the plain text not pushed yet
+    piece_start: usize,
+    // Plain text in the preamble isn't prose, but \title's argument is
+    in_body: bool,
+    // How many text arguments the scan is inside
+    arguments: usize,
+    language: Option<Language>,
+    // The language to go back to at each \end{otherlanguage}
+    other_languages: Vec<Option<Language>>,
+    spans: Vec<ProseSpan>,
+}
+
+impl Scan<'_> {
+    // Scan up to `end`, pushing plain text as prose
+    fn content(&mut self, end: usize) {
+        let text = self.text;
+        self.piece_start = self.pos;
+        
+        while self.pos < end {
+            let at = self.pos;
+            match text.as_bytes()[at] {
+                b'%' => {
+                    self.flush(at);
+                    self.pos = line_end(text, at).min(end);
+                }
+                b'$' => {
+                    self.flush(at);
+                    self.pos = math_end(text, at, end);
+                }
+                b'\\' => {
+                    self.flush(at);
+                    self.command(end);
+                }
+                b'{' => {
+                    self.flush(at);
+                    self.braced_letter(end);
+                }
+                b'}' => {
+                    self.flush(at);
+                    self.pos += 1;
+                }
+                _ => {
+                    self.pos += 1;
+                    continue;
+                }
+            }
+            self.piece_start = self.pos;
+        }
+        
+        self.flush(end);
+    }
+    
+    // Push the plain text from `piece_start` to `end`
+    fn flush(&mut self, end: usize) {
+        let range = self.piece_start..end;
+        if self.has_prose() && !range.is_empty() && !self.text[range.clone()].trim().is_empty() {
+            self.spans.push(ProseSpan { language: self.language, ..ProseSpan::new(range, ProseKind::Text) });
+        }
+    }
+    
+    fn push_decoded(&mut self, range: Range<usize>, decoded: &str) {
+        if self.has_prose() {
+            self.spans.push(ProseSpan {
+                decoded: Some(decoded.to_string()),
+                language: self.language,
+                ..ProseSpan::new(range, ProseKind::Text)
+            });
+        }
+    }
+    
+    fn has_prose(&self) -> bool {
+        self.in_body || self.arguments > 0
+    }
+    
+    // Read the control sequence at `pos`
+    fn command(&mut self, end: usize) {
+        let text = self.text;
+        let at = self.pos;
+        
+        if let Some((letter_end, letter)) = encoded_letter(text, at, end) {
+            self.push_decoded(at..letter_end, &letter);
+            self.pos = letter_end;
+            return;
+        }
+        
+        let rest = &text[at + 1..end];
+        let name_len = rest.bytes().take_while(u8::is_ascii_alphabetic).count();
+        if name_len == 0 {
+            self.control_symbol(end);
+            return;
+        }
+        
+        let name = &rest[..name_len];
+        self.pos = at + 1 + name_len;
+        if text[self.pos..end].starts_with('*') {
+            self.pos += 1;
+        }
+        
+        match name {
+            "begin" => self.begin_environment(end),
+            "end" => self.end_environment(end),
+            "verb" | "lstinline" | "mintinline" => self.inline_verbatim(name, end),
+            "usepackage" | "RequirePackage" => self.use_package(end),
+            "selectlanguage" | "setdefaultlanguage" | "setmainlanguage" => {
+                if let Some(language) = self.argument(end).and_then(|arg| babel_language(&text[arg])) {
+                    self.language = Some(language);
+                }
+            }
+            "foreignlanguage" => {
+                self.skip_optional(end);
+                let language = self.argument(end).and_then(|arg| babel_language(&text[arg]));
+                let previous = self.language;
+                self.language = language.or(previous);
+                self.text_argument(end);
+                self.language = previous;
+            }
+            "href" => {
+                self.url_argument(end);
+                self.text_argument(end);
+            }
+            _ if URL_COMMANDS.contains(&name) => self.url_argument(end),
+            _ if TEXT_COMMANDS.contains(&name) => {
+                self.skip_optional(end);
+                self.text_argument(end);
+            }
+            _ => self.skip_arguments(end),
+        }
+    }
+    
+    // "\\", "\%", "\(...\)", "\[...\]", "\-"
+    fn control_symbol(&mut self, end: usize) {
+        let at = self.pos;
+        let Some(symbol) = self.text[at + 1..end].chars().next() else {
+            self.pos = end;
+            return;
+        };
+        
+        self.pos = match symbol {
+            '(' => find_end(self.text, at + 2, "\\)").min(end),
+            '[' => find_end(self.text, at + 2, "\\]").min(end),
+            // A hyphenation point or italic correction inside a word
+            '-' | '/' => {
+                self.push_decoded(at..at + 2, "");
+                at + 2
+            }
+            _ => at + 1 + symbol.len_utf8(),
+        };
+    }
+    
+    // "{\'e}" is one letter; any other '{' just opens a group
+    fn braced_letter(&mut self, end: usize) {
+        let at = self.pos;
+        if let Some((letter_end, letter)) = encoded_letter(self.text, at + 1, end) {
+            if self.text[letter_end..end].starts_with('}') {
+                self.push_decoded(at..letter_end + 1, &letter);
+                self.pos = letter_end + 1;
+                return;
+            }
+        }
+        self.pos = at + 1;
+    }
+    
+    fn begin_environment(&mut self, end: usize) {
+        let text = self.text;
+        let Some(name) = self.argument(end) else {
+            return;
+        };
+        let name = &text[name];
+        
+        if VERBATIM_ENVIRONMENTS.contains(&name) || MATH_ENVIRONMENTS.contains(&name) {
+            self.pos = find_end(text, self.pos, &format!("\\end{{{}}}", name)).min(end);
+            return;
+        }
+        
+        match name {
+            "document" => self.in_body = true,
+            "otherlanguage" | "otherlanguage*" => {
+                self.other_languages.push(self.language);
+                if let Some(language) = self.argument(end).and_then(|arg| babel_language(&text[arg])) {
+                    self.language = Some(language);
+                }
+            }
+            // Column specs, placement and widths: "{tabular}{ll}", "{figure}[h]"
+            _ => self.skip_arguments(end),
+        }
+    }
+    
+    fn end_environment(&mut self, end: usize) {
+        let Some(name) = self.argument(end) else {
+            return;
+        };
+        
+        match &self.text[name] {
+            "document" => self.in_body = false,
+            "otherlanguage" | "otherlanguage*" => {
+                if let Some(language) = self.other_languages.pop() {
+                    self.language = language;
+                }
+            }
+            _ => {}
+        }
+    }
+    
+    // "\verb|code|", "\lstinline[style=x]{code}", "\mintinline{rust}|code|"
+    fn inline_verbatim(&mut self, name: &str, end: usize) {
+        match name {
+            "mintinline" => {
+                self.argument(end);
+            }
+            "lstinline" => self.skip_optional(end),
+            _ => {}
+        }
+        
+        let from = self.pos;
+        let Some(delimiter) = self.text[from..end].chars().next() else {
+            return;
+        };
+        let close = if delimiter == '{' { '}' } else { delimiter };
+        let code_start = from + delimiter.len_utf8();
+        self.pos = self.text[code_start..end]
+            .find(close)
+            .map_or(end, |i| code_start + i + close.len_utf8());
+    }
+    
+    // "\usepackage[english,french]{babel}": the last language is the main
+    // one, unless another is given as "main=english"
+    fn use_package(&mut self, end: usize) {
+        let text = self.text;
+        let options = self.optional(end);
+        let Some(packages) = self.argument(end) else {
+            return;
+        };
+        
+        if text[packages].split(',').any(|package| package.trim() == "babel") {
+            let language = options.and_then(|options| {
+                let options: Vec<&str> = text[options].split(',').map(str::trim).collect();
+                options
+                    .iter()
+                    .find_map(|option| {
+                        let value = option.strip_prefix("main")?.trim_start().strip_prefix('=')?;
+                        babel_language(value.trim())
+                    })
+                    .or_else(|| options.iter().rev().find_map(|option| babel_language(option)))
+            });
+            if language.is_some() {
+                self.language = language;
+            }
+        }
+    }
+    
+    // The content of the braced argument after `pos` and any spaces,
+    // moving past it
+    fn argument(&mut self, end: usize) -> Option<Range<usize>> {
+        let start = skip_spaces(self.text, self.pos, end);
+        if !self.text[start..end].starts_with('{') {
+            return None;
+        }
+        let close = matching(self.text, start, end);
+        self.pos = (close + 1).min(end);
+        Some(start + 1..close)
+    }
+    
+    fn optional(&mut self, end: usize) -> Option<Range<usize>> {
+        let start = skip_spaces(self.text, self.pos, end);
+        if !self.text[start..end].starts_with('[') {
+            return None;
+        }
+        let close = matching(self.text, start, end);
+        self.pos = (close + 1).min(end);
+        Some(start + 1..close)
+    }
+    
+    fn skip_optional(&mut self, end: usize) {
+        while self.optional(end).is_some() {}
+    }
+    
+    // Scan the braced argument after `pos` as prose
+    fn text_argument(&mut self, end: usize) {
+        let start = skip_spaces(self.text, self.pos, end);
+        if !self.text[start..end].starts_with('{') {
+            return;
+        }
+        
+        let close = matching(self.text, start, end);
+        if self.arguments >= MAX_ARGUMENT_DEPTH {
+            self.pos = (close + 1).min(end);
+            return;
+        }
+        
+        self.pos = start + 1;
+        self.arguments += 1;
+        self.content(close);
+        self.arguments -= 1;
+        self.pos = (close + 1).min(end);
+    }
+    
+    // URLs may hold '%' and '#', so they end at the first '}'
+    fn url_argument(&mut self, end: usize) {
+        let start = skip_spaces(self.text, self.pos, end);
+        if self.text[start..end].starts_with('{') {
+            self.pos = self.text[start..end].find('}').map_or(end, |i| start + i + 1);
+        }
+    }
+    
+    // Skip the arguments written right after a command: "\cite{key}",
+    // "\includegraphics[width=5cm]{path}"
+    fn skip_arguments(&mut self, end: usize) {
+        while self.text[self.pos..end].starts_with(['{', '[']) {
+            self.pos = (matching(self.text, self.pos, end) + 1).min(end);
+        }
+    }
+}
+
+// A letter written as a command at `at` ("\'e", "\"{o}", "\c{c}", "\ss"),
+// and where it ends
+fn encoded_letter(text: &str, at: usize, end: usize) -> Option<(usize, String)> {
+    let rest = text[at..end].strip_prefix('\\')?;
+    let name_len = rest.bytes().take_while(u8::is_ascii_alphabetic).count();
+    
+    if name_len == 0 {
+        let accent = rest.chars().next().filter(char::is_ascii_punctuation)?;
+        return accented_letter(text, at + 2, end, accent);
+    }
+    
+    let name = &rest[..name_len];
+    let after = at + 1 + name_len;
+    if name == "c" {
+        return accented_letter(text, after, end, 'c');
+    }
+    
+    let (_, letter) = LETTER_COMMANDS.iter().find(|(command, _)| *command == name)?;
+    // TeX drops the spaces after a command name; "\ss{}" keeps the one after it
+    let rest = &text[after..end];
+    let letter_end = if rest.starts_with("{}") {
+        after + 2
+    } else {
+        end - rest.trim_start_matches(' ').len()
+    };
+    Some((letter_end, letter.to_string()))
+}
+
+// The accented letter whose base starts at `from`: "e" or "{e}"
+fn accented_letter(text: &str, from: usize, end: usize, accent: char) -> Option<(usize, String)> {
+    let (_, bases, letters) = ACCENTS.iter().find(|(a, _, _)| *a == accent)?;
+    let rest = &text[from..end];
+    
+    let (base, len) = match rest.strip_prefix('{') {
+        Some(inner) => {
+            // "\'{\i}" puts the accent on a dotless i
+            let (base, base_len) = match inner.strip_prefix("\\i") {
+                Some(_) => ('i', 2),
+                None => {
+                    let base = inner.chars().next()?;
+                    (base, base.len_utf8())
+                }
+            };
+            if !inner[base_len..].starts_with('}') {
+                return None;
+            }
+            (base, base_len + 2)
+        }
+        None => {
+            let base = rest.chars().next()?;
+            (base, base.len_utf8())
+        }
+    };
+    
+    let i = bases.chars().position(|c| c == base)?;
+    let letter = letters.chars().nth(i)?;
+    Some((from + len, letter.to_string()))
+}
+
+// Babel and polyglossia language names
+fn babel_language(name: &str) -> Option<Language> {
+    match name.trim() {
+        "american" | "british" | "USenglish" | "UKenglish" | "canadian" | "australian" | "newzealand" => {
+            Some(Language::English)
+        }
+        "francais" | "acadian" | "canadien" => Some(Language::French),
+        "ngerman" | "austrian" | "naustrian" | "swissgerman" | "nswissgerman" => Some(Language::German),
+        "portuges" | "brazil" | "brazilian" => Some(Language::Portuguese),
+        name => Language::from_tag(name),
+    }
+}
+
+// The end of "$...$" or "$$...$$" starting at `at`
+fn math_end(text: &str, at: usize, end: usize) -> usize {
+    let bytes = text.as_bytes();
+    let display = text[at..end].starts_with("$$");
+    let mut i = at + if display { 2 } else { 1 };
+    
+    while i < end {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'$' if !display => return i + 1,
+            b'$' if text[i..end].starts_with("$$") => return i + 2,
+            _ => {}
+        }
+        i += 1;
+    }
+    end
+}
+
+// The position of the '}' or ']' closing the group opened at `open`, or
+// `end` if it isn't closed. Escaped characters and comments don't count,
+// and a ']' only counts outside braces.
+fn matching(text: &str, open: usize, end: usize) -> usize {
+    let bytes = text.as_bytes();
+    let square = bytes[open] == b'[';
+    let mut brackets = 0;
+    let mut braces = 0;
+    let mut i = open;
+    
+    while i < end {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'%' => {
+                i = line_end(text, i);
+                continue;
+            }
+            b'{' => braces += 1,
+            b'}' => {
+                braces -= 1;
+                if !square && braces == 0 {
+                    return i;
+                }
+            }
+            b'[' if square && braces == 0 => brackets += 1,
+            b']' if square && braces == 0 => {
+                brackets -= 1;
+                if brackets == 0 {
+                    return i;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    end
+}
+
+fn skip_spaces(text: &str, from: usize, end: usize) -> usize {
+    let rest = &text[from..end];
+    end - rest.trim_start_matches([' ', '\t', '\n', '\r']).len()
+}
+
+fn line_end(text: &str, at: usize) -> usize {
+    text[at..].find('\n').map_or(text.len(), |i| at + i)
+}
+
+fn find_end(text: &str, from: usize, delimiter: &str) -> usize {
+    text[from..]
+        .find(delimiter)
+        .map_or(text.len(), |i| from + i + delimiter.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn language(text: &str) -> Option<Language> {
+        prose_spans(text).last().and_then(|span| span.language)
+    }
+    
+    #[test]
+    fn babel_main_option_wins() {
+        assert_eq!(language("\\usepackage[main=english,french]{babel}\nHello"), Some(Language::English));
+        assert_eq!(language("\\usepackage[english,french]{babel}\nBonjour"), Some(Language::French));
+    }
+    
+    #[test]
+    fn commands_math_and_comments_are_left_out() {
+        let text = "\\section{Intro}\nThe area is $\\pi r^2$ here. % note\n\\begin{verbatim}\nraw code\n\\end{verbatim}\n";
+        let prose: String = prose_spans(text).into_iter().map(|span| &text[span.range]).collect();
+        assert!(prose.contains("Intro"));
+        assert!(prose.contains("The area is"));
+        assert!(!prose.contains("section"));
+        assert!(!prose.contains("pi"));
+        assert!(!prose.contains("note"));
+        assert!(!prose.contains("raw code"));
+    }
+    
+    #[test]
+    fn deeply_nested_arguments_are_skipped() {
+        let nested = |depth: usize, text: &str| format!("{}{}{}", "\\textbf{".repeat(depth), text, "}".repeat(depth));
+        let prose = |text: &str| -> Vec<String> {
+            prose_spans(text).into_iter().map(|span| text[span.range].to_string()).collect()
+        };
+        
+        let text = format!("\\title{{{}}} After", nested(MAX_ARGUMENT_DEPTH - 1, "Deep enough"));
+        assert_eq!(prose(&text), ["Deep enough", " After"]);
+        
+        let text = format!("\\title{{{}}} After", nested(MAX_ARGUMENT_DEPTH, "Too deep"));
+        assert_eq!(prose(&text), [" After"]);
+        
+        // Unclosed, as well as far past the limit
+        assert!(prose(&"\\textbf{".repeat(50_000)).is_empty());
+    }
+}
@@ -0,0 +1,530 @@
+use crate::prose::{ProseKind, ProseSpan};
+use std::ops::Range;
+
+// How a string literal is written
+struct Quote {
+    open: &'static str,
+    close: &'static str,
+    escapes: bool,
+    multiline: bool,
+    /// Embedded code ("${" ... "}") that isn't part of the text
+    interpolation: &'static [(&'static str, u8)],
+}
+
+impl Quote {
+    const fn new(open: &'static str, close: &'static str) -> Self {
+        Self { open, close, escapes: true, multiline: false, interpolation: &[] }
+    }
+    
+    const fn raw(self) -> Self {
+        Self { escapes: false, ..self }
+    }
+    
+    const fn multiline(self) -> Self {
+        Self { multiline: true, ..self }
+    }
+    
+    const fn interpolating(self, interpolation: &'static [(&'static str, u8)]) -> Self {
+        Self { interpolation, ..self }
+    }
+}
+
+// Longer delimiters come first so `"""` isn't taken for an empty string
+const C_QUOTES: &[Quote] = &[Quote::new("\"", "\"")];
+const RUST_QUOTES: &[Quote] = &[Quote::new("\"", "\"").multiline()];
+const JAVA_QUOTES: &[Quote] = &[Quote::new("\"\"\"", "\"\"\"").multiline(), Quote::new("\"", "\"")];
+const GO_QUOTES: &[Quote] = &[Quote::new("\"", "\""), Quote::new("`", "`").raw().multiline()];
+const PYTHON_QUOTES: &[Quote] = &[
+    Quote::new("\"\"\"", "\"\"\"").multiline(),
+    Quote::new("'''", "'''").multiline(),
+    Quote::new("\"", "\""),
+    Quote::new("'", "'"),
+];
+const JAVASCRIPT_QUOTES: &[Quote] = &[
+    Quote::new("\"", "\""),
+    Quote::new("'", "'"),
+    Quote::new("`", "`").multiline().interpolating(&[("${", b'}')]),
+];
+const RUBY_QUOTES: &[Quote] = &[
+    Quote::new("\"", "\"").multiline().interpolating(&[("#{", b'}')]),
+    Quote::new("'", "'").multiline(),
+];
+const PHP_QUOTES: &[Quote] = &[
+    Quote::new("\"", "\"").multiline().interpolating(&[("{$", b'}')]),
+    Quote::new("'", "'").multiline(),
+];
+const SHELL_QUOTES: &[Quote] = &[
+    Quote::new("\"", "\"").multiline().interpolating(&[("${", b'}'), ("$(", b')')]),
+    Quote::new("'", "'").raw().multiline(),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Syntax {
+    Rust,
+    Python,
+    JavaScript,
+    Java,
+    C,
+    Go,
+    Ruby,
+    Php,
+    Shell,
+}
+
+/// Finds the comments, doc comments and string literals in source code,
+/// which are the only parts of it written in a natural language.
+///
+/// This is a lexer, not a parser: it knows how each language writes
+/// comments and strings, and skips strings that are import paths
+/// (`import "fmt"`, `require 'json'`, `#include "util.h"`).
+#[derive(Debug, Clone, Copy)]
+pub struct Lexer {
+    syntax: Syntax,
+}
+
+impl Lexer {
+    /// The lexer for a language name from `prose::programming_language`
+    pub fn for_language(language: &str) -> Option<Self> {
+        let syntax = match language {
+            "rust" => Syntax::Rust,
+            "python" => Syntax::Python,
+            "javascript" => Syntax::JavaScript,
+            "java" => Syntax::Java,
+            "cpp" => Syntax::C,
+            "go" => Syntax::Go,
+            "ruby" => Syntax::Ruby,
+            "php" => Syntax::Php,
+            "shell" => Syntax::Shell,
+            _ => return None,
+        };
+        
+        Some(Self { syntax })
+    }
+    
+    /// Comment and string contents in `text`, in order, without their
+    /// delimiters
+    pub fn prose_spans(&self, text: &str) -> Vec<ProseSpan> {
+        let mut scan = Scan {
+            lexer: self,
+            text,
+            bytes: text.as_bytes(),
+            spans: Vec::new(),
+            line_start: 0,
+            import_block: false,
+            settled: 0,
+        };
+        scan.run();
+        scan.spans
+    }
+    
+    /// Where the last line of `text` ends that no comment, string or
+    /// import block runs on from, so what follows can be scanned by itself
+    pub fn settled(&self, text: &str) -> usize {
+        let mut scan = Scan {
+            lexer: self,
+            text,
+            bytes: text.as_bytes(),
+            spans: Vec::new(),
+            line_start: 0,
+            import_block: false,
+            settled: 0,
+        };
+        scan.run();
+        scan.settled
+    }
+    
+    fn line_comments(&self) -> &'static [&'static str] {
+        match self.syntax {
+            Syntax::Python | Syntax::Ruby | Syntax::Shell => &["#"],
+            Syntax::Php => &["//", "#"],
+            _ => &["//"],
+        }
+    }
+    
+    fn doc_line_comments(&self) -> &'static [&'static str] {
+        match self.syntax {
+            Syntax::Rust => &["///", "//!"],
+            _ => &[],
+        }
+    }
+    
+    fn block_comment(&self) -> Option<(&'static str, &'static str)> {
+        match self.syntax {
+            Syntax::Python | Syntax::Shell => None,
+            Syntax::Ruby => Some(("=begin", "=end")),
+            _ => Some(("/*", "*/")),
+        }
+    }
+    
+    fn doc_block_comments(&self) -> &'static [&'static str] {
+        match self.syntax {
+            Syntax::Rust => &["/**", "/*!"],
+            Syntax::JavaScript | Syntax::Java | Syntax::C | Syntax::Php => &["/**"],
+            _ => &[],
+        }
+    }
+    
+    fn quotes(&self) -> &'static [Quote] {
+        match self.syntax {
+            Syntax::Rust => RUST_QUOTES,
+            Syntax::Python => PYTHON_QUOTES,
+            Syntax::JavaScript => JAVASCRIPT_QUOTES,
+            Syntax::Java => JAVA_QUOTES,
+            Syntax::C => C_QUOTES,
+            Syntax::Go => GO_QUOTES,
+            Syntax::Ruby => RUBY_QUOTES,
+            Syntax::Php => PHP_QUOTES,
+            Syntax::Shell => SHELL_QUOTES,
+        }
+    }
+    
+    // Languages where 'x' is a character, not a string
+    fn has_char_literals(&self) -> bool {
+        matches!(self.syntax, Syntax::Rust | Syntax::Java | Syntax::C | Syntax::Go)
+    }
+    
+    // A string right after one of these (or inside its parentheses) is a
+    // path, not text
+    fn import_keywords(&self) -> &'static [&'static str] {
+        match self.syntax {
+            Syntax::Rust => &["include_str!", "include_bytes!", "include!"],
+            Syntax::JavaScript => &["import", "from", "require"],
+            Syntax::C => &["#include", "#import"],
+            Syntax::Go => &["import"],
+            Syntax::Ruby => &["require", "require_relative", "load"],
+            Syntax::Php => &["require", "require_once", "include", "include_once"],
+            Syntax::Shell => &["source"],
+            Syntax::Python | Syntax::Java => &[],
+        }
+    }
+}
+
+struct Scan<'a> {
+    lexer: &'a Lexer,
+    text: &'a str,
+    bytes: &'a [u8],
+    spans: Vec<ProseSpan>,
+    line_start: usize,
+    // Inside `import ( ... )` in Go, or the parentheses of `require(...)`
+    import_block: bool,
+    // Just past the last line break reached outside any token
+    settled: usize,
+}
+
+impl Scan<'_> {
+    fn run(&mut self) {
+        let mut i = 0;
+        
+        // "#!/bin/sh" names an interpreter
+        if self.text.starts_with("#!") && !self.text.starts_with("#![") {
+            i = self.line_end(0);
+        }
+        
+        while i < self.bytes.len() {
+            i = match self.bytes[i] {
+                b'\n' => {
+                    self.line_start = i + 1;
+                    if !self.import_block {
+                        self.settled = i + 1;
+                    }
+                    i + 1
+                }
+                _ => self.token(i),
+            };
+        }
+    }
+    
+    // Handle whatever starts at `i`, returning where it ends
+    fn token(&mut self, i: usize) -> usize {
+        let lexer = self.lexer;
+        
+        if let Some(end) = self.comment(i) {
+            return end;
+        }
+        
+        if self.bytes[i] == b'\'' && lexer.has_char_literals() {
+            return self.char_literal_end(i).unwrap_or(i + 1);
+        }
+        
+        if let Some(quote) = lexer.quotes().iter().find(|quote| self.at(i, quote.open)) {
+            return self.string(i, quote.open.len(), quote.close, quote);
+        }
+        
+        let b = self.bytes[i];
+        if b.is_ascii_alphabetic() || b == b'_' {
+            let end = i + self.bytes[i..]
+                .iter()
+                .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+                .count();
+            return self.prefixed_string(i, end).unwrap_or(end);
+        }
+        
+        match b {
+            b'(' if self.after_import_keyword(i) => self.import_block = true,
+            b')' => self.import_block = false,
+            _ => {}
+        }
+        i + 1
+    }
+    
+    fn comment(&mut self, i: usize) -> Option<usize> {
+        let lexer = self.lexer;
+        
+        if let Some((open, close)) = lexer.block_comment().filter(|(open, _)| self.at(i, open)) {
+            // "/**/" is empty, not the start of a doc comment
+            let doc = lexer.doc_block_comments()
+                .iter()
+                .find(|marker| self.at(i, marker) && !self.at(i, "/**/"));
+            let content_start = i + doc.map_or(open.len(), |marker| marker.len());
+            let (content_end, end) = self.block_comment_end(content_start, open, close);
+            let kind = if doc.is_some() { ProseKind::DocComment } else { ProseKind::Comment };
+            self.push(content_start..content_end, kind);
+            self.advance_line_start(i, end);
+            return Some(end);
+        }
+        
+        // "#" in shell only starts a comment at the start of a word ("$#" is a variable)
+        let word_start = self.lexer.syntax != Syntax::Shell
+            || i == 0
+            || matches!(self.bytes[i - 1], b' ' | b'\t' | b'\n' | b';');
+        // PHP 8 attributes look like comments: "#[Route]"
+        let attribute = self.lexer.syntax == Syntax::Php && self.at(i, "#[");
+        if !word_start || attribute {
+            return None;
+        }
+        
+        let doc = lexer.doc_line_comments().iter().find(|marker| self.at(i, marker));
+        let marker = doc.or_else(|| lexer.line_comments().iter().find(|marker| self.at(i, marker)))?;
+        
+        // "////" is a separator, not a doc comment
+        let kind = if doc.is_some() && !self.at(i, "////") { ProseKind::DocComment } else { ProseKind::Comment };
+        let end = self.line_end(i);
+        self.push(i + marker.len()..end, kind);
+        Some(end)
+    }
+    
+    // (end of the contents, end of the comment); Rust block comments nest
+    fn block_comment_end(&self, from: usize, open: &str, close: &str) -> (usize, usize) {
+        let nested = self.lexer.syntax == Syntax::Rust;
+        let mut depth = 0;
+        let mut j = from;
+        
+        while j < self.bytes.len() {
+            if self.at(j, close) {
+                if depth == 0 {
+                    return (j, j + close.len());
+                }
+                depth -= 1;
+                j += close.len();
+            } else if nested && self.at(j, open) {
+                depth += 1;
+                j += open.len();
+            } else {
+                j += 1;
+            }
+        }
+        
+        (self.bytes.len(), self.bytes.len())
+    }
+    
+    // 'a', '\n', '\u{1F600}'; None for a Rust lifetime ('a)
+    fn char_literal_end(&self, i: usize) -> Option<usize> {
+        let rest = &self.text[i + 1..];
+        if let Some(escaped) = rest.strip_prefix('\\') {
+            let close = escaped.find('\'')? + 1;
+            return (close <= 10).then_some(i + 1 + close + 1);
+        }
+        
+        let c = rest.chars().next()?;
+        rest[c.len_utf8()..].starts_with('\'').then_some(i + 1 + c.len_utf8() + 1)
+    }
+    
+    // r"..." and r#"..."# in Rust; r"...", f"..." and friends in Python
+    fn prefixed_string(&mut self, start: usize, end: usize) -> Option<usize> {
+        let prefix = &self.text[start..end];
+        
+        match self.lexer.syntax {
+            Syntax::Rust if matches!(prefix, "r" | "br" | "cr") => {
+                let hashes = self.bytes[end..].iter().take_while(|b| **b == b'#').count();
+                if self.bytes.get(end + hashes) != Some(&b'"') {
+                    return None;
+                }
+                
+                let close = format!("\"{}", "#".repeat(hashes));
+                let quote = Quote::new("\"", "\"").raw().multiline();
+                Some(self.string(end, hashes + 1, &close, &quote))
+            }
+            Syntax::Python if prefix.len() <= 2 && prefix.chars().all(|c| "rRbBuUfF".contains(c)) => {
+                let quote = self.lexer.quotes().iter().find(|quote| self.at(end, quote.open))?;
+                let raw = prefix.contains(['r', 'R']);
+                let formatted = prefix.contains(['f', 'F']);
+                let quote = Quote {
+                    escapes: !raw,
+                    interpolation: if formatted { &[("{", b'}')] } else { &[] },
+                    ..*quote
+                };
+                Some(self.string(end, quote.open.len(), quote.close, &quote))
+            }
+            _ => None,
+        }
+    }
+    
+    // The string opened at `start` by `open_len` bytes, returning its end
+    fn string(&mut self, start: usize, open_len: usize, close: &str, quote: &Quote) -> usize {
+        let is_path = self.import_block || self.after_import_keyword(start);
+        // A string on its own at the start of a statement documents it
+        let docstring = self.lexer.syntax == Syntax::Python
+            && quote.multiline
+            && self.text[self.line_start..start].trim().is_empty();
+        let kind = if docstring { ProseKind::DocComment } else { ProseKind::StringLiteral };
+        
+        let mut pieces = Vec::new();
+        let mut piece_start = start + open_len;
+        let mut j = piece_start;
+        
+        let (content_end, end) = loop {
+            if j >= self.bytes.len() {
+                break (self.bytes.len(), self.bytes.len());
+            }
+            
+            if quote.escapes && self.bytes[j] == b'\\' {
+                j += 2;
+                continue;
+            }
+            
+            if self.at(j, close) {
+                break (j, j + close.len());
+            }
+            
+            if let Some(&(open, closer)) = quote.interpolation.iter().find(|(open, _)| self.at(j, open)) {
+                // "{{" is a literal brace in a Python f-string
+                if open == "{" && self.at(j + 1, "{") {
+                    j += 2;
+                    continue;
+                }
+                
+                pieces.push(piece_start..j);
+                j = self.interpolation_end(j + open.len(), closer);
+                piece_start = j;
+                continue;
+            }
+            
+            // An unterminated string ends with its line
+            if self.bytes[j] == b'\n' && !quote.multiline {
+                break (j, j);
+            }
+            
+            j += 1;
+        };
+        pieces.push(piece_start..content_end);
+        
+        if !is_path {
+            for piece in pieces {
+                self.push(piece, kind);
+            }
+        }
+        self.advance_line_start(start, end);
+        end
+    }
+    
+    // Just past the `closer` matching an interpolation opened before `from`
+    fn interpolation_end(&self, from: usize, closer: u8) -> usize {
+        let opener = if closer == b')' { b'(' } else { b'{' };
+        let mut depth = 0;
+        
+        for (j, &b) in self.bytes.iter().enumerate().skip(from) {
+            if b == opener {
+                depth += 1;
+            } else if b == closer {
+                if depth == 0 {
+                    return j + 1;
+                }
+                depth -= 1;
+            }
+        }
+        
+        self.bytes.len()
+    }
+    
+    // Whether the code before `i` on its line ends with an import keyword
+    fn after_import_keyword(&self, i: usize) -> bool {
+        let before = self.text[self.line_start..i].trim_end_matches([' ', '\t', '(']);
+        self.lexer.import_keywords().iter().any(|keyword| {
+            before.strip_suffix(keyword).is_some_and(|rest| {
+                !rest.ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '.' || c == '$')
+            })
+        })
+    }
+    
+    fn push(&mut self, range: Range<usize>, kind: ProseKind) {
+        if !range.is_empty() {
+            self.spans.push(ProseSpan::new(range, kind));
+        }
+    }
+    
+    fn advance_line_start(&mut self, start: usize, end: usize) {
+        if let Some(newline) = self.text[start..end].rfind('\n') {
+            self.line_start = start + newline + 1;
+        }
+    }
+    
+    fn line_end(&self, i: usize) -> usize {
+        self.text[i..].find('\n').map_or(self.text.len(), |n| i + n)
+    }
+    
+    fn at(&self, i: usize, delimiter: &str) -> bool {
+        self.bytes.get(i..).is_some_and(|rest| rest.starts_with(delimiter.as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn spans<'a>(language: &str, text: &'a str) -> Vec<(&'a str, ProseKind)> {
+        Lexer::for_language(language)
+            .unwrap()
+            .prose_spans(text)
+            .into_iter()
+            .map(|span| (&text[span.range], span.kind))
+            .collect()
+    }
+    
+    #[test]
+    fn rust_comments_and_strings() {
+        let text = "/// Adds one\nfn add(x: i32) -> i32 {\n    // plain comment\n    println!(\"Hello world\");\n    x + 1\n}\n";
+        let found = spans("rust", text);
+        assert!(found.iter().any(|&(text, kind)| text.contains("Adds one") && kind == ProseKind::DocComment));
+        assert!(found.iter().any(|&(text, kind)| text.contains("plain comment") && kind == ProseKind::Comment));
+        assert!(found.contains(&("Hello world", ProseKind::StringLiteral)));
+        assert!(!found.iter().any(|(text, _)| text.contains("add(")));
+    }
+    
+    #[test]
+    fn python_docstrings_and_comments() {
+        let text = "def f():\n    \"\"\"Return the answer.\"\"\"\n    # comment here\n    return 'forty two'\n";
+        let found: Vec<&str> = spans("python", text).into_iter().map(|(text, _)| text).collect();
+        assert!(found.iter().any(|text| text.contains("Return the answer.")));
+        assert!(found.iter().any(|text| text.contains("comment here")));
+        assert!(found.contains(&"forty two"));
+        assert!(!found.iter().any(|text| text.contains("def")));
+    }
+    
+    #[test]
+    fn import_paths_are_left_out() {
+        assert!(spans("go", "import \"fmt\"\n").is_empty());
+        assert!(spans("cpp", "#include \"util.h\"\n").is_empty());
+        assert!(spans("ruby", "require 'json'\n").is_empty());
+    }
+    
+    #[test]
+    fn interpolations_are_left_out() {
+        let found: Vec<&str> = spans("javascript", "const s = `Hello ${name}, welcome`;\n").into_iter().map(|(text, _)| text).collect();
+        assert_eq!(found.concat(), "Hello , welcome");
+    }
+    
+    #[test]
+    fn unknown_languages_have_no_lexer() {
+        assert!(Lexer::for_language("cobol").is_none());
+    }
+}
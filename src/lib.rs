@@ -1,19 +1,55 @@
 // Core modules
+pub mod cache;
 pub mod checker;
+pub mod compound;
 pub mod dictionary;
+#[cfg(feature = "gui")]
 pub mod editor;
+pub mod fluent;
+pub mod gettext;
+pub mod grammar;
+#[cfg(feature = "gui")]
 pub mod gui;
+pub mod hunspell;
+pub mod json;
+pub mod keyboard;
 pub mod language;
+pub mod latex;
+pub mod lexer;
+pub mod markdown;
+pub mod markup;
+pub mod ngram;
+pub mod phonetic;
+pub mod policy;
+pub mod properties;
+pub mod prose;
+pub mod segment;
+pub mod selector;
+pub mod sentence;
+#[cfg(feature = "gui")]
 pub mod sidebar;
+pub mod stream;
+pub mod symspell;
+#[cfg(feature = "gui")]
 pub mod theme;
+pub mod tokenizer;
+pub mod toml;
 pub mod util;
+pub mod wordlist;
+pub mod yaml;
 
 // Re-export common types for easier access
-pub use checker::{DocumentAnalysis, SpellChecker, WordCheck, WordType};
+pub use checker::{
+    Diagnostic, DiagnosticKind, DocumentAnalysis, SpellChecker, Suggestion, SuggestionStrategy, WordCheck, WordType,
+};
 pub use dictionary::DictionaryManager;
+pub use policy::{CheckPolicy, TypePolicy};
+#[cfg(feature = "gui")]
 pub use gui::SpellCheckerApp;
 pub use language::{Language, LanguageManager};
+#[cfg(feature = "gui")]
 pub use theme::AtomTheme;
+#[cfg(feature = "gui")]
 pub use sidebar::Sidebar;
 
 // Error handling
@@ -0,0 +1,396 @@
+use crate::prose::{ProseKind, ProseSpan};
+use std::ops::Range;
+
+const HTML_BLOCK_TAGS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "center", "details", "div", "dl",
+    "fieldset", "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6",
+    "header", "hr", "li", "main", "nav", "ol", "p", "pre", "script", "section",
+    "style", "summary", "table", "tbody", "td", "tfoot", "th", "thead", "tr", "ul",
+];
+
+/// The prose in a Markdown document: paragraphs, headings, list items,
+/// blockquotes, table cells, link text and image descriptions.
+///
+/// Left out are fenced and indented code blocks, inline code, URLs and
+/// autolinks, link targets and reference-link definitions, HTML blocks,
+/// inline tags and entities, and the keys of YAML (`---`) or TOML (`+++`)
+/// front matter. Front matter values are kept.
+pub fn prose_spans(text: &str) -> Vec<ProseSpan> {
+    let mut spans = Vec::new();
+    let lines = lines(text);
+    let mut i = front_matter(text, &lines, &mut spans);
+    
+    // Consecutive prose lines, scanned together since inline code and links
+    // may wrap onto the next line
+    let mut paragraph: Option<Range<usize>> = None;
+    let mut previous_blank = true;
+    let mut in_list = false;
+    
+    while i < lines.len() {
+        let line = lines[i].clone();
+        let content = &text[line.clone()];
+        let trimmed = content.trim_start();
+        let indent = content.len() - trimmed.len();
+        let blank = trimmed.is_empty();
+        
+        let block_end = if blank {
+            None
+        } else if let Some(fence) = code_fence(trimmed) {
+            Some(fenced_block_end(text, &lines, i, fence))
+        } else if previous_blank && !in_list && (content.starts_with("    ") || content.starts_with('\t')) {
+            Some(indented_block_end(text, &lines, i))
+        } else if indent <= 3 && is_html_block_start(trimmed) {
+            Some(html_block_end(text, &lines, i))
+        } else if indent <= 3 && is_link_definition(trimmed) {
+            Some(i + 1)
+        } else {
+            None
+        };
+        
+        if blank || block_end.is_some() {
+            if let Some(range) = paragraph.take() {
+                inline_spans(text, range, &mut spans);
+            }
+        }
+        
+        if let Some(end) = block_end {
+            i = end;
+            previous_blank = false;
+            continue;
+        }
+        
+        if !blank {
+            if is_list_item(trimmed) {
+                in_list = true;
+            } else if indent == 0 && previous_blank {
+                in_list = false;
+            }
+            
+            paragraph = Some(match paragraph {
+                Some(range) => range.start..line.end,
+                None => line.clone(),
+            });
+        }
+        
+        previous_blank = blank;
+        i += 1;
+    }
+    
+    if let Some(range) = paragraph {
+        inline_spans(text, range, &mut spans);
+    }
+    
+    spans
+}
+
+// Byte range of every line, without its line break
+fn lines(text: &str) -> Vec<Range<usize>> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for line in text.split('\n') {
+        let content = line.strip_suffix('\r').unwrap_or(line);
+        lines.push(start..start + content.len());
+        start += line.len() + 1;
+    }
+    lines
+}
+
+// Keeps the values of front matter at the very start of the document,
+// returning the first line after it
+fn front_matter(text: &str, lines: &[Range<usize>], spans: &mut Vec<ProseSpan>) -> usize {
+    let Some(first) = lines.first() else {
+        return 0;
+    };
+    let (fence, separator) = match &text[first.clone()] {
+        "---" => ("---", ':'),
+        "+++" => ("+++", '='),
+        _ => return 0,
+    };
+    
+    let Some(end) = lines.iter().skip(1).position(|line| {
+        let line = &text[line.clone()];
+        line == fence || (fence == "---" && line == "...")
+    }) else {
+        return 0;
+    };
+    
+    for line in &lines[1..=end] {
+        let content = &text[line.clone()];
+        // "title: ..." and "- item" in YAML, "title = ..." in TOML
+        let value_start = match content.find(separator) {
+            Some(at) => at + 1,
+            None => content.len() - content.trim_start_matches([' ', '-']).len(),
+        };
+        let value = &content[value_start..];
+        let value = value.trim();
+        if value.is_empty() || value.starts_with(['[', '{', '#', '|', '>']) {
+            continue;
+        }
+        
+        // Slugs, paths and dates rather than prose
+        let unquoted = value.trim_matches(['"', '\'']);
+        if !unquoted.contains(' ') && unquoted.contains(['_', '/', '.', ':']) {
+            continue;
+        }
+        
+        let start = line.start + value_start + content[value_start..].find(unquoted).unwrap_or(0);
+        spans.push(ProseSpan::new(start..start + unquoted.len(), ProseKind::Text));
+    }
+    
+    end + 2
+}
+
+// The fence character and length of a line opening a code block
+fn code_fence(trimmed: &str) -> Option<(char, usize)> {
+    let c = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|x| *x == c).count();
+    // An info string can't contain backticks: "``` foo ```" is inline code
+    let info = &trimmed[len..];
+    (len >= 3 && !(c == '`' && info.contains('`'))).then_some((c, len))
+}
+
+// The line after the fence closing the block opened on line `start`
+fn fenced_block_end(text: &str, lines: &[Range<usize>], start: usize, (c, len): (char, usize)) -> usize {
+    for (i, line) in lines.iter().enumerate().skip(start + 1) {
+        let trimmed = text[line.clone()].trim();
+        let run = trimmed.chars().take_while(|x| *x == c).count();
+        if run >= len && run == trimmed.chars().count() {
+            return i + 1;
+        }
+    }
+    lines.len()
+}
+
+fn indented_block_end(text: &str, lines: &[Range<usize>], start: usize) -> usize {
+    let mut end = start + 1;
+    for (i, line) in lines.iter().enumerate().skip(start + 1) {
+        let content = &text[line.clone()];
+        if content.starts_with("    ") || content.starts_with('\t') {
+            end = i + 1;
+        } else if !content.trim().is_empty() {
+            break;
+        }
+    }
+    end
+}
+
+// Block-level tags start an HTML block wherever they appear; any other tag
+// only when it is alone on its line
+fn is_html_block_start(trimmed: &str) -> bool {
+    let Some(rest) = trimmed.strip_prefix('<') else {
+        return false;
+    };
+    if rest.starts_with('!') || rest.starts_with('?') {
+        return true;
+    }
+    
+    let rest = rest.strip_prefix('/').unwrap_or(rest);
+    let name: String = rest
+        .chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    if name.is_empty() || is_autolink(trimmed) {
+        return false;
+    }
+    
+    HTML_BLOCK_TAGS.contains(&name.as_str()) || (trimmed.ends_with('>') && trimmed.find('>') == Some(trimmed.len() - 1))
+}
+
+// HTML blocks run to the next blank line; comments to their "-->"
+fn html_block_end(text: &str, lines: &[Range<usize>], start: usize) -> usize {
+    let is_comment = text[lines[start].clone()].trim_start().starts_with("<!--");
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        let content = &text[line.clone()];
+        if is_comment && content.contains("-->") {
+            return i + 1;
+        }
+        if !is_comment && content.trim().is_empty() {
+            return i;
+        }
+    }
+    lines.len()
+}
+
+// "[label]: https://example.com "Title""
+fn is_link_definition(trimmed: &str) -> bool {
+    trimmed.starts_with('[')
+        && !trimmed.starts_with("[^")
+        && trimmed.find("]:").is_some_and(|end| !trimmed[1..end].contains(']'))
+}
+
+fn is_list_item(trimmed: &str) -> bool {
+    let unordered = trimmed.starts_with(['-', '*', '+'])
+        && trimmed[1..].starts_with([' ', '\t']);
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    let ordered = digits > 0
+        && trimmed[digits..].starts_with(['.', ')'])
+        && trimmed[digits + 1..].starts_with([' ', '\t']);
+    unordered || ordered
+}
+
+// "<https://example.com>" or "<someone@example.com>"
+fn is_autolink(text: &str) -> bool {
+    let Some(end) = text.find('>') else {
+        return false;
+    };
+    let inner = &text[1..end];
+    !inner.contains(char::is_whitespace) && (inner.contains("://") || inner.contains('@'))
+}
+
+// Split `range` around inline code, URLs, link targets, tags and entities
+fn inline_spans(text: &str, range: Range<usize>, spans: &mut Vec<ProseSpan>) {
+    let bytes = text.as_bytes();
+    let mut piece_start = range.start;
+    let mut i = range.start;
+    
+    let mut push = |piece: Range<usize>| {
+        if !piece.is_empty() {
+            spans.push(ProseSpan::new(piece, ProseKind::Text));
+        }
+    };
+    
+    while i < range.end {
+        let skip = match bytes[i] {
+            b'\\' => {
+                i += 2;
+                continue;
+            }
+            b'`' => code_span_end(text, i, range.end),
+            b'<' => tag_end(text, i, range.end),
+            b'&' => entity_end(text, i, range.end),
+            // "](target)" and "][label]" after link text
+            b']' if text[i..range.end].starts_with("](") => {
+                closing(text, i + 1, range.end, b'(', b')').map(|end| (i + 1, end))
+            }
+            b']' if text[i..range.end].starts_with("][") => {
+                closing(text, i + 1, range.end, b'[', b']').map(|end| (i + 1, end))
+            }
+            b'h' | b'w' => bare_url_end(text, i, range.end),
+            _ => None,
+        };
+        
+        match skip {
+            Some((start, end)) => {
+                push(piece_start..start);
+                piece_start = end;
+                i = end;
+            }
+            None => i += 1,
+        }
+    }
+    
+    push(piece_start..range.end);
+}
+
+// "`code`" or "``code with ` inside``"
+fn code_span_end(text: &str, start: usize, limit: usize) -> Option<(usize, usize)> {
+    let run = text[start..limit].bytes().take_while(|b| *b == b'`').count();
+    let delimiter = &text[start..start + run];
+    let mut from = start + run;
+    
+    while let Some(found) = text[from..limit].find(delimiter) {
+        let at = from + found;
+        let end_run = text[at..limit].bytes().take_while(|b| *b == b'`').count();
+        if end_run == run {
+            return Some((start, at + run));
+        }
+        from = at + end_run;
+    }
+    
+    // An unmatched run of backticks is just text
+    None
+}
+
+// Autolinks and inline HTML tags
+fn tag_end(text: &str, start: usize, limit: usize) -> Option<(usize, usize)> {
+    let rest = &text[start..limit];
+    if rest.starts_with("<!--") {
+        let end = rest.find("-->").map_or(limit, |end| start + end + 3);
+        return Some((start, end));
+    }
+    
+    let inner = rest[1..].strip_prefix('/').unwrap_or(&rest[1..]);
+    if !inner.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    
+    let end = rest.find('>')?;
+    Some((start, start + end + 1))
+}
+
+// "&amp;", "&#233;", "&#x1F600;"
+fn entity_end(text: &str, start: usize, limit: usize) -> Option<(usize, usize)> {
+    let rest = &text[start + 1..limit];
+    let name = rest.strip_prefix('#').unwrap_or(rest);
+    let len = name.bytes().take_while(u8::is_ascii_alphanumeric).count();
+    let end = start + 1 + (rest.len() - name.len()) + len;
+    (len > 0 && len <= 32 && text[end..limit].starts_with(';')).then_some((start, end + 1))
+}
+
+// "https://..." and "www..." written out in the text
+fn bare_url_end(text: &str, start: usize, limit: usize) -> Option<(usize, usize)> {
+    let rest = &text[start..limit];
+    let is_url = rest.starts_with("http://") || rest.starts_with("https://") || rest.starts_with("www.");
+    let at_word_start = start == 0 || !text[..start].ends_with(|c: char| c.is_alphanumeric());
+    if !is_url || !at_word_start {
+        return None;
+    }
+    
+    let len = rest.find(|c: char| c.is_whitespace() || c == '<' || c == ')').unwrap_or(rest.len());
+    Some((start, start + len))
+}
+
+// The end of the bracketed group opened at `start`
+fn closing(text: &str, start: usize, limit: usize, open: u8, close: u8) -> Option<usize> {
+    let mut depth = 0;
+    for (i, &b) in text.as_bytes()[start..limit].iter().enumerate() {
+        if b == open {
+            depth += 1;
+        } else if b == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(start + i + 1);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn prose(text: &str) -> Vec<&str> {
+        prose_spans(text)
+            .into_iter()
+            .map(|span| &text[span.range])
+            .collect()
+    }
+    
+    #[test]
+    fn code_is_left_out() {
+        let text = "Some text.\n\n```rust\nlet x = 1;\n```\n\n    indented code\n\nUse `foo` here.\n";
+        let words = prose(text).concat();
+        assert!(words.contains("Some text."));
+        assert!(words.contains("here."));
+        assert!(!words.contains("let x"));
+        assert!(!words.contains("indented"));
+        assert!(!words.contains("foo"));
+    }
+    
+    #[test]
+    fn link_text_is_kept_without_its_target() {
+        let words = prose("See [the guide](https://example.com/guide) now.\n").concat();
+        assert!(words.contains("the guide"));
+        assert!(!words.contains("example"));
+    }
+    
+    #[test]
+    fn front_matter_keys_are_left_out() {
+        let words = prose("---\ntitle: Hello world\n---\n\nBody text.\n").concat();
+        assert!(words.contains("Hello world"));
+        assert!(words.contains("Body text."));
+        assert!(!words.contains("title"));
+    }
+}
@@ -0,0 +1,431 @@
+use crate::language::Language;
+use crate::prose::{without_placeholders, ProseKind, ProseSpan};
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+// Elements whose content is never prose
+const VERBATIM_ELEMENTS: &[&str] = &["code", "pre", "script", "style"];
+// Elements whose content runs to their closing tag, '<' and all
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+// HTML elements without a closing tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta",
+    "param", "source", "track", "wbr",
+];
+
+// Named character references. Only the capital Latin-1 letters are listed;
+// "eacute" is found as the lowercase of "Eacute".
+const ENTITIES: &[(&str, &str)] = &[
+    ("amp", "&"), ("lt", "<"), ("gt", ">"), ("quot", "\""), ("apos", "'"),
+    ("nbsp", "\u{A0}"), ("shy", ""), ("ndash", "–"), ("mdash", "—"),
+    ("lsquo", "‘"), ("rsquo", "’"), ("sbquo", "‚"), ("ldquo", "“"), ("rdquo", "”"),
+    ("bdquo", "„"), ("laquo", "«"), ("raquo", "»"), ("hellip", "…"), ("middot", "·"),
+    ("bull", "•"), ("copy", "©"), ("reg", "®"), ("trade", "™"), ("euro", "€"),
+    ("pound", "£"), ("deg", "°"), ("iexcl", "¡"), ("iquest", "¿"), ("ordf", "ª"),
+    ("ordm", "º"), ("szlig", "ß"),
+    ("Agrave", "À"), ("Aacute", "Á"), ("Acirc", "Â"), ("Atilde", "Ã"), ("Auml", "Ä"),
+    ("Aring", "Å"), ("AElig", "Æ"), ("Ccedil", "Ç"), ("Egrave", "È"), ("Eacute", "É"),
+    ("Ecirc", "Ê"), ("Euml", "Ë"), ("Igrave", "Ì"), ("Iacute", "Í"), ("Icirc", "Î"),
+    ("Iuml", "Ï"), ("ETH", "Ð"), ("Ntilde", "Ñ"), ("Ograve", "Ò"), ("Oacute", "Ó"),
+    ("Ocirc", "Ô"), ("Otilde", "Õ"), ("Ouml", "Ö"), ("Oslash", "Ø"), ("OElig", "Œ"),
+    ("Ugrave", "Ù"), ("Uacute", "Ú"), ("Ucirc", "Û"), ("Uuml", "Ü"), ("Yacute", "Ý"),
+    ("Yuml", "Ÿ"), ("THORN", "Þ"),
+];
+
+/// The prose in an HTML or XML document: text nodes, CDATA sections and
+/// the values of `attributes` (lowercase names such as "alt" and "title").
+///
+/// Left out are tags, comments, processing instructions, the content of
+/// `<script>`, `<style>`, `<code>` and `<pre>`, and elements marked
+/// `translate="no"` or, in Android resources, `translatable="false"`.
+/// Entities are decoded, and every span carries the language of the
+/// nearest `lang` or `xml:lang` attribute. With `placeholders`, escapes and
+/// format placeholders (`\n`, `%1$s`) are left out as well.
+pub fn prose_spans(text: &str, attributes: &BTreeSet<String>, placeholders: bool) -> Vec<ProseSpan> {
+    let root = Element {
+        name: String::new(),
+        verbatim: false,
+        translate: true,
+        language: None,
+    };
+    let mut scan = Scan {
+        text,
+        attributes,
+        placeholders,
+        stack: vec![root],
+        spans: Vec::new(),
+    };
+    scan.run();
+    scan.spans
+}
+
+// An open element and what it passes on to its content
+#[derive(Debug, Clone)]
+struct Element {
+    name: String,
+    // Inside code, preformatted text, a script or a style sheet
+    verbatim: bool,
+    translate: bool,
+    language: Option<Language>,
+}
+
+impl Element {
+    fn has_prose(&self) -> bool {
+        self.translate && !self.verbatim
+    }
+}
+
+struct Scan<'a> {
+    text: &'a str,
+    attributes: &'a BTreeSet<String>,
+    // Whether text may hold escapes and format placeholders
+    placeholders: bool,
+    // Never empty: the bottom is the document itself
+    stack: Vec<Element>,
+    spans: Vec<ProseSpan>,
+}
+
+impl Scan<'_> {
+    fn run(&mut self) {
+        let mut pos = 0;
+        let mut text_start = 0;
+        
+        while let Some(found) = self.text[pos..].find('<') {
+            let at = pos + found;
+            // A '<' that opens no markup ("a < b") is text
+            if !opens_markup(&self.text[at..]) {
+                pos = at + 1;
+                continue;
+            }
+            
+            // The text belongs to the elements open before the tag
+            self.text_node(text_start..at);
+            pos = self.markup_end(at);
+            text_start = pos;
+        }
+        
+        self.text_node(text_start..self.text.len());
+    }
+    
+    fn current(&self) -> &Element {
+        self.stack.last().expect("the document element is never closed")
+    }
+    
+    // Read the markup starting at `at`, returning where it ends
+    fn markup_end(&mut self, at: usize) -> usize {
+        let rest = &self.text[at..];
+        
+        if rest.starts_with("<!--") {
+            return find_end(self.text, at + 4, "-->");
+        }
+        if let Some(content) = rest.strip_prefix("<![CDATA[") {
+            let start = at + 9;
+            let end = content.find("]]>").map_or(self.text.len(), |i| start + i);
+            let element = self.current();
+            if element.has_prose() {
+                let language = element.language;
+                self.push_span(ProseSpan { language, ..ProseSpan::new(start..end, ProseKind::Text) });
+            }
+            return find_end(self.text, end, "]]>");
+        }
+        if rest.starts_with("<?") {
+            return find_end(self.text, at + 2, "?>");
+        }
+        if rest.starts_with("<!") {
+            // A DOCTYPE's internal subset holds declarations of its own
+            let close = rest.find('>').unwrap_or(rest.len());
+            return match rest[..close].find('[') {
+                Some(subset) => find_end(self.text, at + subset, "]>"),
+                None => find_end(self.text, at, ">"),
+            };
+        }
+        if let Some(closing) = rest.strip_prefix("</") {
+            let name = tag_name(closing);
+            self.close(&name);
+            return find_end(self.text, at + 2, ">");
+        }
+        
+        let name = tag_name(&rest[1..]);
+        self.open(at, name)
+    }
+    
+    // Read the start tag at `at`, returning where it ends
+    fn open(&mut self, at: usize, name: String) -> usize {
+        let parent = self.current().clone();
+        let mut element = Element {
+            verbatim: parent.verbatim || VERBATIM_ELEMENTS.contains(&name.as_str()),
+            name,
+            ..parent.clone()
+        };
+        
+        let mut pos = at + 1 + element.name.len();
+        let mut checked_values = Vec::new();
+        let mut self_closing = false;
+        
+        loop {
+            let rest = self.text[pos..].trim_start();
+            pos = self.text.len() - rest.len();
+            if rest.is_empty() {
+                break;
+            }
+            if rest.starts_with('>') {
+                pos += 1;
+                break;
+            }
+            if rest.starts_with("/>") {
+                pos += 2;
+                self_closing = true;
+                break;
+            }
+            
+            let name_len = rest
+                .find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/'))
+                .unwrap_or(rest.len());
+            if name_len == 0 {
+                // A stray '=' or '/'
+                pos += 1;
+                continue;
+            }
+            let attribute = rest[..name_len].to_ascii_lowercase();
+            pos += name_len;
+            
+            let after_name = self.text[pos..].trim_start();
+            let value = match after_name.strip_prefix('=') {
+                Some(after_equals) => {
+                    let value_start = self.text.len() - after_equals.trim_start().len();
+                    let (value, end) = attribute_value(self.text, value_start);
+                    pos = end;
+                    value
+                }
+                None => pos..pos,
+            };
+            let value_text = &self.text[value.clone()];
+            
+            match attribute.as_str() {
+                "lang" | "xml:lang" => {
+                    if let Some(language) = Language::from_tag(value_text) {
+                        element.language = Some(language);
+                    }
+                }
+                "translate" => {
+                    if value_text.eq_ignore_ascii_case("no") {
+                        element.translate = false;
+                    } else if value_text.is_empty() || value_text.eq_ignore_ascii_case("yes") {
+                        element.translate = true;
+                    }
+                }
+                "translatable" => element.translate = !value_text.eq_ignore_ascii_case("false"),
+                _ if self.attributes.contains(&attribute) => checked_values.push(value),
+                _ => {}
+            }
+        }
+        
+        // The element's own attributes are prose even in <pre>
+        if element.translate && !parent.verbatim {
+            for value in checked_values {
+                self.push_decoded(value, element.language);
+            }
+        }
+        
+        if self_closing || VOID_ELEMENTS.contains(&element.name.as_str()) {
+            return pos;
+        }
+        if RAW_TEXT_ELEMENTS.contains(&element.name.as_str()) {
+            // Skip the content and the closing tag together. ASCII
+            // lowercasing keeps byte offsets.
+            let closing = format!("</{}", element.name);
+            return match self.text[pos..].to_ascii_lowercase().find(&closing) {
+                Some(found) => find_end(self.text, pos + found, ">"),
+                None => self.text.len(),
+            };
+        }
+        
+        self.stack.push(element);
+        pos
+    }
+    
+    // Close the innermost open `name` and anything left open inside it
+    fn close(&mut self, name: &str) {
+        if let Some(i) = self.stack.iter().rposition(|element| element.name == name) {
+            if i > 0 {
+                self.stack.truncate(i);
+            }
+        }
+    }
+    
+    fn text_node(&mut self, range: Range<usize>) {
+        let element = self.current();
+        if element.has_prose() {
+            let language = element.language;
+            self.push_decoded(range, language);
+        }
+    }
+    
+    // Push `range` split around its entities, each decoded as a span of its own
+    fn push_decoded(&mut self, range: Range<usize>, language: Option<Language>) {
+        let mut piece_start = range.start;
+        let mut pos = range.start;
+        
+        while let Some(found) = self.text[pos..range.end].find('&') {
+            let at = pos + found;
+            let Some((len, decoded)) = entity(&self.text[at..range.end]) else {
+                pos = at + 1;
+                continue;
+            };
+            
+            self.push_span(ProseSpan { language, ..ProseSpan::new(piece_start..at, ProseKind::Text) });
+            // Entities nothing is known about ("&product;") are left out
+            if let Some(decoded) = decoded {
+                self.spans.push(ProseSpan {
+                    decoded: Some(decoded.replace(['\n', '\r'], " ")),
+                    language,
+                    ..ProseSpan::new(at..at + len, ProseKind::Text)
+                });
+            }
+            pos = at + len;
+            piece_start = pos;
+        }
+        
+        self.push_span(ProseSpan { language, ..ProseSpan::new(piece_start..range.end, ProseKind::Text) });
+    }
+    
+    // Push `span` unless it is only whitespace, without its placeholders
+    fn push_span(&mut self, span: ProseSpan) {
+        if !self.placeholders {
+            if !self.text[span.range.clone()].trim().is_empty() {
+                self.spans.push(span);
+            }
+            return;
+        }
+        for part in without_placeholders(self.text, span.range.clone()) {
+            if !self.text[part.clone()].trim().is_empty() {
+                self.spans.push(ProseSpan { range: part, ..span.clone() });
+            }
+        }
+    }
+}
+
+// Whether `rest` starts with a tag, comment, CDATA section, declaration or
+// processing instruction
+fn opens_markup(rest: &str) -> bool {
+    let after = &rest[1..];
+    let name = after.strip_prefix('/').unwrap_or(after);
+    after.starts_with(['!', '?']) || name.starts_with(char::is_alphabetic)
+}
+
+// The name of the tag at the start of `rest`, ASCII lowercased
+fn tag_name(rest: &str) -> String {
+    let len = rest
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, ':' | '-' | '_' | '.')))
+        .unwrap_or(rest.len());
+    rest[..len].to_ascii_lowercase()
+}
+
+// The value of an attribute starting at `start`, without its quotes, and
+// where the value ends
+fn attribute_value(text: &str, start: usize) -> (Range<usize>, usize) {
+    let rest = &text[start..];
+    match rest.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let end = rest[1..].find(quote).map_or(text.len(), |i| start + 1 + i);
+            (start + 1..end, (end + 1).min(text.len()))
+        }
+        _ => {
+            let len = rest
+                .find(|c: char| c.is_whitespace() || c == '>')
+                .unwrap_or(rest.len());
+            (start..start + len, start + len)
+        }
+    }
+}
+
+// The end of `delimiter` searched for from `from`, or the end of the text
+fn find_end(text: &str, from: usize, delimiter: &str) -> usize {
+    text[from..]
+        .find(delimiter)
+        .map_or(text.len(), |i| from + i + delimiter.len())
+}
+
+// The length of the character reference starting `rest` and what it stands
+// for, if known: "&amp;", "&#233;", "&#xE9;"
+fn entity(rest: &str) -> Option<(usize, Option<String>)> {
+    let body = &rest[1..];
+    let end = body.find(';').filter(|end| (1..=32).contains(end))?;
+    let name = &body[..end];
+    
+    let decoded = if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => number.parse::<u32>().ok(),
+        };
+        code.and_then(char::from_u32).map(String::from)
+    } else if name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        named_entity(name)
+    } else {
+        return None;
+    };
+    
+    Some((end + 2, decoded))
+}
+
+fn named_entity(name: &str) -> Option<String> {
+    if let Some((_, value)) = ENTITIES.iter().find(|(entity, _)| *entity == name) {
+        return Some(value.to_string());
+    }
+    if !name.chars().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    ENTITIES
+        .iter()
+        .find(|(entity, _)| entity.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn spans(text: &str) -> Vec<ProseSpan> {
+        prose_spans(text, &BTreeSet::from(["alt".to_string()]), false)
+    }
+    
+    fn prose(text: &str) -> Vec<&str> {
+        spans(text)
+            .into_iter()
+            .map(|span| &text[span.range])
+            .collect()
+    }
+    
+    #[test]
+    fn text_and_chosen_attributes_are_prose() {
+        let words = prose("<p class=\"intro\">Hello <b>world</b></p><img alt=\"A cat\" src=\"cat.png\">").concat();
+        assert!(words.contains("Hello"));
+        assert!(words.contains("world"));
+        assert!(words.contains("A cat"));
+        assert!(!words.contains("intro"));
+        assert!(!words.contains("cat.png"));
+    }
+    
+    #[test]
+    fn verbatim_and_untranslated_elements_are_left_out() {
+        let text = "<p>Run <code>ls -la</code> now</p><script>if (a < b) run()</script><p translate=\"no\">Acme</p>";
+        let words = prose(text).concat();
+        assert!(words.contains("Run"));
+        assert!(!words.contains("ls"));
+        assert!(!words.contains("run()"));
+        assert!(!words.contains("Acme"));
+    }
+    
+    #[test]
+    fn entities_are_decoded() {
+        let decoded: Vec<String> = spans("<p>caf&eacute;</p>").into_iter().filter_map(|span| span.decoded).collect();
+        assert_eq!(decoded, ["é"]);
+    }
+    
+    #[test]
+    fn spans_carry_the_nearest_language() {
+        let spans = spans("<html lang=\"en\"><p>Hello</p><p lang=\"fr\">Bonjour</p></html>");
+        let languages: Vec<Option<Language>> = spans.iter().map(|span| span.language).collect();
+        assert_eq!(languages, [Some(Language::English), Some(Language::French)]);
+    }
+}
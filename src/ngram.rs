@@ -0,0 +1,101 @@
+use crate::language::{Language, LanguageManager};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+// Weight given to the unigram estimate when a bigram was never seen
+// ("stupid backoff")
+const BACKOFF: f64 = 0.4;
+
+/// Unigram and bigram counts from a local corpus.
+///
+/// The count file has one n-gram per line followed by a tab and its count:
+/// `their house\t1520` for a bigram, `their\t90210` for a word on its own.
+/// Lines starting with `#` are comments.
+#[derive(Debug, Clone, Default)]
+pub struct BigramModel {
+    unigrams: HashMap<String, u64>,
+    // Keyed by "first second"
+    bigrams: HashMap<String, u64>,
+    total: u64,
+}
+
+impl BigramModel {
+    /// The model for `language`, if it has a count file
+    pub fn for_language(language: Language) -> Option<Self> {
+        let path = LanguageManager::get_ngram_path(&language)?;
+        match Self::load(&path) {
+            Ok(model) => Some(model),
+            Err(e) => {
+                eprintln!("Warning: Could not load n-gram counts from {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+    
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        Self::parse(BufReader::new(File::open(path)?))
+    }
+    
+    /// Read counts in the format of the count file
+    pub fn parse(reader: impl BufRead) -> anyhow::Result<Self> {
+        let mut model = Self::default();
+        
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            
+            let Some((ngram, count)) = line.rsplit_once('\t') else {
+                continue;
+            };
+            let Ok(count) = count.trim().parse::<u64>() else {
+                continue;
+            };
+            
+            let words: Vec<String> = ngram.split_whitespace().map(str::to_lowercase).collect();
+            match words.as_slice() {
+                [word] => {
+                    *model.unigrams.entry(word.clone()).or_insert(0) += count;
+                    model.total += count;
+                }
+                [first, second] => {
+                    *model.bigrams.entry(format!("{} {}", first, second)).or_insert(0) += count;
+                }
+                _ => {}
+            }
+        }
+        
+        Ok(model)
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.unigrams.is_empty() && self.bigrams.is_empty()
+    }
+    
+    pub fn unigram(&self, word: &str) -> u64 {
+        self.unigrams.get(word).copied().unwrap_or(0)
+    }
+    
+    pub fn bigram(&self, first: &str, second: &str) -> u64 {
+        self.bigrams.get(&format!("{} {}", first, second)).copied().unwrap_or(0)
+    }
+    
+    /// Log probability of `word` following `previous`, backing off to how
+    /// common `word` is when the pair was never seen. Words are lowercase.
+    pub fn log_probability(&self, previous: &str, word: &str) -> f64 {
+        let pair = self.bigram(previous, word);
+        let previous_count = self.unigram(previous);
+        
+        if pair > 0 && previous_count > 0 {
+            return (pair as f64 / previous_count as f64).min(1.0).ln();
+        }
+        
+        // Add-one smoothing so unseen words aren't impossible
+        let vocabulary = self.unigrams.len() as f64 + 1.0;
+        BACKOFF.ln() + ((self.unigram(word) as f64 + 1.0) / (self.total as f64 + vocabulary)).ln()
+    }
+}
@@ -0,0 +1,815 @@
+use crate::language::Language;
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+// Double Metaphone keys are usually cut at 4 characters; a little longer
+// keeps the buckets of a large dictionary small
+const MAX_KEY_LENGTH: usize = 6;
+// Keys this short match too many unrelated words to be useful
+const MIN_KEY_LENGTH: usize = 2;
+
+/// Turns a word into keys that are equal for words which sound alike
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhoneticEncoder {
+    DoubleMetaphone,
+    Afrikaans,
+    French,
+}
+
+impl PhoneticEncoder {
+    pub fn for_language(language: Language) -> Option<Self> {
+        match language {
+            Language::English => Some(PhoneticEncoder::DoubleMetaphone),
+            Language::Afrikaans => Some(PhoneticEncoder::Afrikaans),
+            Language::French => Some(PhoneticEncoder::French),
+            _ => None,
+        }
+    }
+    
+    /// One or two keys for `word` (Double Metaphone gives an alternate
+    /// pronunciation when there is one)
+    pub fn keys(&self, word: &str) -> Vec<String> {
+        let mut keys = match self {
+            PhoneticEncoder::DoubleMetaphone => {
+                let (primary, alternate) = double_metaphone(word);
+                vec![primary, alternate]
+            }
+            PhoneticEncoder::Afrikaans => vec![encode_with_rules(word, AFRIKAANS_RULES, &[])],
+            PhoneticEncoder::French => vec![encode_with_rules(word, FRENCH_RULES, FRENCH_SILENT_ENDINGS)],
+        };
+        
+        keys.retain(|key| key.len() >= MIN_KEY_LENGTH);
+        keys.dedup();
+        keys
+    }
+}
+
+/// Maps phonetic keys back to the dictionary words that produce them
+#[derive(Debug, Clone)]
+pub struct PhoneticIndex {
+    encoder: PhoneticEncoder,
+    words: Vec<String>,
+    keys: HashMap<String, Vec<u32>>,
+}
+
+impl PhoneticIndex {
+    pub fn new<'a>(encoder: PhoneticEncoder, words: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut words: Vec<String> = words.into_iter().map(String::from).collect();
+        words.sort_unstable();
+        words.dedup();
+        
+        let encoded: Vec<(String, u32)> = words
+            .par_iter()
+            .enumerate()
+            .flat_map_iter(|(id, word)| encoder.keys(word).into_iter().map(move |key| (key, id as u32)))
+            .collect();
+        
+        let mut keys: HashMap<String, Vec<u32>> = HashMap::new();
+        for (key, id) in encoded {
+            keys.entry(key).or_default().push(id);
+        }
+        
+        Self { encoder, words, keys }
+    }
+    
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+    
+    /// Indexed words that sound like `word`, alphabetically
+    pub fn lookup(&self, word: &str) -> Vec<&str> {
+        let mut ids: Vec<u32> = self.encoder
+            .keys(word)
+            .iter()
+            .filter_map(|key| self.keys.get(key))
+            .flatten()
+            .copied()
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        
+        ids.into_iter().map(|id| self.words[id as usize].as_str()).collect()
+    }
+}
+
+// A spelling-to-sound rule: `pattern` becomes `output` when the next letter
+// is one of `next` ("" for any letter, "$" for the end of the word). Only
+// the pattern is consumed.
+struct SoundRule {
+    pattern: &'static str,
+    next: &'static str,
+    output: &'static str,
+}
+
+const fn rule(pattern: &'static str, next: &'static str, output: &'static str) -> SoundRule {
+    SoundRule { pattern, next, output }
+}
+
+// Longer patterns come first so "sj" wins over "s"
+const AFRIKAANS_RULES: &[SoundRule] = &[
+    rule("sch", "", "SK"),
+    rule("sj", "", "X"),
+    rule("sh", "", "X"),
+    rule("tj", "", "X"),
+    rule("ch", "", "X"),
+    rule("dt", "", "T"),
+    rule("ph", "", "F"),
+    rule("th", "", "T"),
+    rule("ck", "", "K"),
+    rule("qu", "", "KV"),
+    rule("ng", "", "N"),
+    rule("c", "eiy", "S"),
+    rule("c", "", "K"),
+    rule("q", "", "K"),
+    rule("x", "", "KS"),
+    rule("z", "", "S"),
+    rule("v", "", "F"),
+    rule("w", "", "V"),
+    // Final devoicing: "hond" and "hont" sound the same
+    rule("d", "$", "T"),
+    rule("b", "$", "P"),
+];
+
+const FRENCH_RULES: &[SoundRule] = &[
+    rule("sch", "", "X"),
+    rule("ill", "", "Y"),
+    rule("ch", "", "X"),
+    rule("sh", "", "X"),
+    rule("ph", "", "F"),
+    rule("th", "", "T"),
+    rule("gn", "", "N"),
+    rule("gu", "eiy", "G"),
+    rule("qu", "", "K"),
+    rule("ck", "", "K"),
+    rule("cc", "eiy", "KS"),
+    rule("ç", "", "S"),
+    rule("c", "eiy", "S"),
+    rule("c", "", "K"),
+    rule("g", "eiy", "J"),
+    rule("q", "", "K"),
+    rule("x", "", "KS"),
+    rule("z", "", "S"),
+    rule("w", "", "V"),
+    rule("h", "", ""),
+];
+
+// Written but not pronounced at the end of a French word, stripped in order
+const FRENCH_SILENT_ENDINGS: &[&[char]] = &[&['s', 'x'], &['e'], &['t', 'd', 'p', 'z']];
+
+// Apply `rules` left to right. Vowels only count before the first sound and
+// repeated sounds collapse, so "hondjie" and "honjie" share a key.
+fn encode_with_rules(word: &str, rules: &[SoundRule], silent_endings: &[&[char]]) -> String {
+    let mut word: String = word.to_lowercase().chars().map(fold_vowel).collect();
+    for ending in silent_endings {
+        if word.chars().count() > 3 {
+            if let Some(stripped) = word.strip_suffix(*ending) {
+                word.truncate(stripped.len());
+            }
+        }
+    }
+    
+    let mut key = String::new();
+    let mut rest = word.as_str();
+    
+    while let Some(c) = rest.chars().next() {
+        let matched = rules.iter().find(|rule| {
+            rest.strip_prefix(rule.pattern).is_some_and(|after| match (rule.next, after.chars().next()) {
+                ("", _) => true,
+                ("$", next) => next.is_none(),
+                (next_set, Some(next)) => next_set.contains(next),
+                (_, None) => false,
+            })
+        });
+        
+        if let Some(rule) = matched {
+            key.push_str(rule.output);
+            rest = &rest[rule.pattern.len()..];
+        } else {
+            if "aeiouy".contains(c) {
+                if key.is_empty() {
+                    key.push('A');
+                }
+            } else if c.is_alphabetic() {
+                key.extend(c.to_uppercase());
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    
+    let mut collapsed = String::with_capacity(key.len());
+    for c in key.chars() {
+        if !collapsed.ends_with(c) {
+            collapsed.push(c);
+        }
+    }
+    collapsed.chars().take(MAX_KEY_LENGTH).collect()
+}
+
+fn fold_vowel(c: char) -> char {
+    match c {
+        'á' | 'à' | 'â' | 'ä' => 'a',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'í' | 'ì' | 'î' | 'ï' => 'i',
+        'ó' | 'ò' | 'ô' | 'ö' => 'o',
+        'ú' | 'ù' | 'û' | 'ü' => 'u',
+        'ÿ' => 'y',
+        _ => c,
+    }
+}
+
+/// Primary and alternate Double Metaphone keys (Lawrence Philips' algorithm)
+pub fn double_metaphone(word: &str) -> (String, String) {
+    let chars: Vec<char> = word.trim().to_uppercase().chars().collect();
+    let mut encoder = DoubleMetaphone {
+        slavo_germanic: is_slavo_germanic(&chars),
+        chars,
+        primary: String::new(),
+        alternate: String::new(),
+    };
+    encoder.encode();
+    
+    let DoubleMetaphone { mut primary, mut alternate, .. } = encoder;
+    primary.truncate(MAX_KEY_LENGTH);
+    alternate.truncate(MAX_KEY_LENGTH);
+    (primary, alternate)
+}
+
+fn is_slavo_germanic(chars: &[char]) -> bool {
+    let word: String = chars.iter().collect();
+    word.contains('W') || word.contains('K') || word.contains("CZ") || word.contains("WITZ")
+}
+
+const SILENT_START: &[&str] = &["GN", "KN", "PN", "WR", "PS"];
+const L_R_N_M_B_H_F_V_W_SPACE: &[&str] = &["L", "R", "N", "M", "B", "H", "F", "V", "W", " "];
+const ES_EP_EB_EL_EY_IB_IL_IN_IE_EI_ER: &[&str] = &["ES", "EP", "EB", "EL", "EY", "IB", "IL", "IN", "IE", "EI", "ER"];
+const L_T_K_S_N_M_B_Z: &[&str] = &["L", "T", "K", "S", "N", "M", "B", "Z"];
+
+struct DoubleMetaphone {
+    chars: Vec<char>,
+    slavo_germanic: bool,
+    primary: String,
+    alternate: String,
+}
+
+impl DoubleMetaphone {
+    fn encode(&mut self) {
+        let mut index = 0;
+        if self.contains(0, 2, SILENT_START) {
+            index = 1;
+        }
+        
+        while index < self.chars.len()
+            && (self.primary.len() < MAX_KEY_LENGTH || self.alternate.len() < MAX_KEY_LENGTH)
+        {
+            index = match self.chars[index] {
+                'A' | 'E' | 'I' | 'O' | 'U' | 'Y' => {
+                    if index == 0 {
+                        self.add("A");
+                    }
+                    index + 1
+                }
+                'B' => {
+                    self.add("P");
+                    if self.at(index + 1) == 'B' { index + 2 } else { index + 1 }
+                }
+                'Ç' => {
+                    self.add("S");
+                    index + 1
+                }
+                'C' => self.handle_c(index),
+                'D' => self.handle_d(index),
+                'F' => {
+                    self.add("F");
+                    if self.at(index + 1) == 'F' { index + 2 } else { index + 1 }
+                }
+                'G' => self.handle_g(index),
+                'H' => self.handle_h(index),
+                'J' => self.handle_j(index),
+                'K' => {
+                    self.add("K");
+                    if self.at(index + 1) == 'K' { index + 2 } else { index + 1 }
+                }
+                'L' => self.handle_l(index),
+                'M' => {
+                    self.add("M");
+                    if self.condition_m0(index) { index + 2 } else { index + 1 }
+                }
+                'N' => {
+                    self.add("N");
+                    if self.at(index + 1) == 'N' { index + 2 } else { index + 1 }
+                }
+                'Ñ' => {
+                    self.add("N");
+                    index + 1
+                }
+                'P' => self.handle_p(index),
+                'Q' => {
+                    self.add("K");
+                    if self.at(index + 1) == 'Q' { index + 2 } else { index + 1 }
+                }
+                'R' => self.handle_r(index),
+                'S' => self.handle_s(index),
+                'T' => self.handle_t(index),
+                'V' => {
+                    self.add("F");
+                    if self.at(index + 1) == 'V' { index + 2 } else { index + 1 }
+                }
+                'W' => self.handle_w(index),
+                'X' => self.handle_x(index),
+                'Z' => self.handle_z(index),
+                _ => index + 1,
+            };
+        }
+    }
+    
+    fn handle_c(&mut self, index: usize) -> usize {
+        if self.condition_c0(index) {
+            self.add("K");
+            index + 2
+        } else if index == 0 && self.contains(index, 6, &["CAESAR"]) {
+            self.add("S");
+            index + 2
+        } else if self.contains(index, 2, &["CH"]) {
+            self.handle_ch(index)
+        } else if self.contains(index, 2, &["CZ"]) && !self.contains_before(index, 2, 4, &["WICZ"]) {
+            self.add_both("S", "X");
+            index + 2
+        } else if self.contains(index + 1, 3, &["CIA"]) {
+            self.add("X");
+            index + 3
+        } else if self.contains(index, 2, &["CC"]) && !(index == 1 && self.at(0) == 'M') {
+            self.handle_cc(index)
+        } else if self.contains(index, 2, &["CK", "CG", "CQ"]) {
+            self.add("K");
+            index + 2
+        } else if self.contains(index, 2, &["CI", "CE", "CY"]) {
+            if self.contains(index, 3, &["CIO", "CIE", "CIA"]) {
+                self.add_both("S", "X");
+            } else {
+                self.add("S");
+            }
+            index + 2
+        } else {
+            self.add("K");
+            if self.contains(index + 1, 2, &[" C", " Q", " G"]) {
+                index + 3
+            } else if self.contains(index + 1, 1, &["C", "K", "Q"]) && !self.contains(index + 1, 2, &["CE", "CI"]) {
+                index + 2
+            } else {
+                index + 1
+            }
+        }
+    }
+    
+    fn handle_cc(&mut self, index: usize) -> usize {
+        if self.contains(index + 2, 1, &["I", "E", "H"]) && !self.contains(index + 2, 2, &["HU"]) {
+            if (index == 1 && self.at(index - 1) == 'A') || self.contains_before(index, 1, 5, &["UCCEE", "UCCES"]) {
+                self.add("KS");
+            } else {
+                self.add("X");
+            }
+            index + 3
+        } else {
+            self.add("K");
+            index + 2
+        }
+    }
+    
+    fn handle_ch(&mut self, index: usize) -> usize {
+        if index > 0 && self.contains(index, 4, &["CHAE"]) {
+            self.add_both("K", "X");
+        } else if self.condition_ch0(index) || self.condition_ch1(index) {
+            self.add("K");
+        } else if index > 0 {
+            if self.contains(0, 2, &["MC"]) {
+                self.add("K");
+            } else {
+                self.add_both("X", "K");
+            }
+        } else {
+            self.add("X");
+        }
+        index + 2
+    }
+    
+    fn handle_d(&mut self, index: usize) -> usize {
+        if self.contains(index, 2, &["DG"]) {
+            if self.contains(index + 2, 1, &["I", "E", "Y"]) {
+                self.add("J");
+                index + 3
+            } else {
+                self.add("TK");
+                index + 2
+            }
+        } else if self.contains(index, 2, &["DT", "DD"]) {
+            self.add("T");
+            index + 2
+        } else {
+            self.add("T");
+            index + 1
+        }
+    }
+    
+    fn handle_g(&mut self, index: usize) -> usize {
+        let next = self.at(index + 1);
+        if next == 'H' {
+            self.handle_gh(index)
+        } else if next == 'N' {
+            if index == 1 && is_vowel(self.at(0)) && !self.slavo_germanic {
+                self.add_both("KN", "N");
+            } else if !self.contains(index + 2, 2, &["EY"]) && next != 'Y' && !self.slavo_germanic {
+                self.add_both("N", "KN");
+            } else {
+                self.add("KN");
+            }
+            index + 2
+        } else if self.contains(index + 1, 2, &["LI"]) && !self.slavo_germanic {
+            self.add_both("KL", "L");
+            index + 2
+        } else if (index == 0 && (next == 'Y' || self.contains(index + 1, 2, ES_EP_EB_EL_EY_IB_IL_IN_IE_EI_ER)))
+            || ((self.contains(index + 1, 2, &["ER"]) || next == 'Y')
+                && !self.contains(0, 6, &["DANGER", "RANGER", "MANGER"])
+                && !self.contains_before(index, 1, 1, &["E", "I"])
+                && !self.contains_before(index, 1, 3, &["RGY", "OGY"]))
+        {
+            // Hard or soft: "gerald", "tiger"
+            self.add_both("K", "J");
+            index + 2
+        } else if self.contains(index + 1, 1, &["E", "I", "Y"]) || self.contains_before(index, 1, 4, &["AGGI", "OGGI"]) {
+            if self.contains(0, 4, &["VAN ", "VON "]) || self.contains(0, 3, &["SCH"]) || self.contains(index + 1, 2, &["ET"]) {
+                self.add("K");
+            } else if self.contains(index + 1, 3, &["IER"]) {
+                self.add("J");
+            } else {
+                self.add_both("J", "K");
+            }
+            index + 2
+        } else if next == 'G' {
+            self.add("K");
+            index + 2
+        } else {
+            self.add("K");
+            index + 1
+        }
+    }
+    
+    fn handle_gh(&mut self, index: usize) -> usize {
+        if index > 0 && !is_vowel(self.at(index - 1)) {
+            self.add("K");
+        } else if index == 0 {
+            if self.at(index + 2) == 'I' {
+                self.add("J");
+            } else {
+                self.add("K");
+            }
+        } else if (index > 1 && self.contains_before(index, 2, 1, &["B", "H", "D"]))
+            || (index > 2 && self.contains_before(index, 3, 1, &["B", "H", "D"]))
+            || (index > 3 && self.contains_before(index, 4, 1, &["B", "H"]))
+        {
+            // "bough", "daughter": silent
+        } else if index > 2 && self.at(index - 1) == 'U' && self.contains_before(index, 3, 1, &["C", "G", "L", "R", "T"]) {
+            self.add("F");
+        } else if index > 0 && self.at(index - 1) != 'I' {
+            self.add("K");
+        }
+        index + 2
+    }
+    
+    fn handle_h(&mut self, index: usize) -> usize {
+        if (index == 0 || is_vowel(self.at(index - 1))) && is_vowel(self.at(index + 1)) {
+            self.add("H");
+            index + 2
+        } else {
+            index + 1
+        }
+    }
+    
+    fn handle_j(&mut self, index: usize) -> usize {
+        if self.contains(index, 4, &["JOSE"]) || self.contains(0, 4, &["SAN "]) {
+            if (index == 0 && self.at(index + 4) == ' ') || self.chars.len() == 4 || self.contains(0, 4, &["SAN "]) {
+                self.add("H");
+            } else {
+                self.add_both("J", "H");
+            }
+            return index + 1;
+        }
+        
+        if index == 0 {
+            self.add_both("J", "A");
+        } else if is_vowel(self.at(index - 1))
+            && !self.slavo_germanic
+            && matches!(self.at(index + 1), 'A' | 'O')
+        {
+            self.add_both("J", "H");
+        } else if index == self.chars.len() - 1 {
+            // Philips leaves the alternate silent here, which would make
+            // "nollij" match every word sounded "NL"
+            self.add("J");
+        } else if !self.contains(index + 1, 1, L_T_K_S_N_M_B_Z) && !self.contains_before(index, 1, 1, &["S", "K", "L"]) {
+            self.add("J");
+        }
+        
+        if self.at(index + 1) == 'J' { index + 2 } else { index + 1 }
+    }
+    
+    fn handle_l(&mut self, index: usize) -> usize {
+        if self.at(index + 1) == 'L' {
+            if self.condition_l0(index) {
+                self.primary.push('L');
+            } else {
+                self.add("L");
+            }
+            index + 2
+        } else {
+            self.add("L");
+            index + 1
+        }
+    }
+    
+    fn handle_p(&mut self, index: usize) -> usize {
+        if self.at(index + 1) == 'H' {
+            self.add("F");
+            index + 2
+        } else {
+            self.add("P");
+            if self.contains(index + 1, 1, &["P", "B"]) { index + 2 } else { index + 1 }
+        }
+    }
+    
+    fn handle_r(&mut self, index: usize) -> usize {
+        // French "Rogier": the final R is silent in the primary key
+        if index == self.chars.len() - 1
+            && !self.slavo_germanic
+            && self.contains_before(index, 2, 2, &["IE"])
+            && !self.contains_before(index, 4, 2, &["ME", "MA"])
+        {
+            self.alternate.push('R');
+        } else {
+            self.add("R");
+        }
+        if self.at(index + 1) == 'R' { index + 2 } else { index + 1 }
+    }
+    
+    fn handle_s(&mut self, index: usize) -> usize {
+        if self.contains_before(index, 1, 3, &["ISL", "YSL"]) {
+            index + 1
+        } else if index == 0 && self.contains(index, 5, &["SUGAR"]) {
+            self.add_both("X", "S");
+            index + 1
+        } else if self.contains(index, 2, &["SH"]) {
+            if self.contains(index + 1, 4, &["HEIM", "HOEK", "HOLM", "HOLZ"]) {
+                self.add("S");
+            } else {
+                self.add("X");
+            }
+            index + 2
+        } else if self.contains(index, 3, &["SIO", "SIA"]) || self.contains(index, 4, &["SIAN"]) {
+            if self.slavo_germanic {
+                self.add("S");
+            } else {
+                self.add_both("S", "X");
+            }
+            index + 3
+        } else if (index == 0 && self.contains(index + 1, 1, &["M", "N", "L", "W"])) || self.contains(index + 1, 1, &["Z"]) {
+            self.add_both("S", "X");
+            if self.contains(index + 1, 1, &["Z"]) { index + 2 } else { index + 1 }
+        } else if self.contains(index, 2, &["SC"]) {
+            self.handle_sc(index)
+        } else {
+            if index == self.chars.len() - 1 && self.contains_before(index, 2, 2, &["AI", "OI"]) {
+                self.alternate.push('S');
+            } else {
+                self.add("S");
+            }
+            if self.contains(index + 1, 1, &["S", "Z"]) { index + 2 } else { index + 1 }
+        }
+    }
+    
+    fn handle_sc(&mut self, index: usize) -> usize {
+        if self.at(index + 2) == 'H' {
+            if self.contains(index + 3, 2, &["OO", "ER", "EN", "UY", "ED", "EM"]) {
+                if self.contains(index + 3, 2, &["ER", "EN"]) {
+                    self.add_both("X", "SK");
+                } else {
+                    self.add("SK");
+                }
+            } else if index == 0 && !is_vowel(self.at(3)) && self.at(3) != 'W' {
+                self.add_both("X", "S");
+            } else {
+                self.add("X");
+            }
+        } else if self.contains(index + 2, 1, &["I", "E", "Y"]) {
+            self.add("S");
+        } else {
+            self.add("SK");
+        }
+        index + 3
+    }
+    
+    fn handle_t(&mut self, index: usize) -> usize {
+        if self.contains(index, 4, &["TION"]) || self.contains(index, 3, &["TIA", "TCH"]) {
+            self.add("X");
+            index + 3
+        } else if self.contains(index, 2, &["TH"]) || self.contains(index, 3, &["TTH"]) {
+            if self.contains(index + 2, 2, &["OM", "AM"]) || self.contains(0, 4, &["VAN ", "VON "]) || self.contains(0, 3, &["SCH"]) {
+                self.add("T");
+            } else {
+                self.add_both("0", "T");
+            }
+            index + 2
+        } else {
+            self.add("T");
+            if self.contains(index + 1, 1, &["T", "D"]) { index + 2 } else { index + 1 }
+        }
+    }
+    
+    fn handle_w(&mut self, index: usize) -> usize {
+        if self.contains(index, 2, &["WR"]) {
+            self.add("R");
+            return index + 2;
+        }
+        
+        if index == 0 && (is_vowel(self.at(index + 1)) || self.contains(index, 2, &["WH"])) {
+            if is_vowel(self.at(index + 1)) {
+                self.add_both("A", "F");
+            } else {
+                self.add("A");
+            }
+            index + 1
+        } else if (index == self.chars.len() - 1 && index > 0 && is_vowel(self.at(index - 1)))
+            || self.contains_before(index, 1, 5, &["EWSKI", "EWSKY", "OWSKI", "OWSKY"])
+            || self.contains(0, 3, &["SCH"])
+        {
+            self.alternate.push('F');
+            index + 1
+        } else if self.contains(index, 4, &["WICZ", "WITZ"]) {
+            self.add_both("TS", "FX");
+            index + 4
+        } else {
+            index + 1
+        }
+    }
+    
+    fn handle_x(&mut self, index: usize) -> usize {
+        if index == 0 {
+            self.add("S");
+            return index + 1;
+        }
+        
+        // French "breaux": the final X is silent
+        let silent = index == self.chars.len() - 1
+            && (self.contains_before(index, 3, 3, &["IAU", "EAU"]) || self.contains_before(index, 2, 2, &["AU", "OU"]));
+        if !silent {
+            self.add("KS");
+        }
+        if self.contains(index + 1, 1, &["C", "X"]) { index + 2 } else { index + 1 }
+    }
+    
+    fn handle_z(&mut self, index: usize) -> usize {
+        if self.at(index + 1) == 'H' {
+            self.add("J");
+            return index + 2;
+        }
+        
+        if self.contains(index + 1, 2, &["ZO", "ZI", "ZA"])
+            || (self.slavo_germanic && index > 0 && self.at(index - 1) != 'T')
+        {
+            self.add_both("S", "TS");
+        } else {
+            self.add("S");
+        }
+        if self.at(index + 1) == 'Z' { index + 2 } else { index + 1 }
+    }
+    
+    // Germanic "ACH" as in "Bacher", sounded K
+    fn condition_c0(&self, index: usize) -> bool {
+        if self.contains(index, 4, &["CHIA"]) {
+            return true;
+        }
+        if index <= 1 || is_vowel(self.at(index - 2)) || !self.contains_before(index, 1, 3, &["ACH"]) {
+            return false;
+        }
+        let c = self.at(index + 2);
+        (c != 'I' && c != 'E') || self.contains_before(index, 2, 6, &["BACHER", "MACHER"])
+    }
+    
+    // Greek roots at the start of the word ("chorus", "character")
+    fn condition_ch0(&self, index: usize) -> bool {
+        index == 0
+            && (self.contains(index + 1, 5, &["HARAC", "HARIS"]) || self.contains(index + 1, 3, &["HOR", "HYM", "HIA", "HEM"]))
+            && !self.contains(0, 5, &["CHORE"])
+    }
+    
+    // Germanic and Greek "CH" sounded K ("orchestra", "architect")
+    fn condition_ch1(&self, index: usize) -> bool {
+        self.contains(0, 4, &["VAN ", "VON "])
+            || self.contains(0, 3, &["SCH"])
+            || self.contains_before(index, 2, 6, &["ORCHES", "ARCHIT", "ORCHID"])
+            || self.contains(index + 2, 1, &["T", "S"])
+            || ((self.contains_before(index, 1, 1, &["A", "O", "U", "E"]) || index == 0)
+                && (self.contains(index + 2, 1, L_R_N_M_B_H_F_V_W_SPACE) || index + 1 == self.chars.len() - 1))
+    }
+    
+    // Spanish "-illo"/"-illa" where LL is sounded Y
+    fn condition_l0(&self, index: usize) -> bool {
+        let len = self.chars.len();
+        if index + 3 == len && self.contains_before(index, 1, 4, &["ILLO", "ILLA", "ALLE"]) {
+            return true;
+        }
+        (len >= 2 && (self.contains(len - 2, 2, &["AS", "OS"]) || self.contains(len - 1, 1, &["A", "O"])))
+            && self.contains_before(index, 1, 4, &["ALLE"])
+    }
+    
+    // "dumb", "thumb": the B is silent
+    fn condition_m0(&self, index: usize) -> bool {
+        if self.at(index + 1) == 'M' {
+            return true;
+        }
+        self.contains_before(index, 1, 3, &["UMB"])
+            && (index + 2 == self.chars.len() || self.contains(index + 2, 2, &["ER"]))
+    }
+    
+    fn at(&self, index: usize) -> char {
+        self.chars.get(index).copied().unwrap_or('\0')
+    }
+    
+    // Whether the `len` characters starting at `start` equal one of `options`
+    fn contains(&self, start: usize, len: usize, options: &[&str]) -> bool {
+        let Some(slice) = self.chars.get(start..start + len) else {
+            return false;
+        };
+        options.iter().any(|option| option.chars().eq(slice.iter().copied()))
+    }
+    
+    // Like `contains`, starting `back` characters before `index`
+    fn contains_before(&self, index: usize, back: usize, len: usize, options: &[&str]) -> bool {
+        index.checked_sub(back).is_some_and(|start| self.contains(start, len, options))
+    }
+    
+    fn add(&mut self, code: &str) {
+        self.add_both(code, code);
+    }
+    
+    fn add_both(&mut self, primary: &str, alternate: &str) {
+        self.primary.push_str(primary);
+        self.alternate.push_str(alternate);
+    }
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'A' | 'E' | 'I' | 'O' | 'U' | 'Y')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn keys(language: Language, word: &str) -> Vec<String> {
+        PhoneticEncoder::for_language(language).unwrap().keys(word)
+    }
+    
+    #[test]
+    fn double_metaphone_known_answers() {
+        assert_eq!(double_metaphone("Smith"), ("SM0".to_string(), "XMT".to_string()));
+        assert_eq!(double_metaphone("Schmidt"), ("XMT".to_string(), "SMT".to_string()));
+        // "TH" before "OM" is a plain T, with no alternate "0"
+        assert_eq!(double_metaphone("Thompson"), ("TMPSN".to_string(), "TMPSN".to_string()));
+        assert_eq!(double_metaphone("knight"), ("NT".to_string(), "NT".to_string()));
+        assert_eq!(double_metaphone("night"), ("NT".to_string(), "NT".to_string()));
+    }
+    
+    #[test]
+    fn english_words_that_sound_alike_share_a_key() {
+        let index = PhoneticIndex::new(PhoneticEncoder::DoubleMetaphone, ["Schmidt", "knight", "house"]);
+        
+        assert_eq!(index.lookup("Smith"), ["Schmidt"]);
+        assert_eq!(index.lookup("night"), ["knight"]);
+        assert_eq!(keys(Language::English, "Thompson"), ["TMPSN"]);
+    }
+    
+    #[test]
+    fn afrikaans_devoicing_and_digraphs() {
+        assert_eq!(keys(Language::Afrikaans, "hond"), keys(Language::Afrikaans, "hont"));
+        assert_eq!(keys(Language::Afrikaans, "sjokolade"), keys(Language::Afrikaans, "chokolade"));
+        assert_ne!(keys(Language::Afrikaans, "hond"), keys(Language::Afrikaans, "bont"));
+    }
+    
+    #[test]
+    fn french_silent_endings_and_digraphs() {
+        let vert = keys(Language::French, "vert");
+        
+        assert_eq!(vert, ["VR"]);
+        assert_eq!(keys(Language::French, "verre"), vert);
+        assert_eq!(keys(Language::French, "vers"), vert);
+        assert_eq!(keys(Language::French, "photo"), keys(Language::French, "foto"));
+    }
+    
+    #[test]
+    fn other_languages_have_no_encoder() {
+        assert!(PhoneticEncoder::for_language(Language::German).is_none());
+    }
+}
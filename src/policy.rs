@@ -0,0 +1,177 @@
+use crate::checker::WordType;
+use crate::language::Language;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// How words of one `WordType` are treated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TypePolicy {
+    /// Check words of this type at all
+    pub check: bool,
+    /// Multiplies the confidence that an unknown word of this type is a
+    /// misspelling
+    pub weight: f32,
+    /// Used instead of `weight` in source code, when set
+    pub code_weight: Option<f32>,
+    /// Accept unknown words that look like real words (mostly letters, has
+    /// vowels, no long runs of one letter)
+    pub accept_plausible: bool,
+    /// Accept unknown words of up to this many characters; 0 turns this off
+    pub accept_up_to: usize,
+}
+
+impl Default for TypePolicy {
+    fn default() -> Self {
+        Self {
+            check: true,
+            weight: 1.0,
+            code_weight: None,
+            accept_plausible: false,
+            accept_up_to: 0,
+        }
+    }
+}
+
+/// Settings that decide which words get checked and when an unknown word is
+/// reported. Loads from JSON; missing fields keep their defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CheckPolicy {
+    /// Unknown words are only reported at or above this confidence (0 to 1)
+    pub min_confidence: f32,
+    /// Words with fewer characters are never checked
+    pub min_word_length: usize,
+    pub max_suggestions: usize,
+    /// How many edits a suggestion may be from the misspelling (at most 2)
+    pub max_suggestion_distance: usize,
+    /// Acronyms that are never checked, lowercase
+    pub known_acronyms: BTreeSet<String>,
+    /// Attributes of HTML and XML elements whose values are checked,
+    /// lowercase
+    pub markup_attributes: BTreeSet<String>,
+    /// The language the original strings of translation files (`msgid` in
+    /// gettext catalogs) are written in
+    pub source_language: Language,
+    /// JSONPath-like selectors (`$.messages`, `$..title`,
+    /// `$.items[*].label`) picking the values of data files (JSON, YAML,
+    /// TOML, Fluent, `.properties`) that are checked; empty checks them all
+    pub value_include: Vec<String>,
+    /// Selectors for values of data files that are never checked
+    pub value_exclude: Vec<String>,
+    pub normal: TypePolicy,
+    pub proper_noun: TypePolicy,
+    pub acronym: TypePolicy,
+    pub code_identifier: TypePolicy,
+    pub technical_term: TypePolicy,
+    pub number: TypePolicy,
+    pub symbol: TypePolicy,
+    pub short_word: TypePolicy,
+    pub abbreviation: TypePolicy,
+}
+
+impl Default for CheckPolicy {
+    fn default() -> Self {
+        let skip = TypePolicy {
+            check: false,
+            weight: 0.2,
+            ..TypePolicy::default()
+        };
+        
+        Self {
+            min_confidence: 0.7,
+            min_word_length: 3,
+            max_suggestions: 5,
+            max_suggestion_distance: crate::symspell::DEFAULT_MAX_DISTANCE,
+            known_acronyms: DEFAULT_ACRONYMS.iter().map(|a| a.to_string()).collect(),
+            markup_attributes: DEFAULT_MARKUP_ATTRIBUTES.iter().map(|a| a.to_string()).collect(),
+            source_language: Language::English,
+            value_include: Vec::new(),
+            value_exclude: Vec::new(),
+            normal: TypePolicy {
+                weight: 1.2,
+                ..TypePolicy::default()
+            },
+            proper_noun: TypePolicy {
+                weight: 0.6,
+                accept_plausible: true,
+                ..TypePolicy::default()
+            },
+            acronym: TypePolicy {
+                weight: 0.4,
+                accept_plausible: true,
+                ..TypePolicy::default()
+            },
+            // In code most identifiers aren't words at all; elsewhere a
+            // "snake_case" word is more likely a real mistake
+            code_identifier: TypePolicy {
+                weight: 0.8,
+                code_weight: Some(0.3),
+                ..TypePolicy::default()
+            },
+            technical_term: TypePolicy {
+                weight: 0.8,
+                ..TypePolicy::default()
+            },
+            number: skip.clone(),
+            symbol: skip.clone(),
+            short_word: skip.clone(),
+            abbreviation: skip,
+        }
+    }
+}
+
+impl CheckPolicy {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+    
+    pub fn for_type(&self, word_type: &WordType) -> &TypePolicy {
+        match word_type {
+            WordType::Normal => &self.normal,
+            WordType::ProperNoun => &self.proper_noun,
+            WordType::Acronym => &self.acronym,
+            WordType::CodeIdentifier => &self.code_identifier,
+            WordType::TechnicalTerm => &self.technical_term,
+            WordType::Number => &self.number,
+            WordType::Symbol => &self.symbol,
+            WordType::ShortWord => &self.short_word,
+            WordType::Abbreviation => &self.abbreviation,
+        }
+    }
+    
+    pub fn for_type_mut(&mut self, word_type: &WordType) -> &mut TypePolicy {
+        match word_type {
+            WordType::Normal => &mut self.normal,
+            WordType::ProperNoun => &mut self.proper_noun,
+            WordType::Acronym => &mut self.acronym,
+            WordType::CodeIdentifier => &mut self.code_identifier,
+            WordType::TechnicalTerm => &mut self.technical_term,
+            WordType::Number => &mut self.number,
+            WordType::Symbol => &mut self.symbol,
+            WordType::ShortWord => &mut self.short_word,
+            WordType::Abbreviation => &mut self.abbreviation,
+        }
+    }
+    
+    /// Check words of the given types and skip every other type
+    pub fn check_only(&mut self, types: &[WordType]) {
+        for word_type in WordType::ALL {
+            self.for_type_mut(&word_type).check = types.contains(&word_type);
+        }
+    }
+}
+
+const DEFAULT_ACRONYMS: &[&str] = &[
+    "api", "http", "https", "url", "uri", "html", "css", "js", "ts",
+    "json", "xml", "sql", "nosql", "cpu", "gpu", "ram", "rom", "usb",
+    "ssd", "hdd", "lan", "wan", "vpn", "dns", "ip", "tcp", "udp",
+    "ftp", "ssh", "ssl", "tls", "csv", "pdf", "doc", "jpg", "png",
+    "gif", "mp3", "mp4", "avi", "mkv", "zip", "rar", "tar", "gz",
+    "exe", "dll", "so", "dylib", "bin", "iso", "img", "vm", "aws",
+    "gcp", "azure", "ui", "ux", "cli", "gui", "ide", "sdk",
+];
+
+const DEFAULT_MARKUP_ATTRIBUTES: &[&str] = &["alt", "aria-label", "placeholder", "title"];
@@ -0,0 +1,104 @@
+use crate::prose::{lines, string_value_spans, ProseSpan};
+use crate::selector::{PathSegment, ValueFilter};
+
+/// The prose in a Java `.properties` file: the values picked by `filter`.
+/// A key is a path split at its dots, so `errors.network.title` is at
+/// `$.errors.network.title` and `$.errors` picks it too.
+///
+/// Keys, comments (`#`, `!`), escapes and MessageFormat placeholders
+/// (`{0}`) are left out; `\u00e9` escapes and the doubled quote of
+/// MessageFormat (`don''t`) are decoded. Values continue over lines ending
+/// with a backslash.
+pub fn prose_spans(text: &str, filter: &ValueFilter) -> Vec<ProseSpan> {
+    let mut spans = Vec::new();
+    // Whether the value going on from the line before is checked
+    let mut continued: Option<bool> = None;
+    
+    for (line_start, line) in lines(text) {
+        let trimmed = line.trim_start();
+        let content_start = line_start + (line.len() - trimmed.len());
+        
+        let (value_start, checked) = match continued {
+            Some(checked) => (content_start, checked),
+            None => {
+                if trimmed.is_empty() || trimmed.starts_with(['#', '!']) {
+                    continue;
+                }
+                let key_len = key_end(trimmed);
+                let path: Vec<PathSegment> = trimmed[..key_len]
+                    .split('.')
+                    .map(|key| PathSegment::Key(key.replace('\\', "")))
+                    .collect();
+                
+                // The key ends at whitespace, then maybe one '=' or ':'
+                let after = trimmed[key_len..].trim_start();
+                let after = after.strip_prefix(['=', ':']).unwrap_or(after).trim_start();
+                (line_start + line.len() - after.len(), filter.checks(&path))
+            }
+        };
+        
+        let value = &text[value_start..line_start + line.len()];
+        let continues = value.bytes().rev().take_while(|&b| b == b'\\').count() % 2 == 1;
+        let value_end = line_start + line.len() - usize::from(continues);
+        if checked {
+            spans.extend(string_value_spans(text, value_start..value_end));
+        }
+        continued = continues.then_some(checked);
+    }
+    
+    spans
+}
+
+// The length of the key at the start of `line`: up to an unescaped '=',
+// ':' or whitespace
+fn key_end(line: &str) -> usize {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'=' | b':' | b' ' | b'\t' | b'\x0c' => return i,
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn spans(text: &str, filter: &ValueFilter) -> Vec<String> {
+        prose_spans(text, filter)
+            .into_iter()
+            .map(|span| span.decoded.unwrap_or_else(|| text[span.range].to_string()))
+            .collect()
+    }
+    
+    #[test]
+    fn values_are_prose_without_keys_or_comments() {
+        let text = "# A comment\n! Another\nerrors.network.title = No connection\ngreeting: Hello {0}\n";
+        assert_eq!(spans(text, &ValueFilter::default()), ["No connection", "Hello "]);
+    }
+    
+    #[test]
+    fn values_continue_over_lines() {
+        let text = "long = First part \\\n    second part\n";
+        assert_eq!(spans(text, &ValueFilter::default()), ["First part ", "second part"]);
+    }
+    
+    #[test]
+    fn escapes_and_doubled_quotes_are_decoded() {
+        let words = spans("a = caf\\u00e9\nb = don''t\n", &ValueFilter::default()).concat();
+        assert!(words.contains('é'));
+        assert!(words.contains("don't"));
+    }
+    
+    #[test]
+    fn keys_are_split_at_dots() {
+        let text = "errors.network.title = No connection\nmenu.open = Open\n";
+        let filter = ValueFilter::new(&["$.errors".to_string()], &[]);
+        assert_eq!(spans(text, &filter), ["No connection"]);
+    }
+}
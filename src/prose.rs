@@ -0,0 +1,523 @@
+use crate::fluent;
+use crate::gettext;
+use crate::json;
+use crate::language::Language;
+use crate::latex;
+use crate::lexer::Lexer;
+use crate::markdown;
+use crate::markup;
+use crate::policy::CheckPolicy;
+use crate::properties;
+use crate::selector::ValueFilter;
+use crate::toml;
+use crate::yaml;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+/// Where a piece of prose was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ProseKind {
+    Comment,
+    /// Documentation comments and docstrings
+    DocComment,
+    StringLiteral,
+    /// Running text of a document
+    Text,
+}
+
+/// A span of a file that holds prose to check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProseSpan {
+    /// Byte range in the file
+    pub range: Range<usize>,
+    pub kind: ProseKind,
+    /// What the span stands for when that isn't its text, such as the "é"
+    /// of "&eacute;". Never contains a line break.
+    pub decoded: Option<String>,
+    /// The language the span is written in, when the file says
+    pub language: Option<Language>,
+}
+
+impl ProseSpan {
+    pub fn new(range: Range<usize>, kind: ProseKind) -> Self {
+        Self { range, kind, decoded: None, language: None }
+    }
+}
+
+// Escapes and format placeholders in translated strings: "\n", "%s",
+// "%1$d", "%(name)s", "{name}", "{0}", "${name}", "{{name}}"
+static PLACEHOLDER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\\.|%(?:\([^)]*\))?(?:\d+\$)?[-+#0]*(?:\d+|\*)?(?:\.(?:\d+|\*))?(?:hh|h|ll|l|L|q|j|z|t)?[diouxXeEfFgGaAcspn@%]|\$?\{\{?[^{}]*\}\}?").unwrap()
+});
+
+// Escapes in string values that stand for a character: "\u00e9", and the
+// doubled quote of YAML and MessageFormat ("don''t")
+static CHARACTER_ESCAPE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\\u[0-9a-fA-F]{4}|''").unwrap()
+});
+
+/// Reads the prose out of one kind of file
+#[derive(Debug, Clone, Copy)]
+pub enum FrontEnd {
+    /// Comments and strings in source code
+    Code(Lexer),
+    Markdown,
+    /// Text and chosen attributes of HTML
+    Html,
+    /// Text and chosen attributes of XML, whose resource files (Android
+    /// strings.xml) use escapes and placeholders
+    Xml,
+    /// Running text and text arguments of LaTeX
+    Latex,
+    /// Original and translated strings of gettext catalogs
+    Gettext,
+    /// String values of JSON, YAML and TOML
+    Json,
+    Yaml,
+    Toml,
+    /// Messages of Fluent (`.ftl`) and Java `.properties` files
+    Fluent,
+    Properties,
+}
+
+/// The language a file is written in, from its extension
+pub fn programming_language(filename: &str) -> Option<&'static str> {
+    match filename.rsplit('.').next() {
+        Some("rs") => Some("rust"),
+        Some("py") => Some("python"),
+        Some("js") | Some("ts") | Some("jsx") | Some("tsx") => Some("javascript"),
+        Some("java") => Some("java"),
+        Some("cpp") | Some("cc") | Some("cxx") | Some("c") | Some("h") | Some("hpp") => Some("cpp"),
+        Some("go") => Some("go"),
+        Some("rb") => Some("ruby"),
+        Some("php") => Some("php"),
+        Some("html") | Some("htm") | Some("xhtml") => Some("html"),
+        Some("css") => Some("css"),
+        Some("md") | Some("markdown") => Some("markdown"),
+        Some("json") => Some("json"),
+        Some("toml") => Some("toml"),
+        Some("yaml") | Some("yml") => Some("yaml"),
+        Some("xml") => Some("xml"),
+        Some("sh") | Some("bash") => Some("shell"),
+        Some("tex") | Some("ltx") => Some("latex"),
+        Some("po") | Some("pot") => Some("gettext"),
+        Some("ftl") => Some("fluent"),
+        Some("properties") => Some("properties"),
+        _ => None,
+    }
+}
+
+impl FrontEnd {
+    pub fn for_filename(filename: &str) -> Option<Self> {
+        match programming_language(filename)? {
+            "markdown" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            "xml" => Some(Self::Xml),
+            "latex" => Some(Self::Latex),
+            "gettext" => Some(Self::Gettext),
+            "json" => Some(Self::Json),
+            "yaml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            "fluent" => Some(Self::Fluent),
+            "properties" => Some(Self::Properties),
+            language => Lexer::for_language(language).map(Self::Code),
+        }
+    }
+    
+    /// Whether the prose sits in source code, where words may be identifiers
+    pub fn is_code(&self) -> bool {
+        matches!(self, Self::Code(_))
+    }
+    
+    /// Whether files read by this front end are translation resources,
+    /// which are named by their language ("fr.json")
+    pub fn names_language(&self) -> bool {
+        matches!(self, Self::Xml | Self::Json | Self::Yaml | Self::Toml | Self::Fluent | Self::Properties)
+    }
+    
+    /// Whether the prose of a file can be found a block of lines at a
+    /// time, so a long file needn't be read whole
+    pub fn reads_by_block(&self) -> bool {
+        matches!(self, Self::Code(_) | Self::Gettext | Self::Fluent | Self::Properties)
+    }
+    
+    /// Where the last block of `text`, whole lines read so far, ends:
+    /// nothing before it runs on into what comes after. 0 when there is
+    /// no such place yet.
+    pub fn settled(&self, text: &str) -> usize {
+        if let Self::Code(lexer) = self {
+            return lexer.settled(text);
+        }
+        
+        let mut settled = 0;
+        let mut previous: Option<&str> = None;
+        for (start, line) in lines(text) {
+            let ends_block = previous.is_some_and(|previous| match self {
+                // Entries are separated by blank lines
+                Self::Gettext => previous.trim().is_empty(),
+                // Anything in the first column starts an entry or a comment
+                Self::Fluent => !line.is_empty() && !line.starts_with(char::is_whitespace),
+                // Unless the line before goes on with a backslash
+                Self::Properties => previous.bytes().rev().take_while(|&b| b == b'\\').count() % 2 == 0,
+                _ => false,
+            });
+            if ends_block {
+                settled = start;
+            }
+            previous = Some(line);
+        }
+        settled
+    }
+    
+    /// The prose spans of `text`, sorted and not overlapping
+    pub fn spans(&self, text: &str, policy: &CheckPolicy) -> Vec<ProseSpan> {
+        let filter = || ValueFilter::new(&policy.value_include, &policy.value_exclude);
+        match self {
+            Self::Code(lexer) => lexer.prose_spans(text),
+            Self::Markdown => markdown::prose_spans(text),
+            Self::Html => markup::prose_spans(text, &policy.markup_attributes, false),
+            Self::Xml => markup::prose_spans(text, &policy.markup_attributes, true),
+            Self::Latex => latex::prose_spans(text),
+            Self::Gettext => gettext::prose_spans(text, policy.source_language),
+            Self::Json => json::prose_spans(text, &filter()),
+            Self::Yaml => yaml::prose_spans(text, &filter()),
+            Self::Toml => toml::prose_spans(text, &filter()),
+            Self::Fluent => fluent::prose_spans(text, &filter()),
+            Self::Properties => properties::prose_spans(text, &filter()),
+        }
+    }
+    
+    /// The prose of `text`, with spans that don't name their language
+    /// taken to be in `language`
+    pub fn extract(&self, text: &str, policy: &CheckPolicy, language: Option<Language>) -> Prose {
+        Prose::in_language(text, self.spans(text, policy), language)
+    }
+}
+
+/// Whether files named `filename` have their prose extracted before checking
+pub fn has_front_end(filename: &str) -> bool {
+    FrontEnd::for_filename(filename).is_some()
+}
+
+/// The prose of a file, ready to check: everything outside its spans is
+/// replaced by whitespace and decoded spans by what they stand for.
+///
+/// Line breaks are kept, so a line of `text` is the same line of the file.
+/// So are byte offsets until the first decoded span; `to_original` maps an
+/// offset back to the file.
+#[derive(Debug, Clone)]
+pub struct Prose {
+    pub text: String,
+    // (range in `text`, range in the file) of every decoded span whose
+    // length changed, in order
+    replacements: Vec<(Range<usize>, Range<usize>)>,
+    /// Ranges of `text` whose spans named their language
+    pub languages: Vec<(Range<usize>, Language)>,
+}
+
+impl Prose {
+    /// The prose of `text` given its `spans` (sorted, not overlapping)
+    pub fn new(text: &str, spans: &[ProseSpan]) -> Self {
+        let mut result = String::with_capacity(text.len());
+        let mut replacements = Vec::new();
+        let mut languages: Vec<(Range<usize>, Language)> = Vec::new();
+        let mut previous_language = None;
+        let mut pos = 0;
+        
+        for span in spans {
+            blank_into(&mut result, &text[pos..span.range.start]);
+            
+            let start = result.len();
+            match &span.decoded {
+                Some(decoded) => {
+                    result.push_str(decoded);
+                    if decoded.len() != span.range.len() {
+                        replacements.push((start..result.len(), span.range.clone()));
+                    }
+                }
+                None => result.push_str(&text[span.range.clone()]),
+            }
+            
+            // Neighbouring spans in one language share a range
+            if let Some(language) = span.language {
+                match languages.last_mut() {
+                    Some((range, last)) if previous_language == Some(language) && *last == language => {
+                        range.end = result.len();
+                    }
+                    _ => languages.push((start..result.len(), language)),
+                }
+            }
+            previous_language = span.language;
+            pos = span.range.end;
+        }
+        blank_into(&mut result, &text[pos..]);
+        
+        Self { text: result, replacements, languages }
+    }
+    
+    /// The prose of `text` given its `spans`, with spans that don't name
+    /// their language taken to be in `language`
+    pub fn in_language(text: &str, mut spans: Vec<ProseSpan>, language: Option<Language>) -> Self {
+        if language.is_some() {
+            for span in spans.iter_mut().filter(|span| span.language.is_none()) {
+                span.language = language;
+            }
+        }
+        Self::new(text, &spans)
+    }
+    
+    /// Whether every offset in `text` is the same offset in the file
+    pub fn keeps_offsets(&self) -> bool {
+        self.replacements.is_empty()
+    }
+    
+    /// The offset in the file of `offset` in `text`. An offset inside a
+    /// decoded span maps to the start of the span.
+    pub fn to_original(&self, offset: usize) -> usize {
+        // The last replacement starting before `offset`
+        let i = self.replacements.partition_point(|(decoded, _)| decoded.start < offset);
+        match i.checked_sub(1).map(|i| &self.replacements[i]) {
+            None => offset,
+            Some((decoded, original)) if offset < decoded.end => original.start,
+            Some((decoded, original)) => original.end + (offset - decoded.end),
+        }
+    }
+    
+    /// The offset in `text` of `offset` in the file; the inverse of
+    /// `to_original`
+    pub fn to_prose(&self, offset: usize) -> usize {
+        let i = self.replacements.partition_point(|(_, original)| original.start < offset);
+        match i.checked_sub(1).map(|i| &self.replacements[i]) {
+            None => offset,
+            Some((decoded, original)) if offset < original.end => decoded.start,
+            Some((decoded, original)) => decoded.end + (offset - original.end),
+        }
+    }
+    
+    /// A hash of each line's prose and the languages marked in it, to tell
+    /// which lines an edit changed
+    pub fn line_hashes(&self) -> Vec<u64> {
+        lines(&self.text)
+            .map(|(start, line)| {
+                let end = start + line.len();
+                let mut hasher = DefaultHasher::new();
+                line.hash(&mut hasher);
+                let first = self.languages.partition_point(|(range, _)| range.end <= start);
+                for (range, language) in self.languages[first..].iter().take_while(|(range, _)| range.start < end) {
+                    (range.start.max(start) - start, range.end.min(end) - start, language).hash(&mut hasher);
+                }
+                hasher.finish()
+            })
+            .collect()
+    }
+}
+
+// Push whitespace taking up as many bytes as `text`, keeping its line breaks
+fn blank_into(result: &mut String, text: &str) {
+    for c in text.chars() {
+        if c == '\n' || c == '\r' {
+            result.push(c);
+        } else {
+            result.push_str(blank(c.len_utf8()));
+        }
+    }
+}
+
+// Whitespace taking up `len` bytes, so offsets stay the same. Only a 4-byte
+// character (an emoji in code) becomes two characters.
+fn blank(len: usize) -> &'static str {
+    match len {
+        1 => " ",
+        2 => "\u{A0}",
+        3 => "\u{2003}",
+        _ => "\u{A0}\u{A0}",
+    }
+}
+
+// (byte offset, content) of every line, without its line break
+pub(crate) fn lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut offset = 0;
+    text.split('\n').map(move |line| {
+        let start = offset;
+        offset += line.len() + 1;
+        (start, line.strip_suffix('\r').unwrap_or(line))
+    })
+}
+
+/// The parts of `range` in `text` left after taking out escapes ("\n")
+/// and format placeholders ("%s", "%1$d", "{name}", "${count}")
+pub fn without_placeholders(text: &str, range: Range<usize>) -> Vec<Range<usize>> {
+    let mut parts = Vec::new();
+    let mut start = range.start;
+    for placeholder in PLACEHOLDER_REGEX.find_iter(&text[range.clone()]) {
+        let at = range.start + placeholder.start();
+        if at > start {
+            parts.push(start..at);
+        }
+        start = range.start + placeholder.end();
+    }
+    if start < range.end {
+        parts.push(start..range.end);
+    }
+    parts
+}
+
+/// Prose spans for the string value at `range` in `text`: nothing for a
+/// value that looks like a key, path or URL; otherwise the value without
+/// its placeholders, with "\u00e9" escapes decoded
+pub fn string_value_spans(text: &str, range: Range<usize>) -> Vec<ProseSpan> {
+    let value = &text[range.clone()];
+    let single_token = !value.trim().contains(char::is_whitespace);
+    if value.contains("://") || (single_token && value.contains(['/', '_', '@'])) {
+        return Vec::new();
+    }
+    
+    let mut spans = Vec::new();
+    let push_text = |spans: &mut Vec<ProseSpan>, part: Range<usize>| {
+        for piece in without_placeholders(text, part) {
+            if !text[piece.clone()].trim().is_empty() {
+                spans.push(ProseSpan::new(piece, ProseKind::Text));
+            }
+        }
+    };
+    
+    let mut start = range.start;
+    for escape in CHARACTER_ESCAPE_REGEX.find_iter(value) {
+        let at = range.start + escape.start();
+        push_text(&mut spans, start..at);
+        
+        let decoded = match escape.as_str().strip_prefix("\\u") {
+            Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+            None => Some('\''),
+        };
+        if let Some(c) = decoded.filter(|c| !c.is_control()) {
+            spans.push(ProseSpan {
+                decoded: Some(c.to_string()),
+                ..ProseSpan::new(at..range.start + escape.end(), ProseKind::Text)
+            });
+        }
+        start = range.start + escape.end();
+    }
+    push_text(&mut spans, start..range.end);
+    
+    spans
+}
+
+/// The language a file is written in, going by how translation files are
+/// usually named: "fr.json", "messages_af.properties", "pt-BR.yaml",
+/// "values-fr/strings.xml", "locales/de/common.json"
+pub fn filename_language(path: &str) -> Option<Language> {
+    let mut components = path.rsplit(['/', '\\']);
+    let stem = components.next()?.split('.').next()?;
+    
+    // The whole stem, or what follows the base name ("messages_pt_BR")
+    if let Some(language) = locale(stem) {
+        return Some(language);
+    }
+    if let Some(language) = stem.match_indices(['_', '-']).find_map(|(i, _)| locale(&stem[i + 1..])) {
+        return Some(language);
+    }
+    
+    // The nearest directories, with Android's "values-" prefix
+    components
+        .take(2)
+        .find_map(|directory| locale(directory.strip_prefix("values-").unwrap_or(directory)))
+}
+
+// A lowercase language code of two or three letters, maybe with a script
+// and region: "fr", "afr", "fr-CA", "pt_BR", "zh-Hant", "es-419", and
+// Android's "pt-rBR". Anything else after the code ("it-security") is not
+// a locale.
+fn locale(tag: &str) -> Option<Language> {
+    let mut parts = tag.split(['_', '-']);
+    let code = parts.next()?;
+    if !(2..=3).contains(&code.len()) || !code.chars().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    if !parts.all(is_script_or_region) {
+        return None;
+    }
+    Language::from_tag(tag)
+}
+
+// "Hant", "BR", "419" or "rBR"
+fn is_script_or_region(part: &str) -> bool {
+    let region = part.strip_prefix('r').filter(|region| region.len() == 2).unwrap_or(part);
+    match region.len() {
+        2 => region.chars().all(|c| c.is_ascii_uppercase()),
+        3 => region.chars().all(|c| c.is_ascii_digit()),
+        4 => {
+            region.starts_with(|c: char| c.is_ascii_uppercase())
+                && region[1..].chars().all(|c| c.is_ascii_lowercase())
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn language_from_translation_file_names() {
+        assert_eq!(filename_language("fr.json"), Some(Language::French));
+        assert_eq!(filename_language("i18n/messages_af.properties"), Some(Language::Afrikaans));
+        assert_eq!(filename_language("messages_pt_BR.properties"), Some(Language::Portuguese));
+        assert_eq!(filename_language("res/values-fr/strings.xml"), Some(Language::French));
+        assert_eq!(filename_language("res/values-pt-rBR/strings.xml"), Some(Language::Portuguese));
+        assert_eq!(filename_language("locales/de/common.json"), Some(Language::German));
+    }
+    
+    #[test]
+    fn words_that_start_like_language_codes() {
+        assert_eq!(filename_language("it-security.md"), None);
+        assert_eq!(filename_language("de-duplication.md"), None);
+        assert_eq!(filename_language("es-modules.json"), None);
+        assert_eq!(filename_language("src/config.json"), None);
+    }
+    
+    #[test]
+    fn offsets_map_both_ways_around_decoded_spans() {
+        let text = "a &eacute; b";
+        let span = |range: Range<usize>, decoded: Option<&str>| ProseSpan {
+            decoded: decoded.map(str::to_string),
+            ..ProseSpan::new(range, ProseKind::Text)
+        };
+        let prose = Prose::new(text, &[span(0..2, None), span(2..10, Some("\u{e9}")), span(10..12, None)]);
+        assert_eq!(prose.text, "a \u{e9} b");
+        assert_eq!(prose.to_original(5), 11);
+        assert_eq!(prose.to_prose(11), 5);
+        assert_eq!(prose.to_prose(4), 2);
+        assert_eq!(prose.to_prose(1), 1);
+    }
+    
+    #[test]
+    fn line_hashes_change_only_with_the_prose() {
+        let hashes = |text: &str| FrontEnd::Code(Lexer::for_language("rust").unwrap()).extract(text, &CheckPolicy::default(), None).line_hashes();
+        let before = hashes("let a = 1; // one\nlet b = 2;\n// two\n");
+        let after = hashes("let a = 10; // one\nlet b = 2;\n// three\n");
+        assert_eq!(before[1], after[1]);
+        assert_ne!(before[2], after[2]);
+    }
+    
+    #[test]
+    fn blocks_end_where_nothing_runs_on() {
+        let po = "msgid \"One\"\nmsgstr \"Un\"\n\nmsgid \"Two\"\n";
+        assert_eq!(FrontEnd::Gettext.settled(po), po.find("msgid \"Two").unwrap());
+        
+        let properties = "a = one \\\n    two\nb = three\n";
+        assert_eq!(FrontEnd::Properties.settled(properties), properties.len());
+        assert_eq!(FrontEnd::Properties.settled("a = one \\\n"), 0);
+        
+        let ftl = "hello = Hello\n    .title = Hi\nbye = Bye\n";
+        assert_eq!(FrontEnd::Fluent.settled(ftl), ftl.find("bye").unwrap());
+        
+        let rust = FrontEnd::for_filename("a.rs").unwrap();
+        let code = "// done\nlet s = 1;\n/* still\nopen\n";
+        assert_eq!(rust.settled(code), code.find("/*").unwrap());
+    }
+}
@@ -0,0 +1,151 @@
+use std::ops::Range;
+
+// Longest dictionary word tried at each position, in characters
+const MAX_WORD_CHARS: usize = 16;
+
+/// One piece of a run of text written without spaces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// Byte range in the segmented text
+    pub range: Range<usize>,
+    /// Whether the piece is a word accepted by the dictionary
+    pub known: bool,
+}
+
+// Best way found to reach a char boundary: the fewest characters left
+// outside dictionary words, then the fewest pieces
+#[derive(Debug, Clone, Copy)]
+struct Path {
+    unknown_chars: usize,
+    pieces: usize,
+    from: usize,
+    known: bool,
+}
+
+impl Path {
+    fn cost(&self) -> (usize, usize) {
+        (self.unknown_chars, self.pieces)
+    }
+}
+
+/// Split `text`, written without spaces (Chinese, Japanese), into words
+/// accepted by `is_word`.
+///
+/// Every way of splitting the text is scored as a lattice and the one that
+/// leaves the fewest characters outside dictionary words wins; ties go to
+/// the split with the fewest (so longest) words. Neighbouring characters no
+/// word covers are kept together as one unknown segment.
+pub fn segment(text: &str, is_word: impl Fn(&str) -> bool) -> Vec<Segment> {
+    let boundaries: Vec<usize> = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .collect();
+    let char_count = boundaries.len() - 1;
+    
+    let mut best: Vec<Option<Path>> = vec![None; char_count + 1];
+    best[0] = Some(Path { unknown_chars: 0, pieces: 0, from: 0, known: true });
+    
+    for start in 0..char_count {
+        let Some(here) = best[start] else {
+            continue;
+        };
+        
+        let mut relax = |end: usize, known: bool| {
+            let path = Path {
+                unknown_chars: here.unknown_chars + if known { 0 } else { end - start },
+                pieces: here.pieces + 1,
+                from: start,
+                known,
+            };
+            if best[end].is_none_or(|current| path.cost() < current.cost()) {
+                best[end] = Some(path);
+            }
+        };
+        
+        // A character no word covers can always be passed over
+        relax(start + 1, false);
+        
+        for end in start + 1..=char_count.min(start + MAX_WORD_CHARS) {
+            if is_word(&text[boundaries[start]..boundaries[end]]) {
+                relax(end, true);
+            }
+        }
+    }
+    
+    // Walk back from the end, merging neighbouring unknown characters
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut end = char_count;
+    while end > 0 {
+        let Some(path) = best[end] else {
+            break;
+        };
+        
+        let range = boundaries[path.from]..boundaries[end];
+        match segments.last_mut() {
+            Some(next) if !path.known && !next.known => next.range.start = range.start,
+            _ => segments.push(Segment { range, known: path.known }),
+        }
+        end = path.from;
+    }
+    
+    segments.reverse();
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // The pieces of `text` with unknown ones in brackets
+    fn split(text: &str, words: &[&str]) -> Vec<String> {
+        segment(text, |part| words.contains(&part))
+            .into_iter()
+            .map(|segment| {
+                let piece = &text[segment.range];
+                if segment.known { piece.to_string() } else { format!("[{}]", piece) }
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn chinese_run() {
+        let words = ["我", "喜欢", "学习", "中文", "中华", "人民", "共和国", "中华人民共和国"];
+        
+        assert_eq!(split("我喜欢学习中文", &words), ["我", "喜欢", "学习", "中文"]);
+        // Fewest pieces wins when every split covers the run
+        assert_eq!(split("中华人民共和国", &words), ["中华人民共和国"]);
+    }
+    
+    #[test]
+    fn japanese_kana_and_kanji_run() {
+        let words = ["私", "は", "東京", "に", "行きます", "カタカナ"];
+        
+        assert_eq!(split("私は東京に行きます", &words), ["私", "は", "東京", "に", "行きます"]);
+        assert_eq!(split("カタカナは", &words), ["カタカナ", "は"]);
+    }
+    
+    #[test]
+    fn characters_outside_the_dictionary() {
+        let words = ["我", "喜欢", "学习"];
+        
+        // Neighbouring unknown characters form one segment
+        assert_eq!(split("我喜欢龘靐学习", &words), ["我", "喜欢", "[龘靐]", "学习"]);
+        assert_eq!(split("龘我", &words), ["[龘]", "我"]);
+        assert_eq!(split("龘靐", &words), ["[龘靐]"]);
+        assert!(split("", &words).is_empty());
+        
+        // Covering every character beats starting with the longer word
+        assert_eq!(split("喜欢学", &["喜", "欢学", "喜欢"]), ["喜", "欢学"]);
+    }
+    
+    #[test]
+    fn ranges_are_byte_offsets() {
+        let segments = segment("我喜欢", |part| part == "喜欢");
+        
+        assert_eq!(
+            segments,
+            [Segment { range: 0..3, known: false }, Segment { range: 3..9, known: true }]
+        );
+    }
+}
@@ -0,0 +1,188 @@
+/// One step from a value of a data file to a value inside it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+// One step of a selector
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Key(String),
+    Index(usize),
+    /// `*` and `[*]`
+    Any,
+    /// `..`: any number of steps, none included
+    Descendants,
+}
+
+impl Step {
+    fn matches(&self, segment: &PathSegment) -> bool {
+        match (self, segment) {
+            (Step::Any, _) => true,
+            (Step::Key(key), PathSegment::Key(name)) => key == name,
+            (Step::Index(index), PathSegment::Index(i)) => index == i,
+            _ => false,
+        }
+    }
+}
+
+/// A JSONPath-like selector: `$.messages`, `$.errors.*`, `$..title`,
+/// `$.items[*].label`, `$['key with spaces']`. The leading `$` is optional.
+///
+/// A selector picks the values it names and everything inside them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    steps: Vec<Step>,
+}
+
+impl Selector {
+    /// Read a selector. Anything that isn't selector syntax is taken as
+    /// part of a key, so this never fails.
+    pub fn parse(selector: &str) -> Self {
+        let mut steps = Vec::new();
+        let rest = selector.trim();
+        let mut rest = rest.strip_prefix('$').unwrap_or(rest);
+        
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("..") {
+                steps.push(Step::Descendants);
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix('.') {
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = bracket_end(after);
+                let inner = after[..end].trim();
+                let quoted = inner
+                    .strip_prefix('\'')
+                    .and_then(|s| s.strip_suffix('\''))
+                    .or_else(|| inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+                steps.push(match quoted {
+                    Some(key) => Step::Key(key.to_string()),
+                    None if inner == "*" => Step::Any,
+                    None => match inner.parse() {
+                        Ok(index) => Step::Index(index),
+                        Err(_) => Step::Key(inner.to_string()),
+                    },
+                });
+                rest = after.get(end + 1..).unwrap_or_default();
+            } else {
+                let end = rest.find(['.', '[']).unwrap_or(rest.len());
+                let key = &rest[..end];
+                steps.push(if key == "*" { Step::Any } else { Step::Key(key.to_string()) });
+                rest = &rest[end..];
+            }
+        }
+        
+        Self { steps }
+    }
+    
+    /// Whether the value at `path` is selected, by itself or as part of a
+    /// value that is
+    pub fn selects(&self, path: &[PathSegment]) -> bool {
+        selects(&self.steps, path)
+    }
+}
+
+// Whether `steps` pick `path` or one of its ancestors
+fn selects(steps: &[Step], path: &[PathSegment]) -> bool {
+    match steps.split_first() {
+        None => true,
+        Some((Step::Descendants, rest)) => (0..=path.len()).any(|skip| selects(rest, &path[skip..])),
+        Some((step, rest)) => match path.split_first() {
+            Some((segment, path_rest)) => step.matches(segment) && selects(rest, path_rest),
+            None => false,
+        },
+    }
+}
+
+// The position of the ']' closing a bracket, skipping quoted keys
+fn bracket_end(after: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in after.char_indices() {
+        match (quote, c) {
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, ']') => return i,
+            _ => {}
+        }
+    }
+    after.len()
+}
+
+/// Which values of a data file are checked: those picked by an include
+/// selector (every value when there are none) and by no exclude selector
+#[derive(Debug, Clone, Default)]
+pub struct ValueFilter {
+    include: Vec<Selector>,
+    exclude: Vec<Selector>,
+}
+
+impl ValueFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Self {
+        Self {
+            include: include.iter().map(|s| Selector::parse(s)).collect(),
+            exclude: exclude.iter().map(|s| Selector::parse(s)).collect(),
+        }
+    }
+    
+    pub fn checks(&self, path: &[PathSegment]) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|s| s.selects(path)))
+            && !self.exclude.iter().any(|s| s.selects(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn path(segments: &[&str]) -> Vec<PathSegment> {
+        segments
+            .iter()
+            .map(|s| match s.parse() {
+                Ok(index) => PathSegment::Index(index),
+                Err(_) => PathSegment::Key(s.to_string()),
+            })
+            .collect()
+    }
+    
+    fn selects(selector: &str, segments: &[&str]) -> bool {
+        Selector::parse(selector).selects(&path(segments))
+    }
+    
+    #[test]
+    fn keys_pick_values_and_everything_inside() {
+        assert!(selects("$.messages", &["messages"]));
+        assert!(selects("$.messages", &["messages", "hello"]));
+        assert!(selects("messages", &["messages", "hello"]));
+        assert!(!selects("$.messages", &["meta"]));
+        assert!(!selects("$.messages.hello", &["messages"]));
+    }
+    
+    #[test]
+    fn wildcards_indexes_and_descendants() {
+        assert!(selects("$.errors.*", &["errors", "network"]));
+        assert!(selects("$.items[*].label", &["items", "2", "label"]));
+        assert!(selects("$.items[0]", &["items", "0"]));
+        assert!(!selects("$.items[0]", &["items", "1"]));
+        assert!(selects("$..title", &["title"]));
+        assert!(selects("$..title", &["pages", "3", "title"]));
+        assert!(!selects("$..title", &["pages", "3", "body"]));
+    }
+    
+    #[test]
+    fn quoted_keys() {
+        assert!(selects("$['key with spaces']", &["key with spaces"]));
+        assert!(selects("$[\"a.b\"]", &["a.b"]));
+        assert!(!selects("$[\"a.b\"]", &["a", "b"]));
+    }
+    
+    #[test]
+    fn filter_includes_then_excludes() {
+        let filter = ValueFilter::new(&["$.messages".to_string()], &["$..id".to_string()]);
+        assert!(filter.checks(&path(&["messages", "hello"])));
+        assert!(!filter.checks(&path(&["messages", "id"])));
+        assert!(!filter.checks(&path(&["meta"])));
+        assert!(ValueFilter::default().checks(&path(&["anything"])));
+    }
+}
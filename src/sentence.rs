@@ -0,0 +1,182 @@
+use crate::language::Language;
+use std::collections::HashSet;
+use std::ops::Range;
+
+// Abbreviations whose trailing period doesn't end a sentence, lowercase and
+// without the final period ("e.g." is stored as "e.g")
+const COMMON_ABBREVIATIONS: &[&str] = &[
+    "dr", "prof", "vs", "ca", "cf", "approx", "nr", "vol", "pp", "eds", "jan", "feb",
+    "apr", "jun", "jul", "aug", "sep", "sept", "oct", "nov",
+];
+
+// Abbreviations that are also words, or often end a sentence ("He said
+// no.", "apples, pears etc."). Their period only goes on with the sentence
+// when a lowercase word or a digit follows: "No. 5", "St. petersburg".
+const COMMON_AMBIGUOUS: &[&str] = &["no", "st", "ed", "mar", "dec", "fig", "etc", "p"];
+
+const ENGLISH_ABBREVIATIONS: &[&str] = &["mr", "mrs", "ms", "jr", "sr", "e.g", "i.e", "dept", "gov", "mt"];
+const ENGLISH_AMBIGUOUS: &[&str] = &["inc", "ltd", "co", "corp", "est", "u.s", "u.k", "a.m", "p.m"];
+
+const AFRIKAANS_ABBREVIATIONS: &[&str] = &["mnr", "mev", "mej", "bv", "d.w.s", "dws", "nl", "t.o.v", "m.a.w", "ds"];
+const AFRIKAANS_AMBIGUOUS: &[&str] = &["me", "ens"];
+
+const FRENCH_ABBREVIATIONS: &[&str] = &["mm", "mme", "mlle", "c.-à-d", "p.ex", "av"];
+const FRENCH_AMBIGUOUS: &[&str] = &["m", "env"];
+
+const GERMAN_ABBREVIATIONS: &[&str] = &["hr", "fr", "z.b", "bzw", "d.h", "evtl", "ggf", "inkl"];
+const GERMAN_AMBIGUOUS: &[&str] = &["s", "usw", "u.a"];
+
+const OPENING_PUNCTUATION: &[char] = &['"', '\'', '“', '‘', '«', '„', '(', '[', '¿', '¡'];
+const CLOSING_PUNCTUATION: &[char] = &['"', '\'', '”', '’', '»', ')', ']'];
+const TERMINATORS: &[char] = &['!', '?', '…', '。', '！', '？'];
+
+/// Finds sentence boundaries without being fooled by abbreviations
+/// ("Dr. Smith"), decimals ("3.5 kg") or quoted speech ("He said. “Go.”")
+pub struct SentenceSegmenter {
+    abbreviations: HashSet<&'static str>,
+    ambiguous: HashSet<&'static str>,
+}
+
+impl SentenceSegmenter {
+    pub fn for_language(language: Language) -> Self {
+        let (specific, ambiguous): (&[&str], &[&str]) = match language {
+            Language::English => (ENGLISH_ABBREVIATIONS, ENGLISH_AMBIGUOUS),
+            Language::Afrikaans => (AFRIKAANS_ABBREVIATIONS, AFRIKAANS_AMBIGUOUS),
+            Language::French => (FRENCH_ABBREVIATIONS, FRENCH_AMBIGUOUS),
+            Language::German => (GERMAN_ABBREVIATIONS, GERMAN_AMBIGUOUS),
+            _ => (&[], &[]),
+        };
+        
+        Self {
+            abbreviations: COMMON_ABBREVIATIONS.iter().chain(specific).copied().collect(),
+            ambiguous: COMMON_AMBIGUOUS.iter().chain(ambiguous).copied().collect(),
+        }
+    }
+    
+    /// Whether a sentence starts at byte `offset` of `text`, judged by the
+    /// text before it and, after an abbreviation that is also a word, by
+    /// the word at `offset`
+    pub fn is_sentence_start(&self, text: &str, offset: usize) -> bool {
+        let before = &text[..offset];
+        
+        // Step back over opening quotes and the whitespace before them
+        let before = before.trim_end_matches(OPENING_PUNCTUATION);
+        let trimmed = before.trim_end();
+        if trimmed.is_empty() {
+            return true;
+        }
+        
+        // A terminator must be followed by some space: "3.5" and "e.g" aren't boundaries
+        let gap = &before[trimmed.len()..];
+        if gap.is_empty() {
+            return false;
+        }
+        
+        // A blank line starts a new paragraph, even after a heading with no period
+        if gap.matches('\n').count() >= 2 {
+            return true;
+        }
+        
+        let unquoted = trimmed.trim_end_matches(CLOSING_PUNCTUATION);
+        match unquoted.chars().next_back() {
+            Some(c) if TERMINATORS.contains(&c) => true,
+            Some('.') => !self.is_abbreviation(&unquoted[..unquoted.len() - 1], &text[offset..]),
+            _ => false,
+        }
+    }
+    
+    /// Byte ranges of the sentences in `text`, without surrounding whitespace
+    pub fn sentences(&self, text: &str) -> Vec<Range<usize>> {
+        let mut sentences = Vec::new();
+        let mut start: Option<usize> = None;
+        let mut prev_whitespace = true;
+        
+        for (i, c) in text.char_indices() {
+            if !c.is_whitespace() && prev_whitespace && start.is_some() && self.is_sentence_start(text, i) {
+                if let Some(s) = start.take() {
+                    sentences.push(s..text[..i].trim_end().len());
+                }
+            }
+            if !c.is_whitespace() && start.is_none() {
+                start = Some(i);
+            }
+            prev_whitespace = c.is_whitespace();
+        }
+        
+        if let Some(s) = start {
+            sentences.push(s..text.trim_end().len());
+        }
+        
+        sentences
+    }
+    
+    // `text` ends just before a period and `after` is what follows it;
+    // decide whether that period belongs to an abbreviation or initial
+    // rather than ending the sentence
+    fn is_abbreviation(&self, text: &str, after: &str) -> bool {
+        let token_start = text
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace() || OPENING_PUNCTUATION.contains(c))
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let token = &text[token_start..];
+        
+        // A bare "." or an ellipsis ends the sentence
+        if token.is_empty() || token.ends_with('.') {
+            return false;
+        }
+        
+        // Single-letter initials ("J. R. R. Tolkien")
+        let mut chars = token.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            if c.is_uppercase() {
+                return true;
+            }
+        }
+        
+        let token = token.to_lowercase();
+        self.abbreviations.contains(token.as_str())
+            || (self.ambiguous.contains(token.as_str())
+                && after.starts_with(|c: char| c.is_lowercase() || c.is_ascii_digit()))
+    }
+    
+    /// Whether `word` (without its period) is a common abbreviation
+    pub fn is_known_abbreviation(&self, word: &str) -> bool {
+        let word = word.to_lowercase();
+        self.abbreviations.contains(word.as_str()) || self.ambiguous.contains(word.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn starts_at(text: &str, word: &str) -> bool {
+        let segmenter = SentenceSegmenter::for_language(Language::English);
+        segmenter.is_sentence_start(text, text.find(word).unwrap())
+    }
+    
+    #[test]
+    fn abbreviations_continue_the_sentence() {
+        assert!(!starts_at("Ask Dr. Smith about it.", "Smith"));
+        assert!(!starts_at("See e.g. Tolkien.", "Tolkien"));
+        assert!(!starts_at("Written by J. R. R. Tolkien.", "Tolkien"));
+        assert!(!starts_at("Item No. 5 is gone.", "5"));
+    }
+    
+    #[test]
+    fn words_that_look_like_abbreviations_end_sentences() {
+        assert!(starts_at("He said no. Recieve it.", "Recieve"));
+        assert!(starts_at("Apples, pears etc. Then more.", "Then"));
+        assert!(starts_at("It is made by Acme Inc. The rest is history.", "The"));
+        assert!(starts_at("We met at the U.S. Later on we left.", "Later"));
+    }
+    
+    #[test]
+    fn sentences_split_at_boundaries() {
+        let segmenter = SentenceSegmenter::for_language(Language::English);
+        let text = "He said no. Then he left. Dr. Who stayed.";
+        let sentences: Vec<&str> = segmenter.sentences(text).into_iter().map(|r| &text[r]).collect();
+        assert_eq!(sentences, ["He said no.", "Then he left.", "Dr. Who stayed."]);
+    }
+}
@@ -1,10 +1,15 @@
-use crate::checker::{DocumentAnalysis, SpellChecker, WordType};
+use crate::checker::{DiagnosticKind, DocumentAnalysis, SpellChecker, WordType};
 use eframe::egui;
+use std::ops::Range;
+
+const DICTIONARY_FILTER_LIMIT: usize = 50;
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Sidebar {
     pub show_dictionary: bool,
     pub show_errors: bool,
+    #[serde(default)]
+    pub show_grammar: bool,
     pub show_stats: bool,
     pub show_find: bool,
     pub show_replace: bool,
@@ -17,6 +22,9 @@ pub struct Sidebar {
     pub dictionary_filter: String,
     pub show_ignored_words: bool,
     pub error_filter: ErrorFilter,
+    /// Only list diagnostics from this rule
+    #[serde(default)]
+    pub grammar_rule_filter: Option<String>,
 }
 
 #[derive(Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
@@ -39,6 +47,7 @@ impl Sidebar {
         Self {
             show_dictionary: true,
             show_errors: false,
+            show_grammar: false,
             show_stats: false,
             show_find: false,
             show_replace: false,
@@ -51,9 +60,11 @@ impl Sidebar {
             dictionary_filter: String::new(),
             show_ignored_words: false,
             error_filter: ErrorFilter::All,
+            grammar_rule_filter: None,
         }
     }
     
+    #[allow(clippy::too_many_arguments)]
     pub fn show(
         &mut self,
         ui: &mut egui::Ui,
@@ -62,7 +73,8 @@ impl Sidebar {
         content: &str,
         on_add_word: &mut Option<String>,
         on_ignore_word: &mut Option<String>,
-        on_replace: &mut Option<(String, String)>,
+        on_replace: &mut Option<(String, String, bool)>,
+        on_apply_fix: &mut Option<(Range<usize>, String)>,
         on_import_dict: &mut bool,
         on_export_dict: &mut bool,
         on_clear_ignored: &mut bool,
@@ -79,6 +91,11 @@ impl Sidebar {
                     self.show_errors = true;
                 }
                 
+                if ui.selectable_label(self.show_grammar, "✏️ Grammar").clicked() {
+                    self.reset_tabs();
+                    self.show_grammar = true;
+                }
+                
                 if ui.selectable_label(self.show_stats, "📊 Stats").clicked() {
                     self.reset_tabs();
                     self.show_stats = true;
@@ -104,6 +121,8 @@ impl Sidebar {
                     on_import_dict, on_export_dict, on_clear_ignored);
             } else if self.show_errors {
                 self.show_errors_view(ui, analysis, on_replace);
+            } else if self.show_grammar {
+                self.show_grammar_view(ui, spell_checker, analysis, on_apply_fix);
             } else if self.show_stats {
                 self.show_stats_view(ui, analysis, spell_checker);
             } else if self.show_find {
@@ -117,11 +136,13 @@ impl Sidebar {
     fn reset_tabs(&mut self) {
         self.show_dictionary = false;
         self.show_errors = false;
+        self.show_grammar = false;
         self.show_stats = false;
         self.show_find = false;
         self.show_replace = false;
     }
     
+    #[allow(clippy::too_many_arguments)]
     fn show_dictionary_view(
         &mut self,
         ui: &mut egui::Ui,
@@ -162,10 +183,10 @@ impl Sidebar {
             let mut new_word = String::new();
             let response = ui.text_edit_singleline(&mut new_word);
             
-            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) && !new_word.is_empty() {
-                if crate::util::is_valid_word(&new_word) {
-                    *on_add_word = Some(new_word.clone());
-                }
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) && !new_word.is_empty()
+                && crate::util::is_valid_word(&new_word)
+            {
+                *on_add_word = Some(new_word.clone());
             }
             
             let add_enabled = !new_word.is_empty() && crate::util::is_valid_word(&new_word);
@@ -183,10 +204,10 @@ impl Sidebar {
             let mut ignore_word = String::new();
             let response = ui.text_edit_singleline(&mut ignore_word);
             
-            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) && !ignore_word.is_empty() {
-                if crate::util::is_valid_word(&ignore_word) {
-                    *on_ignore_word = Some(ignore_word.clone());
-                }
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) && !ignore_word.is_empty()
+                && crate::util::is_valid_word(&ignore_word)
+            {
+                *on_ignore_word = Some(ignore_word.clone());
             }
             
             let ignore_enabled = !ignore_word.is_empty() && crate::util::is_valid_word(&ignore_word);
@@ -219,6 +240,30 @@ impl Sidebar {
             ui.text_edit_singleline(&mut self.dictionary_filter);
         });
         
+        let filter = self.dictionary_filter.trim();
+        if !filter.is_empty() {
+            if let Ok(dictionary) = spell_checker.get_current_dictionary() {
+                let matches = dictionary.words_with_prefix(filter, DICTIONARY_FILTER_LIMIT);
+                
+                if matches.is_empty() {
+                    ui.label("No dictionary words match the filter");
+                } else {
+                    egui::ScrollArea::vertical()
+                        .id_source("dictionary_filter_results")
+                        .max_height(150.0)
+                        .show(ui, |ui| {
+                            for word in &matches {
+                                ui.label(word);
+                            }
+                        });
+                    
+                    if matches.len() == DICTIONARY_FILTER_LIMIT {
+                        ui.label(format!("Showing the first {} matches", DICTIONARY_FILTER_LIMIT));
+                    }
+                }
+            }
+        }
+        
         ui.separator();
         
         ui.label("ℹ️ Added words are saved permanently");
@@ -229,7 +274,7 @@ impl Sidebar {
         &mut self,
         ui: &mut egui::Ui,
         analysis: &Option<DocumentAnalysis>,
-        on_replace: &mut Option<(String, String)>,
+        on_replace: &mut Option<(String, String, bool)>,
     ) {
         ui.heading("Spelling Errors");
         
@@ -293,12 +338,22 @@ impl Sidebar {
                     if !word.suggestions.is_empty() {
                         ui.indent("suggestions", |ui| {
                             ui.label("Suggestions:");
-                            for suggestion in &word.suggestions {
+                            // Already sorted best first by the checker
+                            for (rank, suggestion) in word.suggestions.iter().enumerate() {
                                 ui.horizontal(|ui| {
                                     if ui.button("Use").clicked() {
-                                        *on_replace = Some((word.word.clone(), suggestion.clone()));
+                                        let find = word.identifier.clone().unwrap_or_else(|| word.word.clone());
+                                        *on_replace = Some((find, suggestion.word.clone(), true));
+                                    }
+                                    if rank == 0 {
+                                        ui.strong(&suggestion.word);
+                                    } else {
+                                        ui.label(&suggestion.word);
                                     }
-                                    ui.label(suggestion);
+                                    ui.colored_label(
+                                        egui::Color32::GRAY,
+                                        format!("{:.0}%", suggestion.score * 100.0)
+                                    );
                                 });
                             }
                         });
@@ -311,10 +366,8 @@ impl Sidebar {
             ui.separator();
             ui.horizontal(|ui| {
                 ui.label(format!("Errors: {}/{}", filtered_errors.len(), analysis.misspelled_words));
-                if analysis.misspelled_words > 0 {
-                    if ui.button("▶️ Fix All").clicked() {
-                        ui.label("Feature coming soon...");
-                    }
+                if analysis.misspelled_words > 0 && ui.button("▶️ Fix All").clicked() {
+                    ui.label("Feature coming soon...");
                 }
             });
         } else {
@@ -322,6 +375,74 @@ impl Sidebar {
         }
     }
     
+    fn show_grammar_view(
+        &mut self,
+        ui: &mut egui::Ui,
+        spell_checker: &SpellChecker,
+        analysis: &Option<DocumentAnalysis>,
+        on_apply_fix: &mut Option<(Range<usize>, String)>,
+    ) {
+        ui.heading("Grammar");
+        
+        ui.horizontal(|ui| {
+            ui.label("Rule:");
+            egui::ComboBox::from_id_source("grammar_rule_filter")
+                .selected_text(self.grammar_rule_filter.as_deref().unwrap_or("All"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.grammar_rule_filter, None, "All");
+                    for rule_id in spell_checker.grammar_rule_ids() {
+                        ui.selectable_value(&mut self.grammar_rule_filter, Some(rule_id.to_string()), rule_id);
+                    }
+                });
+        });
+        
+        let Some(analysis) = analysis else {
+            ui.label("No document loaded or checked.");
+            return;
+        };
+        
+        let diagnostics: Vec<&crate::checker::Diagnostic> = analysis.diagnostics
+            .iter()
+            .filter(|d| self.grammar_rule_filter.as_deref().is_none_or(|id| d.rule_id == id))
+            .collect();
+        
+        if diagnostics.is_empty() {
+            ui.colored_label(egui::Color32::GREEN, "✅ No grammar issues found!");
+            return;
+        }
+        
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for diagnostic in &diagnostics {
+                ui.horizontal(|ui| {
+                    match diagnostic.kind {
+                        DiagnosticKind::Grammar => ui.colored_label(egui::Color32::from_rgb(100, 149, 237), "✎"),
+                        DiagnosticKind::RealWord => ui.colored_label(egui::Color32::from_rgb(255, 165, 0), "⚠"),
+                    };
+                    ui.label(&diagnostic.message);
+                    ui.label(format!("(L{}:C{})", diagnostic.line, diagnostic.column));
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::GRAY, diagnostic.rule_id);
+                    if let Some(replacement) = &diagnostic.replacement {
+                        let label = if replacement.is_empty() {
+                            format!("Remove '{}'", diagnostic.text)
+                        } else {
+                            format!("Use '{}'", replacement)
+                        };
+                        if ui.button(label).clicked() {
+                            *on_apply_fix = Some((diagnostic.range.clone(), replacement.clone()));
+                        }
+                    }
+                });
+                
+                ui.separator();
+            }
+        });
+        
+        ui.label(format!("Issues: {}/{}", diagnostics.len(), analysis.diagnostics.len()));
+    }
+    
     fn show_stats_view(
         &mut self,
         ui: &mut egui::Ui,
@@ -445,7 +566,7 @@ impl Sidebar {
         }
     }
     
-    fn show_replace_view(&mut self, ui: &mut egui::Ui, content: &str, on_replace: &mut Option<(String, String)>) {
+    fn show_replace_view(&mut self, ui: &mut egui::Ui, content: &str, on_replace: &mut Option<(String, String, bool)>) {
         ui.heading("Find and Replace");
         
         ui.horizontal(|ui| {
@@ -463,7 +584,7 @@ impl Sidebar {
         
         ui.horizontal(|ui| {
             if ui.button("Replace").clicked() && !self.find_text.is_empty() {
-                *on_replace = Some((self.find_text.clone(), self.replace_text.clone()));
+                *on_replace = Some((self.find_text.clone(), self.replace_text.clone(), false));
             }
             
             if ui.button("Replace All").clicked() && !self.find_text.is_empty() {
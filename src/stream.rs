@@ -0,0 +1,475 @@
+use crate::checker::{Diagnostic, SpellChecker, WordCheck};
+use crate::dictionary::Dictionary;
+use crate::gettext;
+use crate::language::Language;
+use crate::prose::{FrontEnd, Prose};
+use serde::Serialize;
+use std::io::{self, BufRead, Cursor, Read};
+use std::ops::Range;
+use std::sync::Arc;
+
+// Longer lines (minified JSON, logs) are checked in pieces of at most this
+// many bytes, cut after a space where there is one
+const MAX_PIECE_BYTES: usize = 64 * 1024;
+
+// A file whose prose is found a block at a time is read in blocks of whole
+// lines of at least this many bytes, and cut wherever it has got to at the
+// most, even inside a comment or entry
+const BLOCK_BYTES: usize = 64 * 1024;
+const MAX_BLOCK_BYTES: usize = 1024 * 1024;
+
+/// The longest file whose front end needs it whole (Markdown, HTML, LaTeX,
+/// JSON, YAML, TOML) that is streamed with one; longer ones are checked as
+/// plain text
+pub const MAX_WHOLE_FILE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Problems found on one line of a streamed document
+#[derive(Debug, Clone, Serialize)]
+pub struct LineReport {
+    pub line: usize,
+    /// Byte offset of the line from the start of the input, or of the piece
+    /// checked when the line is too long to check at once
+    pub offset: usize,
+    pub misspelled: Vec<WordCheck>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Running totals for the part of a stream checked so far
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StreamTotals {
+    pub lines_checked: usize,
+    pub bytes_read: usize,
+    pub total_words: usize,
+    pub misspelled_words: usize,
+    pub suggestions_count: usize,
+    pub diagnostics: usize,
+}
+
+impl StreamTotals {
+    pub fn accuracy(&self) -> f32 {
+        crate::util::calculate_accuracy(self.total_words - self.misspelled_words, self.total_words)
+    }
+}
+
+/// Checks a `BufRead` one line at a time, yielding a `LineReport` for every
+/// line with a misspelling or diagnostic.
+///
+/// Only the current and previous line are held in memory, so inputs of any
+/// size can be checked; a line longer than 64 KiB is checked in pieces. The
+/// previous line is kept so sentence starts and grammar rules can still see
+/// across the line break.
+///
+/// A file with a front end has its prose checked instead: source code,
+/// gettext catalogs, Fluent and properties files a block of lines at a
+/// time, other files whole if they are at most `MAX_WHOLE_FILE_BYTES` long.
+pub struct StreamCheck<'a, R> {
+    checker: &'a SpellChecker,
+    dictionary: Arc<Dictionary>,
+    reader: R,
+    // Input read ahead of the reader, to be checked before it
+    head: Cursor<Vec<u8>>,
+    prose: Option<ProseSource>,
+    is_code: bool,
+    buffer: Vec<u8>,
+    // The part of an overlong line read past the end of the last piece
+    carry: Vec<u8>,
+    // Whether the last piece ended before its line did, and how many
+    // characters of the line came before the current piece
+    line_continues: bool,
+    column_base: usize,
+    previous_line: String,
+    previous_offset: usize,
+    // Words and already reported diagnostics of the previous line, with
+    // offsets relative to its start
+    previous_words: Vec<WordCheck>,
+    previous_diagnostics: Vec<(Range<usize>, &'static str)>,
+    totals: StreamTotals,
+}
+
+impl<'a, R: BufRead> StreamCheck<'a, R> {
+    pub(crate) fn new(checker: &'a SpellChecker, dictionary: Arc<Dictionary>, reader: R, is_code: bool) -> Self {
+        Self {
+            checker,
+            dictionary,
+            reader,
+            head: Cursor::new(Vec::new()),
+            prose: None,
+            is_code,
+            buffer: Vec::new(),
+            carry: Vec::new(),
+            line_continues: false,
+            column_base: 0,
+            previous_line: String::new(),
+            previous_offset: 0,
+            previous_words: Vec::new(),
+            previous_diagnostics: Vec::new(),
+            totals: StreamTotals::default(),
+        }
+    }
+    
+    // Check `head` before what is left in the reader
+    pub(crate) fn with_head(mut self, head: Vec<u8>) -> Self {
+        self.head = Cursor::new(head);
+        self
+    }
+    
+    // Check the prose `front_end` finds in `head` and the rest of the
+    // reader, with spans that don't name their language taken to be in
+    // `language`
+    pub(crate) fn with_front_end(mut self, front_end: FrontEnd, language: Option<Language>, head: String) -> Self {
+        self.prose = Some(ProseSource {
+            front_end,
+            language,
+            translations: None,
+            pending: Some(head),
+            text: Cursor::new(Vec::new()),
+            prose: Prose::new("", &[]),
+            languages: Vec::new(),
+            original: String::new(),
+            prose_start: 0,
+            file_start: 0,
+            previous: None,
+            line_start: 0,
+            counted: (0, 1),
+        });
+        self
+    }
+    
+    pub fn totals(&self) -> &StreamTotals {
+        &self.totals
+    }
+    
+    // Check the line in `self.buffer`, which starts at byte `line_offset`
+    fn check_buffered_line(&mut self, line_offset: usize) -> LineReport {
+        let raw = String::from_utf8_lossy(&self.buffer);
+        let line = raw.strip_suffix('\n').unwrap_or(&raw);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let line_num = self.totals.lines_checked;
+        
+        // The previous line comes first so the checker can look back across the break
+        let window = format!("{}\n{}", self.previous_line, line);
+        let line_start = self.previous_line.len() + 1;
+        
+        let languages = self.prose.as_ref().map_or_else(Vec::new, |source| source.languages_on(line_offset, line.len(), line_start));
+        let mut context = self.checker.line_context(&self.dictionary, &window, self.is_code);
+        context.languages = &languages;
+        let mut words = Vec::new();
+        self.checker.check_line(line, line_num, line_start, &context, None, &mut words);
+        for word in &mut words {
+            word.column += self.column_base;
+        }
+        self.column_base += line.chars().count();
+        
+        let mut window_words = std::mem::take(&mut self.previous_words);
+        let previous_count = window_words.len();
+        window_words.append(&mut words);
+        
+        // A rule may only now flag the end of the previous line ("a" then
+        // "apple" on the next), so report anything there not reported yet
+        let mut diagnostics = self.checker.grammar_diagnostics(&window_words, &window);
+        diagnostics.retain(|d| {
+            d.range.start >= line_start || !self.previous_diagnostics.contains(&(d.range.clone(), d.rule_id))
+        });
+        self.previous_diagnostics = diagnostics.iter()
+            .filter(|d| d.range.start >= line_start)
+            .map(|d| (d.range.start - line_start..d.range.end - line_start, d.rule_id))
+            .collect();
+        
+        let previous_offset = self.previous_offset;
+        let to_document = |position: usize| {
+            if position >= line_start {
+                position - line_start + line_offset
+            } else {
+                position + previous_offset
+            }
+        };
+        for diagnostic in &mut diagnostics {
+            diagnostic.range = to_document(diagnostic.range.start)..to_document(diagnostic.range.end);
+        }
+        
+        let mut words = window_words.split_off(previous_count);
+        let mut misspelled = Vec::new();
+        for word in &mut words {
+            word.offset -= line_start;
+            if word.skipped {
+                continue;
+            }
+            
+            self.totals.total_words += 1;
+            self.totals.suggestions_count += word.suggestions.len();
+            if !word.is_correct {
+                self.totals.misspelled_words += 1;
+                let mut reported = word.clone();
+                reported.offset += line_offset;
+                misspelled.push(reported);
+            }
+        }
+        self.totals.diagnostics += diagnostics.len();
+        
+        self.previous_line = line.to_string();
+        self.previous_offset = line_offset;
+        self.previous_words = words;
+        
+        let mut line_offset = line_offset;
+        if let Some(source) = &mut self.prose {
+            for word in &mut misspelled {
+                source.move_word(word);
+            }
+            for diagnostic in &mut diagnostics {
+                diagnostic.range = source.to_file(diagnostic.range.start)..source.to_file(diagnostic.range.end);
+            }
+            line_offset = source.to_file(line_offset);
+        }
+        
+        LineReport {
+            line: line_num,
+            offset: line_offset,
+            misspelled,
+            diagnostics,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for StreamCheck<'_, R> {
+    type Item = io::Result<LineReport>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let read = match &mut self.prose {
+                Some(source) => loop {
+                    match read_piece(&mut source.text, &mut self.buffer, &mut self.carry, MAX_PIECE_BYTES) {
+                        Ok(0) => match source.next_block(&mut self.reader, self.checker) {
+                            Ok(true) => continue,
+                            Ok(false) => break Ok(0),
+                            Err(e) => break Err(e),
+                        },
+                        read => break read,
+                    }
+                },
+                None => read_piece(&mut (&mut self.head).chain(&mut self.reader), &mut self.buffer, &mut self.carry, MAX_PIECE_BYTES),
+            };
+            let read = match read {
+                Ok(0) => return None,
+                Ok(read) => read,
+                Err(e) => return Some(Err(e)),
+            };
+            
+            let line_offset = self.totals.bytes_read;
+            self.totals.bytes_read += read;
+            if !self.line_continues {
+                self.totals.lines_checked += 1;
+                self.column_base = 0;
+                if let Some(source) = &mut self.prose {
+                    source.start_line(line_offset);
+                }
+            }
+            self.line_continues = !self.buffer.ends_with(b"\n");
+            
+            let report = self.check_buffered_line(line_offset);
+            if !report.misspelled.is_empty() || !report.diagnostics.is_empty() {
+                return Some(Ok(report));
+            }
+        }
+    }
+}
+
+// A range of prose marked as another language, with its dictionary
+pub(crate) type MarkedLanguage = (Range<usize>, Option<Arc<Dictionary>>);
+
+// The prose of a file with a front end, found a block of whole lines at a
+// time, and how to map it back to the file
+struct ProseSource {
+    front_end: FrontEnd,
+    language: Option<Language>,
+    // The language of a gettext catalog's translations, from its header in
+    // the first block
+    translations: Option<Option<Language>>,
+    // What has been read of the next block; None once the file is read
+    pending: Option<String>,
+    // The prose of the current block, what is left of its text to check,
+    // its marked languages and the block itself
+    text: Cursor<Vec<u8>>,
+    prose: Prose,
+    languages: Vec<MarkedLanguage>,
+    original: String,
+    // Where the current block starts in the prose and in the file
+    prose_start: usize,
+    file_start: usize,
+    // The same for the block before, whose last line may still be reported
+    previous: Option<(usize, usize, Prose)>,
+    // Where the current line starts in the file, and the last offset on it
+    // whose column is known, so long lines aren't counted over and over
+    line_start: usize,
+    counted: (usize, usize),
+}
+
+impl ProseSource {
+    // Read the next block and find its prose; false at the end of the file
+    fn next_block<R: BufRead>(&mut self, reader: &mut R, checker: &SpellChecker) -> io::Result<bool> {
+        let Some(pending) = &mut self.pending else {
+            return Ok(false);
+        };
+        
+        let mut line = Vec::new();
+        let mut next_check = BLOCK_BYTES;
+        let cut = loop {
+            let room = MAX_BLOCK_BYTES.saturating_sub(pending.len());
+            line.clear();
+            let read = reader.by_ref().take(room as u64).read_until(b'\n', &mut line)?;
+            pending.push_str(&String::from_utf8_lossy(&line));
+            if read == 0 {
+                break pending.len();
+            }
+            
+            if self.front_end.reads_by_block() && pending.len() >= next_check {
+                let settled = self.front_end.settled(pending);
+                if settled > 0 {
+                    break settled;
+                }
+                next_check = pending.len() + BLOCK_BYTES;
+            }
+        };
+        
+        let rest = pending.split_off(cut);
+        let block = std::mem::replace(pending, rest);
+        if block.is_empty() {
+            self.pending = None;
+            return Ok(false);
+        }
+        
+        if self.file_start == 0 && self.original.is_empty() {
+            self.translations = gettext::translation_language(&block);
+        }
+        let (mut prose, languages) = checker.block_prose(&block, self.front_end, self.language, self.translations);
+        
+        // A line cut by the end of the block goes on in the next one
+        let end = self.file_start + self.original.len();
+        let column = self.counted.1 + self.original[self.counted.0.min(end) - self.file_start..].chars().count();
+        self.counted = (end, column);
+        
+        let prose_end = self.prose_start + self.text.get_ref().len();
+        let previous = std::mem::replace(&mut self.prose, Prose::new("", &[]));
+        self.previous = Some((self.prose_start, self.file_start, previous));
+        self.prose_start = prose_end;
+        self.file_start = end;
+        self.text = Cursor::new(std::mem::take(&mut prose.text).into_bytes());
+        self.prose = prose;
+        self.languages = languages;
+        self.original = block;
+        Ok(true)
+    }
+    
+    // The offset in the file of `offset` in the prose
+    fn to_file(&self, offset: usize) -> usize {
+        match &self.previous {
+            Some((prose_start, file_start, prose)) if offset < self.prose_start => {
+                file_start + prose.to_original(offset.saturating_sub(*prose_start))
+            }
+            _ => self.file_start + self.prose.to_original(offset.saturating_sub(self.prose_start)),
+        }
+    }
+    
+    fn start_line(&mut self, offset: usize) {
+        self.line_start = self.to_file(offset);
+        self.counted = (self.line_start, 1);
+    }
+    
+    // The marked languages of the `len` bytes of prose at `start`, moved
+    // to where those bytes are in the window being checked
+    fn languages_on(&self, start: usize, len: usize, window_start: usize) -> Vec<MarkedLanguage> {
+        let start = start - self.prose_start;
+        let end = start + len;
+        let first = self.languages.partition_point(|(range, _)| range.end <= start);
+        self.languages[first..]
+            .iter()
+            .take_while(|(range, _)| range.start < end)
+            .map(|(range, dictionary)| {
+                let range = range.start.max(start) - start + window_start..range.end.min(end) - start + window_start;
+                (range, dictionary.clone())
+            })
+            .collect()
+    }
+    
+    // Move a word found on the current line of the prose to where it is
+    // in the file
+    fn move_word(&mut self, word: &mut WordCheck) {
+        let start = self.to_file(word.offset);
+        let end = self.to_file(word.offset + (word.end - word.start));
+        let (from, column) = if start >= self.counted.0 { self.counted } else { (self.line_start.max(self.file_start), 1) };
+        let column = column + self.original[from - self.file_start..start - self.file_start].chars().count();
+        self.counted = (start, column);
+        
+        word.offset = start;
+        word.start = start - self.line_start;
+        word.end = end - self.line_start;
+        word.column = column;
+    }
+}
+
+// Read the next piece of input into `buffer`: the rest of the line with its
+// line break, or `max` bytes of it cut after the last space (or at a
+// character boundary) with the rest left in `carry`. Returns its length.
+fn read_piece<R: BufRead>(reader: &mut R, buffer: &mut Vec<u8>, carry: &mut Vec<u8>, max: usize) -> io::Result<usize> {
+    buffer.clear();
+    // What was carried over never holds a line break
+    buffer.append(carry);
+    
+    while buffer.len() < max {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            return Ok(buffer.len());
+        }
+        let window = &available[..available.len().min(max - buffer.len())];
+        match window.iter().position(|&b| b == b'\n') {
+            Some(i) => {
+                buffer.extend_from_slice(&window[..=i]);
+                reader.consume(i + 1);
+                return Ok(buffer.len());
+            }
+            None => {
+                let len = window.len();
+                buffer.extend_from_slice(window);
+                reader.consume(len);
+            }
+        }
+    }
+    
+    let cut = match buffer.iter().rposition(|&b| b == b' ' || b == b'\t') {
+        Some(space) => space + 1,
+        // One huge token: don't split a UTF-8 sequence
+        None => (1..buffer.len()).rev().find(|&i| buffer[i] & 0xC0 != 0x80).unwrap_or(buffer.len()),
+    };
+    *carry = buffer.split_off(cut);
+    Ok(buffer.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    
+    fn pieces(input: &str, max: usize) -> Vec<String> {
+        let mut reader = Cursor::new(input.as_bytes());
+        let (mut buffer, mut carry) = (Vec::new(), Vec::new());
+        let mut pieces = Vec::new();
+        while read_piece(&mut reader, &mut buffer, &mut carry, max).unwrap() > 0 {
+            pieces.push(String::from_utf8(buffer.clone()).unwrap());
+        }
+        pieces
+    }
+    
+    #[test]
+    fn short_lines_are_read_whole() {
+        assert_eq!(pieces("one two\nthree", 64), ["one two\n", "three"]);
+    }
+    
+    #[test]
+    fn long_lines_are_cut_after_a_space() {
+        assert_eq!(pieces("alpha beta gamma delta\nend\n", 12), ["alpha beta ", "gamma delta\n", "end\n"]);
+    }
+    
+    #[test]
+    fn long_tokens_are_cut_at_a_character_boundary() {
+        assert_eq!(pieces("ééééé", 5), ["éé", "éé", "é"]);
+    }
+}
@@ -0,0 +1,190 @@
+use rayon::prelude::*;
+use std::collections::HashSet;
+
+// Only the first few characters of each word are used to generate deletes.
+// This keeps the index small while still finding every candidate within
+// the maximum edit distance (the remaining characters are verified later).
+const DEFAULT_PREFIX_LENGTH: usize = 7;
+pub const DEFAULT_MAX_DISTANCE: usize = 2;
+
+/// Symmetric-delete suggestion index (SymSpell).
+///
+/// Every dictionary word contributes all strings reachable by deleting up to
+/// `max_distance` characters from its prefix. A lookup generates the same
+/// deletes for the input and intersects them with the index, so only a
+/// handful of candidates ever reach the edit-distance check.
+#[derive(Debug, Clone)]
+pub struct SymSpellIndex {
+    words: Vec<String>,
+    // (hash of delete, word id), sorted so lookups are a binary search
+    entries: Vec<(u32, u32)>,
+    max_distance: usize,
+    prefix_length: usize,
+}
+
+impl SymSpellIndex {
+    pub fn new<'a>(words: impl IntoIterator<Item = &'a str>) -> Self {
+        Self::with_settings(words, DEFAULT_MAX_DISTANCE, DEFAULT_PREFIX_LENGTH)
+    }
+    
+    pub fn with_settings<'a>(words: impl IntoIterator<Item = &'a str>, max_distance: usize, prefix_length: usize) -> Self {
+        // Sorting gives stable word ids, which keeps results independent of
+        // the iteration order of whatever set the words came from
+        let mut words: Vec<String> = words.into_iter().map(String::from).collect();
+        words.sort_unstable();
+        words.dedup();
+        
+        let mut entries: Vec<(u32, u32)> = words
+            .par_iter()
+            .enumerate()
+            .flat_map_iter(|(id, word)| {
+                let prefix: Vec<char> = word.chars().take(prefix_length).collect();
+                deletes(&prefix, max_distance)
+                    .into_iter()
+                    .map(move |hash| (hash, id as u32))
+            })
+            .collect();
+        entries.par_sort_unstable();
+        
+        Self {
+            words,
+            entries,
+            max_distance,
+            prefix_length,
+        }
+    }
+    
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+    
+    pub fn max_distance(&self) -> usize {
+        self.max_distance
+    }
+    
+    /// Return every indexed word within `max_distance` edits of `input`, a
+    /// transposition counting as one, ordered by distance and then
+    /// alphabetically
+    pub fn lookup(&self, input: &str, max_distance: usize) -> Vec<(&str, usize)> {
+        let max_distance = max_distance.min(self.max_distance);
+        let input_chars: Vec<char> = input.chars().collect();
+        let input_len = input_chars.len();
+        let prefix: Vec<char> = input_chars.iter().take(self.prefix_length).copied().collect();
+        
+        let mut candidate_ids = HashSet::new();
+        for hash in deletes(&prefix, max_distance) {
+            let start = self.entries.partition_point(|(h, _)| *h < hash);
+            for (h, id) in &self.entries[start..] {
+                if *h != hash {
+                    break;
+                }
+                candidate_ids.insert(*id);
+            }
+        }
+        
+        let mut results: Vec<(&str, usize)> = candidate_ids
+            .into_iter()
+            .filter_map(|id| {
+                let word = self.words[id as usize].as_str();
+                let len_diff = word.chars().count().abs_diff(input_len);
+                if len_diff > max_distance {
+                    return None;
+                }
+                let distance = crate::keyboard::osa_distance(input, word);
+                (distance <= max_distance).then_some((word, distance))
+            })
+            .collect();
+        
+        results.sort_unstable_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(b.0)));
+        results
+    }
+}
+
+/// Hashes of all strings obtainable by deleting up to `max_distance` characters
+fn deletes(chars: &[char], max_distance: usize) -> Vec<u32> {
+    let mut hashes = Vec::new();
+    let mut removed = Vec::with_capacity(max_distance);
+    collect_deletes(chars, max_distance, 0, &mut removed, &mut hashes);
+    
+    // Repeated letters produce the same delete more than once
+    hashes.sort_unstable();
+    hashes.dedup();
+    hashes
+}
+
+fn collect_deletes(chars: &[char], max_distance: usize, start: usize, removed: &mut Vec<usize>, hashes: &mut Vec<u32>) {
+    hashes.push(fnv1a(chars.iter().enumerate().filter(|(i, _)| !removed.contains(i)).map(|(_, c)| *c)));
+    
+    if removed.len() == max_distance {
+        return;
+    }
+    for i in start..chars.len() {
+        removed.push(i);
+        collect_deletes(chars, max_distance, i + 1, removed, hashes);
+        removed.pop();
+    }
+}
+
+// FNV-1a is used instead of the std hasher so the index is identical on every run
+fn fnv1a(chars: impl Iterator<Item = char>) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for c in chars {
+        for byte in (c as u32).to_le_bytes() {
+            hash ^= byte as u32;
+            hash = hash.wrapping_mul(0x0100_0193);
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn transpositions_are_one_edit() {
+        let index = SymSpellIndex::new(["the", "then", "receive"]);
+        assert_eq!(index.lookup("teh", 1), [("the", 1)]);
+        assert_eq!(index.lookup("tehn", 2), [("then", 1), ("the", 2)]);
+        assert_eq!(index.lookup("recieve", 1), [("receive", 1)]);
+    }
+    
+    #[test]
+    fn lookup_within_one_and_two_edits() {
+        let index = SymSpellIndex::new(["house", "mouse", "horse", "hose", "castle"]);
+        assert_eq!(index.lookup("hous", 1), [("house", 1)]);
+        assert_eq!(index.lookup("hous", 2), [("house", 1), ("horse", 2), ("hose", 2), ("mouse", 2)]);
+        assert!(index.lookup("hxyzq", 2).is_empty());
+        // Never further than the index was built for
+        assert!(SymSpellIndex::with_settings(["house"], 1, DEFAULT_PREFIX_LENGTH).lookup("hoxxe", 2).is_empty());
+    }
+    
+    #[test]
+    fn results_do_not_depend_on_word_order() {
+        let words = ["bark", "park", "perk", "part", "dark"];
+        let mut reversed = words;
+        reversed.reverse();
+        assert_eq!(SymSpellIndex::new(words).lookup("pork", 2), SymSpellIndex::new(reversed).lookup("pork", 2));
+    }
+    
+    #[test]
+    fn edits_past_the_prefix_are_found() {
+        let index = SymSpellIndex::new(["international", "internationally"]);
+        assert_eq!(index.lookup("internatoinal", 1), [("international", 1)]);
+        assert_eq!(index.lookup("internationaly", 1), [("international", 1), ("internationally", 1)]);
+        assert!(index.lookup("internationxxxx", 2).is_empty());
+    }
+    
+    #[test]
+    fn non_ascii_words() {
+        let index = SymSpellIndex::new(["café", "größe", "naïve", "日本語"]);
+        assert_eq!(index.lookup("cafe", 1), [("café", 1)]);
+        assert_eq!(index.lookup("grösse", 2), [("größe", 2)]);
+        assert_eq!(index.lookup("naive", 1), [("naïve", 1)]);
+        assert_eq!(index.lookup("日本", 1), [("日本語", 1)]);
+    }
+}
@@ -0,0 +1,277 @@
+use crate::language::Language;
+use crate::sentence::SentenceSegmenter;
+use serde::Serialize;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+const ENGLISH_ORDINALS: &[&str] = &["st", "nd", "rd", "th"];
+const AFRIKAANS_ORDINALS: &[&str] = &["ste", "de", "e"];
+const FRENCH_ORDINALS: &[&str] = &["er", "re", "ère", "e", "è", "ème", "eme", "nd", "nde", "d", "de"];
+const SPANISH_ORDINALS: &[&str] = &["º", "ª", "o", "a", "er", "ra"];
+const ITALIAN_ORDINALS: &[&str] = &["º", "ª", "o", "a"];
+const PORTUGUESE_ORDINALS: &[&str] = &["º", "ª", "o", "a"];
+
+/// What kind of token a `Tokenizer` found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TokenKind {
+    Word,
+    /// A known abbreviation followed by its period ("Dr.") or letters
+    /// separated by periods ("e.g", "U.S")
+    Abbreviation,
+    /// A number with an ordinal suffix ("1st", "2e", "3ème", "4de")
+    Ordinal,
+    /// Digits, possibly with separators ("3.14", "1,000")
+    Number,
+    /// Letters and digits mixed ("mp3", "utf8")
+    Alphanumeric,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    /// Byte range in the tokenized text
+    pub bytes: Range<usize>,
+    /// Character range in the tokenized text
+    pub chars: Range<usize>,
+    pub kind: TokenKind,
+}
+
+/// Splits text into the tokens the checker looks at. Punctuation and
+/// whitespace are never returned.
+pub trait Tokenizer: Send + Sync {
+    /// Tokens of `text` in order
+    fn tokenize<'a>(&self, text: &'a str) -> Vec<Token<'a>>;
+}
+
+/// Unicode (UAX #29) word boundaries, adjusted for spell checking:
+/// hyphenated words ("well-known", "ge-eet") stay whole, a run of Chinese or
+/// Japanese characters is one token, and words joined by a period or colon
+/// without a space ("end.Next", "self.name") are split unless they form an
+/// abbreviation
+pub struct UnicodeTokenizer {
+    segmenter: SentenceSegmenter,
+    ordinal_suffixes: &'static [&'static str],
+}
+
+impl UnicodeTokenizer {
+    pub fn for_language(language: Language) -> Self {
+        let ordinal_suffixes = match language {
+            Language::Afrikaans => AFRIKAANS_ORDINALS,
+            Language::French => FRENCH_ORDINALS,
+            Language::Spanish => SPANISH_ORDINALS,
+            Language::Italian => ITALIAN_ORDINALS,
+            Language::Portuguese => PORTUGUESE_ORDINALS,
+            _ => ENGLISH_ORDINALS,
+        };
+        
+        Self {
+            segmenter: SentenceSegmenter::for_language(language),
+            ordinal_suffixes,
+        }
+    }
+    
+    fn classify(&self, token: &str, after: &str) -> TokenKind {
+        let first = token.chars().next().unwrap_or(' ');
+        let has_letters = token.chars().any(char::is_alphabetic);
+        let has_digits = token.chars().any(|c| c.is_ascii_digit());
+        
+        if !has_letters {
+            return TokenKind::Number;
+        }
+        
+        if first.is_ascii_digit() {
+            let suffix = token.trim_start_matches(|c: char| c.is_ascii_digit());
+            if self.ordinal_suffixes.contains(&suffix.to_lowercase().as_str()) {
+                return TokenKind::Ordinal;
+            }
+        }
+        
+        if has_digits {
+            return TokenKind::Alphanumeric;
+        }
+        
+        // Single letters joined by periods ("e.g", "U.S"), or an
+        // abbreviation the segmenter knows ("z.B", "Dr" before its period).
+        // Other dotted tokens ("is.it") are words run together.
+        let dotted = token.contains('.');
+        let initials = dotted && token.split('.').all(|part| part.chars().count() == 1);
+        if initials || ((dotted || after.starts_with('.')) && self.segmenter.is_known_abbreviation(token)) {
+            return TokenKind::Abbreviation;
+        }
+        
+        TokenKind::Word
+    }
+}
+
+impl Default for UnicodeTokenizer {
+    fn default() -> Self {
+        Self::for_language(Language::English)
+    }
+}
+
+impl Tokenizer for UnicodeTokenizer {
+    fn tokenize<'a>(&self, text: &'a str) -> Vec<Token<'a>> {
+        let mut spans: Vec<Range<usize>> = Vec::new();
+        
+        for (start, segment) in text.split_word_bound_indices() {
+            if !segment.chars().any(char::is_alphanumeric) {
+                continue;
+            }
+            
+            for part in split_joined_words(segment) {
+                let part = start + part.start..start + part.end;
+                
+                if let Some(previous) = spans.last_mut() {
+                    let gap = &text[previous.end..part.start];
+                    let joined_by_hyphen = gap == "-"
+                        && text[..previous.end].ends_with(char::is_alphabetic)
+                        && text[part.start..].starts_with(char::is_alphabetic);
+                    let cjk_run = gap.is_empty()
+                        && text[..previous.end].ends_with(is_cjk_char)
+                        && text[part.start..].starts_with(is_cjk_char);
+                    
+                    if joined_by_hyphen || cjk_run {
+                        previous.end = part.end;
+                        continue;
+                    }
+                }
+                
+                spans.push(part);
+            }
+        }
+        
+        // Character offsets, counted in one pass since the spans are in order
+        let mut byte_pos = 0;
+        let mut char_pos = 0;
+        let mut char_offset = |byte: usize| {
+            char_pos += text[byte_pos..byte].chars().count();
+            byte_pos = byte;
+            char_pos
+        };
+        
+        spans
+            .into_iter()
+            .map(|bytes| {
+                let chars = char_offset(bytes.start)..char_offset(bytes.end);
+                let token = &text[bytes.clone()];
+                Token {
+                    text: token,
+                    kind: self.classify(token, &text[bytes.end..]),
+                    bytes,
+                    chars,
+                }
+            })
+            .collect()
+    }
+}
+
+// UAX #29 keeps "self.name" and "Note:This" together. Split them, but leave
+// abbreviations such as "e.g" and "U.S" (parts of one or two letters) alone.
+fn split_joined_words(segment: &str) -> Vec<Range<usize>> {
+    let whole = Range { start: 0, end: segment.len() };
+    if !segment.contains(['.', ':']) || segment.starts_with(|c: char| c.is_ascii_digit()) {
+        return vec![whole];
+    }
+    
+    let is_abbreviation = !segment.contains(':')
+        && segment.split('.').all(|part| (1..=2).contains(&part.chars().count()));
+    if is_abbreviation {
+        return vec![whole];
+    }
+    
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (i, c) in segment.char_indices() {
+        if c == '.' || c == ':' {
+            if i > start {
+                parts.push(start..i);
+            }
+            start = i + 1;
+        }
+    }
+    if start < segment.len() {
+        parts.push(start..segment.len());
+    }
+    parts
+}
+
+/// Byte ranges of the words inside a code identifier, split on underscores,
+/// digits, case changes and the end of an acronym: "HTTPServerError" gives
+/// "HTTP", "Server" and "Error", "calc_total2" gives "calc" and "total".
+pub fn identifier_parts(identifier: &str) -> Vec<Range<usize>> {
+    let chars: Vec<(usize, char)> = identifier.char_indices().collect();
+    let mut parts = Vec::new();
+    let mut start: Option<usize> = None;
+    
+    for (i, &(byte, c)) in chars.iter().enumerate() {
+        if !c.is_alphabetic() {
+            if let Some(part_start) = start.take() {
+                parts.push(part_start..byte);
+            }
+            continue;
+        }
+        
+        if let Some(part_start) = start {
+            let previous = chars[i - 1].1;
+            let next = chars.get(i + 1).map(|&(_, next)| next);
+            // "recieveMessage", or the "S" ending "HTTP" in "HTTPServer"
+            let case_change = (previous.is_lowercase() && c.is_uppercase())
+                || (previous.is_uppercase() && c.is_uppercase() && next.is_some_and(char::is_lowercase));
+            if case_change {
+                parts.push(part_start..byte);
+                start = Some(byte);
+            }
+        } else {
+            start = Some(byte);
+        }
+    }
+    
+    if let Some(part_start) = start {
+        parts.push(part_start..identifier.len());
+    }
+    parts
+}
+
+/// Whether `c` is a Chinese or Japanese character. Hangul is not included:
+/// Korean separates words with spaces.
+pub fn is_cjk_char(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{309F}' // Hiragana
+        | '\u{30A0}'..='\u{30FF}' // Katakana
+        | '\u{3400}'..='\u{4DBF}' // CJK extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK unified ideographs
+        | '\u{F900}'..='\u{FAFF}' // CJK compatibility ideographs
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn kinds(language: Language, text: &str) -> Vec<(&str, TokenKind)> {
+        UnicodeTokenizer::for_language(language)
+            .tokenize(text)
+            .into_iter()
+            .map(|token| (token.text, token.kind))
+            .collect()
+    }
+    
+    #[test]
+    fn abbreviations_are_letters_and_periods_or_known() {
+        assert_eq!(kinds(Language::English, "e.g. the U.S. and Dr. Who"), [
+            ("e.g", TokenKind::Abbreviation),
+            ("the", TokenKind::Word),
+            ("U.S", TokenKind::Abbreviation),
+            ("and", TokenKind::Word),
+            ("Dr", TokenKind::Abbreviation),
+            ("Who", TokenKind::Word),
+        ]);
+        assert_eq!(kinds(Language::German, "z.B. hier"), [("z.B", TokenKind::Abbreviation), ("hier", TokenKind::Word)]);
+    }
+    
+    #[test]
+    fn words_joined_by_a_period_are_words() {
+        assert_eq!(kinds(Language::English, "is.it"), [("is.it", TokenKind::Word)]);
+        assert_eq!(kinds(Language::English, "self.name"), [("self", TokenKind::Word), ("name", TokenKind::Word)]);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::prose::{string_value_spans, ProseSpan};
+use crate::selector::{PathSegment, ValueFilter};
+use std::ops::Range;
+
+/// The prose in a TOML document: the string values picked by `filter`.
+///
+/// Keys, table headers, comments and values that aren't strings are left
+/// out, as are values that look like keys, paths or URLs, and placeholders
+/// inside values. Each `[[array]]` table is a new index of its path.
+pub fn prose_spans(text: &str, filter: &ValueFilter) -> Vec<ProseSpan> {
+    let mut scan = Scan {
+        text,
+        pos: 0,
+        filter,
+        table: Vec::new(),
+        array_tables: Vec::new(),
+        spans: Vec::new(),
+    };
+    loop {
+        scan.skip_space(true);
+        if scan.pos >= text.len() {
+            break;
+        }
+        let before = scan.pos;
+        if scan.rest().starts_with('[') {
+            scan.header();
+        } else {
+            let path = scan.table.clone();
+            scan.key_value(path);
+        }
+        // Junk the scan didn't understand
+        if scan.pos == before {
+            scan.skip_line();
+        }
+    }
+    scan.spans
+}
+
+struct Scan<'a> {
+    text: &'a str,
+    pos: usize,
+    filter: &'a ValueFilter,
+    // The path of the current table
+    table: Vec<PathSegment>,
+    // The keys of every `[[array]]` table and how many it has had
+    array_tables: Vec<(Vec<String>, usize)>,
+    spans: Vec<ProseSpan>,
+}
+
+impl<'a> Scan<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+    
+    // `[table]` or `[[array]]`
+    fn header(&mut self) {
+        let is_array = self.rest().starts_with("[[");
+        self.pos += if is_array { 2 } else { 1 };
+        let keys = self.keys();
+        let close = if is_array { "]]" } else { "]" };
+        if self.rest().starts_with(close) {
+            self.pos += close.len();
+        }
+        
+        if is_array {
+            match self.array_tables.iter_mut().find(|(table, _)| *table == keys) {
+                Some((_, count)) => *count += 1,
+                None => self.array_tables.push((keys.clone(), 1)),
+            }
+        }
+        
+        // Keys of array tables take the index of their latest table
+        self.table.clear();
+        for i in 0..keys.len() {
+            self.table.push(PathSegment::Key(keys[i].clone()));
+            if let Some((_, count)) = self.array_tables.iter().find(|(table, _)| *table == keys[..=i]) {
+                self.table.push(PathSegment::Index(count - 1));
+            }
+        }
+        self.skip_line();
+    }
+    
+    // `key = value`, below `path`
+    fn key_value(&mut self, mut path: Vec<PathSegment>) {
+        let keys = self.keys();
+        if keys.is_empty() || !self.rest().starts_with('=') {
+            return;
+        }
+        self.pos += 1;
+        path.extend(keys.into_iter().map(PathSegment::Key));
+        self.value(&mut path);
+    }
+    
+    // A dotted key, leaving `pos` after the space following it
+    fn keys(&mut self) -> Vec<String> {
+        let mut keys = Vec::new();
+        loop {
+            self.skip_space(false);
+            let key = match self.rest().chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let range = self.string(quote, false);
+                    self.text[range].to_string()
+                }
+                _ => {
+                    let rest = self.rest();
+                    let len = rest
+                        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+                        .unwrap_or(rest.len());
+                    self.pos += len;
+                    rest[..len].to_string()
+                }
+            };
+            if key.is_empty() && !self.text[..self.pos].ends_with(['"', '\'']) {
+                return keys;
+            }
+            keys.push(key);
+            self.skip_space(false);
+            if !self.rest().starts_with('.') {
+                return keys;
+            }
+            self.pos += 1;
+        }
+    }
+    
+    // The value at `pos`, at `path`
+    fn value(&mut self, path: &mut Vec<PathSegment>) {
+        self.skip_space(false);
+        let rest = self.rest();
+        if let Some(quote) = ["\"\"\"", "'''"].into_iter().find(|quote| rest.starts_with(quote)) {
+            let range = self.string(quote.chars().next().unwrap(), true);
+            self.push_value(path, range);
+        } else if let Some(quote @ ('"' | '\'')) = rest.chars().next() {
+            let range = self.string(quote, false);
+            self.push_value(path, range);
+        } else if rest.starts_with('[') {
+            self.pos += 1;
+            let mut index = 0;
+            loop {
+                self.skip_space(true);
+                match self.rest().chars().next() {
+                    None => return,
+                    Some(']') => {
+                        self.pos += 1;
+                        return;
+                    }
+                    Some(',') => {
+                        self.pos += 1;
+                        continue;
+                    }
+                    _ => {}
+                }
+                let before = self.pos;
+                path.push(PathSegment::Index(index));
+                self.value(path);
+                path.pop();
+                index += 1;
+                if self.pos == before {
+                    self.pos += self.rest().chars().next().map_or(1, char::len_utf8);
+                }
+            }
+        } else if rest.starts_with('{') {
+            self.pos += 1;
+            loop {
+                self.skip_space(false);
+                match self.rest().chars().next() {
+                    None | Some('\n') => return,
+                    Some('}') => {
+                        self.pos += 1;
+                        return;
+                    }
+                    Some(',') => {
+                        self.pos += 1;
+                        continue;
+                    }
+                    _ => {}
+                }
+                let before = self.pos;
+                self.key_value(path.clone());
+                if self.pos == before {
+                    self.pos += self.rest().chars().next().map_or(1, char::len_utf8);
+                }
+            }
+        } else {
+            // Numbers, booleans and dates
+            let len = rest
+                .find([',', ']', '}', '#', '\n'])
+                .unwrap_or(rest.len());
+            self.pos += len;
+        }
+    }
+    
+    fn push_value(&mut self, path: &[PathSegment], range: Range<usize>) {
+        if self.filter.checks(path) {
+            self.spans.extend(string_value_spans(self.text, range));
+        }
+    }
+    
+    // The content of the string whose quote is at `pos`, moving past it.
+    // Literal strings (') have no escapes.
+    fn string(&mut self, quote: char, multiline: bool) -> Range<usize> {
+        let bytes = self.text.as_bytes();
+        let quote = quote as u8;
+        let quote_len = if multiline { 3 } else { 1 };
+        let start = self.pos + quote_len;
+        let mut i = start;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' if quote == b'"' => i += 1,
+                b'\n' if !multiline => break,
+                c if c == quote && (!multiline || bytes[i..].starts_with(&[quote; 3])) => {
+                    // Up to two quotes may end the content: """a "quote"""""
+                    let mut end = i;
+                    if multiline {
+                        while bytes.get(end + 3) == Some(&quote) && end < i + 2 {
+                            end += 1;
+                        }
+                    }
+                    self.pos = end + quote_len;
+                    return start..end;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        let end = i.min(bytes.len());
+        self.pos = end;
+        start..end
+    }
+    
+    // Whitespace and comments, with line breaks when `newlines`
+    fn skip_space(&mut self, newlines: bool) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start_matches(|c: char| c == ' ' || c == '\t' || (newlines && (c == '\n' || c == '\r')));
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with('#') {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else {
+                return;
+            }
+        }
+    }
+    
+    fn skip_line(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.find('\n').map_or(rest.len(), |end| end + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn prose<'a>(text: &'a str, filter: &ValueFilter) -> Vec<&'a str> {
+        prose_spans(text, filter)
+            .into_iter()
+            .map(|span| &text[span.range])
+            .collect()
+    }
+    
+    #[test]
+    fn string_values_are_prose() {
+        let text = "# A comment\ntitle = \"Hello world\"\ncount = 3\n\n[package]\ndescription = 'Checks spelling'\n";
+        assert_eq!(prose(text, &ValueFilter::default()), ["Hello world", "Checks spelling"]);
+    }
+    
+    #[test]
+    fn filter_follows_tables() {
+        let text = "[messages]\nhi = \"Hello\"\n\n[meta]\nauthor = \"Someone\"\n";
+        let filter = ValueFilter::new(&["$.messages".to_string()], &[]);
+        assert_eq!(prose(text, &filter), ["Hello"]);
+    }
+}
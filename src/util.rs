@@ -1,22 +1,9 @@
+use crate::tokenizer::{TokenKind, Tokenizer, UnicodeTokenizer};
 use regex::Regex;
 use std::collections::HashMap;
 use once_cell::sync::Lazy;
 
-// Compile regex only once for better performance
-pub static WORD_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"\b[\p{L}][\p{L}'-]*\b").unwrap()
-});
-
-// New regex for CJK languages (Chinese, Japanese, Korean)
-pub static CJK_WORD_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"[\p{Han}\p{Hiragana}\p{Katakana}\p{Hangul}]+|[\p{L}][\p{L}'-]*").unwrap()
-});
-
-// Regex for programming languages (ignores common code patterns)
-pub static CODE_WORD_REGEX: Lazy<Regex> = Lazy::new(|| {
-    // Match words but ignore common programming patterns
-    Regex::new(r"\b([a-zA-Z][a-zA-Z'-]{2,})\b").unwrap()
-});
+static TOKENIZER: Lazy<UnicodeTokenizer> = Lazy::new(UnicodeTokenizer::default);
 
 // Regex to identify code-specific patterns to ignore
 static CODE_IGNORE_REGEX: Lazy<Regex> = Lazy::new(|| {
@@ -46,31 +33,29 @@ static CODE_KEYWORDS: Lazy<Vec<&'static str>> = Lazy::new(|| {
 
 /// Extract words from text based on language and context
 pub fn extract_words(text: &str, is_cjk: bool, is_code: bool) -> Vec<String> {
+    let words = TOKENIZER
+        .tokenize(text)
+        .into_iter()
+        .filter(|token| token.kind == TokenKind::Word)
+        .map(|token| token.text);
+    
     if is_cjk {
-        CJK_WORD_REGEX
-            .find_iter(text)
-            .map(|mat| mat.as_str().to_string())
-            .collect()
+        words.map(String::from).collect()
     } else if is_code {
         // For code, we want to be more selective
-        CODE_WORD_REGEX
-            .find_iter(text)
-            .map(|mat| mat.as_str())
+        words
             .filter(|word| {
                 // Filter out common programming constructs
                 !CODE_IGNORE_REGEX.is_match(word) &&
                 !is_code_keyword(word) &&
-                word.len() > 2 && // Ignore very short words
+                word.chars().count() > 2 && // Ignore very short words
                 !is_likely_code_symbol(word) &&
                 !is_common_code_pattern(word)
             })
             .map(|word| word.to_lowercase())
             .collect()
     } else {
-        WORD_REGEX
-            .find_iter(text)
-            .map(|mat| mat.as_str().to_lowercase())
-            .collect()
+        words.map(str::to_lowercase).collect()
     }
 }
 
@@ -172,13 +157,59 @@ pub fn is_valid_word(word: &str) -> bool {
     trimmed.len() >= 2
 }
 
+/// Strip accents from Latin letters ("été" -> "ete", "naïef" -> "naief").
+/// Ligatures are spelled out ("cœur" -> "coeur"); everything else is kept.
+pub fn fold_diacritics(word: &str) -> String {
+    let mut folded = String::with_capacity(word.len());
+    for c in word.chars() {
+        match fold_char(c) {
+            Some(base) => folded.push_str(base),
+            None => folded.push(c),
+        }
+    }
+    folded
+}
+
+/// Whether `word` has any letter `fold_diacritics` would change
+pub fn has_diacritics(word: &str) -> bool {
+    word.chars().any(|c| fold_char(c).is_some())
+}
+
+fn fold_char(c: char) -> Option<&'static str> {
+    let base = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' => "a",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' => "A",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' => "e",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ę' => "E",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' => "i",
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ī' => "I",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' => "o",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' => "O",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' => "u",
+        'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ū' => "U",
+        'ý' | 'ÿ' => "y",
+        'Ý' | 'Ÿ' => "Y",
+        'ç' => "c",
+        'Ç' => "C",
+        'ñ' => "n",
+        'Ñ' => "N",
+        'œ' => "oe",
+        'Œ' => "OE",
+        'æ' => "ae",
+        'Æ' => "AE",
+        'ß' => "ss",
+        _ => return None,
+    };
+    Some(base)
+}
+
 /// Check if text contains CJK characters
 pub fn is_cjk_text(text: &str) -> bool {
     text.chars().any(|c| {
-        ('\u{4E00}' <= c && c <= '\u{9FFF}') || // Chinese
-        ('\u{3040}' <= c && c <= '\u{309F}') || // Hiragana
-        ('\u{30A0}' <= c && c <= '\u{30FF}') || // Katakana
-        ('\u{AC00}' <= c && c <= '\u{D7AF}')    // Hangul
+        ('\u{4E00}'..='\u{9FFF}').contains(&c) || // Chinese
+        ('\u{3040}'..='\u{309F}').contains(&c) || // Hiragana
+        ('\u{30A0}'..='\u{30FF}').contains(&c) || // Katakana
+        ('\u{AC00}'..='\u{D7AF}').contains(&c)    // Hangul
     })
 }
 
@@ -225,8 +256,7 @@ pub fn is_code_file(filename: &str) -> bool {
             "go" | "rb" | "php" | "cs" | "swift" | "kt" | "scala" | "hs" | "lua" |
             "pl" | "r" | "m" | "f" | "f90" | "f95" | "f03" | "f08" | "v" | "sv" |
             "vhd" | "vhdl" | "asm" | "s" | "sh" | "bash" | "zsh" | "fish" |
-            "ps1" | "bat" | "cmd" | "yml" | "yaml" | "toml" | "json" | "xml" | "html" |
-            "htm" | "css" | "scss" | "less" | "md" | "markdown" | "tex" | "bib"
+            "ps1" | "bat" | "cmd" | "css" | "scss" | "less" | "bib"
         )
     } else {
         false
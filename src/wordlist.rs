@@ -0,0 +1,286 @@
+use fst::automaton::{Levenshtein, Str};
+use fst::{Automaton, IntoStreamer, Map, MapBuilder, Streamer};
+use memmap2::Mmap;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Backing storage for the FST: either built in memory or a slice of a
+/// memory-mapped cache file. Both are cheap to clone.
+#[derive(Clone)]
+enum WordBytes {
+    Owned(Arc<Vec<u8>>),
+    Mapped(Arc<Mmap>, Range<usize>),
+}
+
+impl AsRef<[u8]> for WordBytes {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            WordBytes::Owned(bytes) => bytes,
+            WordBytes::Mapped(mmap, range) => &mmap[range.clone()],
+        }
+    }
+}
+
+/// Immutable, compact word list backed by a finite-state transducer.
+///
+/// Shared words are stored once as an FST (prefixes and suffixes are
+/// deduplicated), which is far smaller than a `HashSet<String>` and still
+/// supports exact lookups, prefix enumeration and fuzzy traversal. Each word
+/// carries a frequency count, 0 when the source didn't give one.
+#[derive(Clone)]
+pub struct WordList {
+    map: Map<WordBytes>,
+}
+
+impl WordList {
+    pub fn empty() -> Self {
+        Self::from_words(std::iter::empty::<String>()).expect("empty FST always builds")
+    }
+    
+    /// Build a word list from any collection of words (order and duplicates don't matter)
+    pub fn from_words<I, S>(words: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::from_counts(words.into_iter().map(|word| (word, 0)))
+    }
+    
+    /// Build a word list with frequency counts. A word listed more than once
+    /// keeps its highest count.
+    pub fn from_counts<I, S>(counts: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = (S, u64)>,
+        S: Into<String>,
+    {
+        let mut counts: Vec<(String, u64)> = counts.into_iter().map(|(word, count)| (word.into(), count)).collect();
+        // Highest count first within each word, so dedup keeps it
+        counts.sort_unstable_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+        counts.dedup_by(|a, b| a.0 == b.0);
+        
+        let bytes = Map::from_iter(counts)?.into_fst().into_inner();
+        Ok(Self { map: Map::new(WordBytes::Owned(Arc::new(bytes)))? })
+    }
+    
+    /// Build a word list from words already in lexicographic order, without
+    /// duplicates. Nothing is collected first, so the words can be streamed.
+    pub fn from_sorted<'a>(words: impl IntoIterator<Item = &'a str>) -> anyhow::Result<Self> {
+        let bytes = Map::from_iter(words.into_iter().map(|word| (word, 0)))?.into_fst().into_inner();
+        Ok(Self { map: Map::new(WordBytes::Owned(Arc::new(bytes)))? })
+    }
+    
+    /// Use a range of a memory-mapped file as the word list without copying it.
+    /// The FST checksum is verified first, since a truncated or corrupt file
+    /// can otherwise pass the format checks and panic on lookup.
+    pub fn from_mapped(mmap: Arc<Mmap>, range: Range<usize>) -> anyhow::Result<Self> {
+        if range.end > mmap.len() || range.start > range.end {
+            anyhow::bail!("word list range {:?} is outside the mapped file", range);
+        }
+        let map = Map::new(WordBytes::Mapped(mmap, range))?;
+        map.as_fst().verify()?;
+        Ok(Self { map })
+    }
+    
+    /// Raw FST bytes, suitable for writing to a cache file
+    pub fn as_bytes(&self) -> &[u8] {
+        self.map.as_fst().as_bytes()
+    }
+    
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+    
+    pub fn contains(&self, word: &str) -> bool {
+        self.map.contains_key(word)
+    }
+    
+    /// How often `word` occurs according to the source, 0 if unknown
+    pub fn frequency(&self, word: &str) -> u64 {
+        self.map.get(word).unwrap_or(0)
+    }
+    
+    /// The highest frequency of any word
+    pub fn max_frequency(&self) -> u64 {
+        let mut stream = self.map.values().into_stream();
+        let mut max = 0;
+        while let Some(count) = stream.next() {
+            max = max.max(count);
+        }
+        max
+    }
+    
+    /// All words in lexicographic order
+    pub fn words(&self) -> Vec<String> {
+        collect_strings(self.map.stream(), usize::MAX)
+    }
+    
+    /// All words with their frequencies, in lexicographic order
+    pub fn counts(&self) -> Vec<(String, u64)> {
+        let mut stream = self.map.stream();
+        let mut counts = Vec::new();
+        while let Some((key, count)) = stream.next() {
+            if let Ok(word) = std::str::from_utf8(key) {
+                counts.push((word.to_string(), count));
+            }
+        }
+        counts
+    }
+    
+    /// Words starting with `prefix`, in lexicographic order
+    pub fn with_prefix(&self, prefix: &str, limit: usize) -> Vec<String> {
+        let matcher = Str::new(prefix).starts_with();
+        collect_strings(self.map.search(matcher).into_stream(), limit)
+    }
+    
+    /// Words within `distance` edits of `word`, found by walking the FST with
+    /// a Levenshtein automaton instead of scanning every entry
+    pub fn fuzzy(&self, word: &str, distance: u32) -> Vec<String> {
+        match Levenshtein::new(word, distance) {
+            Ok(matcher) => collect_strings(self.map.search(matcher).into_stream(), usize::MAX),
+            // The automaton refuses to build for very long inputs
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// Merge two lists into a new one, walking both FSTs side by side. A word
+    /// in both keeps its highest count.
+    pub fn merge(&self, other: &WordList) -> anyhow::Result<Self> {
+        let mut builder = MapBuilder::memory();
+        let mut stream = self.map.op().add(&other.map).union();
+        while let Some((word, counts)) = stream.next() {
+            let count = counts.iter().map(|indexed| indexed.value).max().unwrap_or(0);
+            builder.insert(word, count)?;
+        }
+        
+        let bytes = builder.into_inner()?;
+        Ok(Self { map: Map::new(WordBytes::Owned(Arc::new(bytes)))? })
+    }
+    
+    /// Merge another set of words into a new list, keeping existing counts
+    pub fn union<I, S>(&self, words: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.union_counts(words.into_iter().map(|word| (word, 0)))
+    }
+    
+    /// Merge words with frequency counts into a new list
+    pub fn union_counts<I, S>(&self, counts: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = (S, u64)>,
+        S: Into<String>,
+    {
+        Self::from_counts(
+            self.counts()
+                .into_iter()
+                .chain(counts.into_iter().map(|(word, count)| (word.into(), count))),
+        )
+    }
+}
+
+impl Default for WordList {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl fmt::Debug for WordList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WordList").field("len", &self.len()).finish()
+    }
+}
+
+fn collect_strings<S>(mut stream: S, limit: usize) -> Vec<String>
+where
+    S: for<'a> Streamer<'a, Item = (&'a [u8], u64)>,
+{
+    let mut words = Vec::new();
+    while let Some((key, _)) = stream.next() {
+        if words.len() >= limit {
+            break;
+        }
+        if let Ok(word) = std::str::from_utf8(key) {
+            words.push(word.to_string());
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn words_keep_their_highest_count() {
+        let words = WordList::from_counts([("house", 3), ("mouse", 0), ("house", 7)]).unwrap();
+        
+        assert_eq!(words.len(), 2);
+        assert!(words.contains("house"));
+        assert!(!words.contains("hous"));
+        assert_eq!(words.frequency("house"), 7);
+        assert_eq!(words.frequency("mouse"), 0);
+        assert_eq!(words.frequency("louse"), 0);
+        assert_eq!(words.max_frequency(), 7);
+    }
+    
+    #[test]
+    fn union_builds_a_new_list() {
+        let words = WordList::from_counts([("house", 5)]).unwrap();
+        let merged = words.union_counts([("mouse", 2), ("house", 1)]).unwrap();
+        
+        assert!(!words.contains("mouse"));
+        assert_eq!(merged.counts(), [("house".to_string(), 5), ("mouse".to_string(), 2)]);
+        
+        let merged = merged.union(["louse"]).unwrap();
+        assert_eq!(merged.words(), ["house", "louse", "mouse"]);
+        assert_eq!(merged.frequency("house"), 5);
+    }
+    
+    #[test]
+    fn merge_walks_both_lists() {
+        let words = WordList::from_counts([("house", 5), ("mouse", 2)]).unwrap();
+        let forms = WordList::from_sorted(["house", "houses", "mice"]).unwrap();
+        let merged = words.merge(&forms).unwrap();
+        
+        assert_eq!(merged.words(), ["house", "houses", "mice", "mouse"]);
+        assert_eq!(merged.frequency("house"), 5);
+        assert_eq!(merged.frequency("mouse"), 2);
+        assert!(WordList::from_sorted(["mouse", "house"]).is_err());
+    }
+    
+    #[test]
+    fn prefix_and_fuzzy_walks() {
+        let words = WordList::from_words(["huis", "huisie", "huise", "muis", "hond"]).unwrap();
+        
+        assert_eq!(words.with_prefix("huis", 10), ["huis", "huise", "huisie"]);
+        assert_eq!(words.with_prefix("huis", 2), ["huis", "huise"]);
+        assert!(words.with_prefix("kat", 10).is_empty());
+        assert_eq!(words.fuzzy("huis", 1), ["huis", "huise", "muis"]);
+        assert_eq!(words.fuzzy("hiis", 0), Vec::<String>::new());
+    }
+    
+    #[test]
+    fn non_ascii_words() {
+        let words = WordList::from_counts([("geëet", 4), ("naïef", 1)]).unwrap();
+        
+        assert!(words.contains("geëet"));
+        assert!(!words.contains("geeet"));
+        assert_eq!(words.fuzzy("geeet", 1), ["geëet"]);
+        assert_eq!(words.with_prefix("na", 10), ["naïef"]);
+    }
+    
+    #[test]
+    fn bytes_read_back_as_the_same_list() {
+        let words = WordList::from_counts([("house", 5), ("mouse", 2)]).unwrap();
+        let copy = WordList { map: Map::new(WordBytes::Owned(Arc::new(words.as_bytes().to_vec()))).unwrap() };
+        
+        assert_eq!(copy.counts(), words.counts());
+        assert!(WordList::empty().is_empty());
+    }
+}
@@ -0,0 +1,246 @@
+use crate::prose::{lines, string_value_spans, ProseSpan};
+use crate::selector::{PathSegment, ValueFilter};
+use std::ops::Range;
+
+/// The prose in a YAML document: the scalar values picked by `filter`,
+/// including block scalars (`|`, `>`) and plain values running over
+/// several lines.
+///
+/// Keys, comments, anchors, tags, aliases and flow collections (`[a, b]`,
+/// `{a: b}`) are left out, as are values that look like keys, paths or
+/// URLs, and placeholders inside values.
+pub fn prose_spans(text: &str, filter: &ValueFilter) -> Vec<ProseSpan> {
+    let mut scan = Scan {
+        text,
+        filter,
+        stack: Vec::new(),
+        block: None,
+        continued: None,
+        spans: Vec::new(),
+    };
+    for (line_start, line) in lines(text) {
+        scan.line(line_start, line);
+    }
+    scan.spans
+}
+
+struct Scan<'a> {
+    text: &'a str,
+    filter: &'a ValueFilter,
+    // (column, segment) of every key and sequence item the line is in
+    stack: Vec<(usize, PathSegment)>,
+    // The column of the key owning the block scalar being read, and
+    // whether it is checked
+    block: Option<(usize, bool)>,
+    // The same for a plain value that may go on over the next lines
+    continued: Option<(usize, bool)>,
+    spans: Vec<ProseSpan>,
+}
+
+impl Scan<'_> {
+    fn line(&mut self, line_start: usize, line: &str) {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        let content_start = line_start + indent;
+        
+        if let Some((column, checked)) = self.block {
+            if trimmed.is_empty() {
+                return;
+            }
+            if indent > column {
+                if checked {
+                    self.push_text(content_start..line_start + line.trim_end().len());
+                }
+                return;
+            }
+            self.block = None;
+        }
+        
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            return;
+        }
+        if let Some((column, checked)) = self.continued {
+            if indent > column {
+                if checked {
+                    let value = without_comment(trimmed);
+                    self.push_text(content_start..content_start + value.len());
+                }
+                return;
+            }
+            self.continued = None;
+        }
+        
+        // Directives ("%YAML 1.2") and document markers
+        if trimmed.starts_with('%') {
+            self.stack.clear();
+            return;
+        }
+        if trimmed.starts_with("---") || trimmed.starts_with("...") {
+            self.stack.clear();
+            // A document can start on the marker line: "--- |"
+            let rest = trimmed[3..].trim_start();
+            if trimmed.starts_with("---") && !rest.is_empty() {
+                self.value(line_start + line.len() - rest.len(), rest, indent);
+            }
+            return;
+        }
+        
+        self.node(content_start, trimmed, indent, indent);
+    }
+    
+    // A sequence item or mapping entry starting at `start`, in `column`.
+    // A scalar there belongs to the key or item in `owner`.
+    fn node(&mut self, start: usize, content: &str, column: usize, owner: usize) {
+        if content == "-" || content.starts_with("- ") {
+            // Sibling items share a column; the previous one is still open
+            let mut index = 0;
+            while let Some((open, segment)) = self.stack.last() {
+                if *open < column {
+                    break;
+                }
+                if *open == column {
+                    if let PathSegment::Index(previous) = segment {
+                        index = previous + 1;
+                        self.stack.pop();
+                    }
+                    break;
+                }
+                self.stack.pop();
+            }
+            self.stack.push((column, PathSegment::Index(index)));
+            
+            let item = content[1..].trim_start();
+            let offset = content.len() - item.len();
+            if !item.is_empty() {
+                self.node(start + offset, item, column + offset, column);
+            }
+            return;
+        }
+        
+        match entry(content) {
+            Some((key, value_offset)) => {
+                while self.stack.last().is_some_and(|(open, _)| *open >= column) {
+                    self.stack.pop();
+                }
+                self.stack.push((column, PathSegment::Key(key)));
+                let value = content[value_offset..].trim_start();
+                let value_start = start + content.len() - value.len();
+                self.value(value_start, value, column);
+            }
+            // A scalar on its own line, such as a sequence item
+            None => self.value(start, content, owner),
+        }
+    }
+    
+    // The value at `start`, belonging to the key or item in `column`
+    fn value(&mut self, start: usize, value: &str, column: usize) {
+        let mut value = value;
+        let mut start = start;
+        // Anchors and tags come before the value itself
+        while value.starts_with(['&', '!']) {
+            let len = value.find(char::is_whitespace).unwrap_or(value.len());
+            let rest = value[len..].trim_start();
+            start += value.len() - rest.len();
+            value = rest;
+        }
+        
+        let checked = self.filter.checks(&self.path());
+        match value.chars().next() {
+            None | Some('#') => {}
+            // Aliases and flow collections
+            Some('*' | '[' | '{') => {}
+            Some('|' | '>') => self.block = Some((column, checked)),
+            Some(quote @ ('"' | '\'')) => {
+                if checked {
+                    let content = &value[1..];
+                    let len = closing_quote(content, quote).unwrap_or(content.len());
+                    self.push_text(start + 1..start + 1 + len);
+                }
+            }
+            Some(_) => {
+                if checked {
+                    let value = without_comment(value);
+                    self.push_text(start..start + value.len());
+                }
+                self.continued = Some((column, checked));
+            }
+        }
+    }
+    
+    fn path(&self) -> Vec<PathSegment> {
+        self.stack.iter().map(|(_, segment)| segment.clone()).collect()
+    }
+    
+    fn push_text(&mut self, range: Range<usize>) {
+        self.spans.extend(string_value_spans(self.text, range));
+    }
+}
+
+// The key of a "key: value" entry and where its value starts
+fn entry(content: &str) -> Option<(String, usize)> {
+    if let Some(quote @ ('"' | '\'')) = content.chars().next() {
+        let len = closing_quote(&content[1..], quote)?;
+        let after = &content[len + 2..];
+        let colon = after.len() - after.trim_start().len();
+        return after[colon..]
+            .starts_with(':')
+            .then(|| (content[1..len + 1].to_string(), len + 2 + colon + 1));
+    }
+    
+    let colon = content
+        .match_indices(':')
+        .map(|(i, _)| i)
+        .find(|&i| content[i + 1..].is_empty() || content[i + 1..].starts_with([' ', '\t']))?;
+    let key = content[..colon].trim_end();
+    (!key.is_empty() && !key.contains(" #")).then(|| (key.to_string(), colon + 1))
+}
+
+// The length of a quoted string's content, given what follows the opening
+// quote. A single-quoted string doubles its quotes ("don''t").
+fn closing_quote(content: &str, quote: char) -> Option<usize> {
+    let bytes = content.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if quote == '"' => i += 1,
+            b'\'' if quote == '\'' && bytes.get(i + 1) == Some(&b'\'') => i += 1,
+            c if c == quote as u8 => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// A plain value without its trailing comment and whitespace
+fn without_comment(value: &str) -> &str {
+    let end = value.find(" #").or_else(|| value.find("\t#")).unwrap_or(value.len());
+    value[..end].trim_end()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn prose(text: &str) -> Vec<&str> {
+        prose_spans(text, &ValueFilter::default())
+            .into_iter()
+            .map(|span| &text[span.range])
+            .collect()
+    }
+    
+    #[test]
+    fn directive_with_multibyte_text() {
+        assert_eq!(prose("key: value\n%a\u{e9}"), ["value"]);
+        assert_eq!(prose("%\u{e9}\u{e9}\nkey: value"), ["value"]);
+    }
+    
+    #[test]
+    fn keys_comments_and_filtered_values_are_left_out() {
+        let text = "# A comment\ntitle: Hello world # trailing\nmeta:\n  author: Someone\n";
+        assert_eq!(prose(text), ["Hello world", "Someone"]);
+        let filter = ValueFilter::new(&[], &["$.meta".to_string()]);
+        let spans: Vec<&str> = prose_spans(text, &filter).into_iter().map(|span| &text[span.range]).collect();
+        assert_eq!(spans, ["Hello world"]);
+    }
+}